@@ -0,0 +1,4 @@
+ALTER TABLE users DROP COLUMN IF EXISTS updated_at;
+UPDATE users SET role = '' WHERE role IS NULL;
+ALTER TABLE users ALTER COLUMN role SET NOT NULL;
+ALTER TABLE users ALTER COLUMN id SET DEFAULT gen_random_uuid();
@@ -0,0 +1,5 @@
+-- users mirrors Keycloak identities: id is the Keycloak user id, and
+-- accounts may be created before they are given an EHR role
+ALTER TABLE users ALTER COLUMN id DROP DEFAULT;
+ALTER TABLE users ALTER COLUMN role DROP NOT NULL;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
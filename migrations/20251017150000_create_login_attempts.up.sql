@@ -0,0 +1,11 @@
+-- Recent failed logins per account (scope 'account', key = lowercased email) and per
+-- source address (scope 'ip'). lockouts counts consecutive lockouts for the back-off.
+CREATE TABLE IF NOT EXISTS login_attempts (
+    scope TEXT NOT NULL,
+    key TEXT NOT NULL,
+    failures INTEGER NOT NULL DEFAULT 0,
+    lockouts INTEGER NOT NULL DEFAULT 0,
+    last_failure_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until TIMESTAMPTZ,
+    PRIMARY KEY (scope, key)
+);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS web_sessions;
+DROP TABLE IF EXISTS oidc_authorizations;
@@ -0,0 +1,24 @@
+-- Browser sign-ins waiting for the authorization callback, keyed by the state value
+-- sent through the browser. Each row is redeemed once and only for a few minutes.
+CREATE TABLE IF NOT EXISTS oidc_authorizations (
+    state TEXT PRIMARY KEY,
+    nonce TEXT NOT NULL,
+    code_verifier TEXT NOT NULL,
+    return_to TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Sessions held by browsers in the session cookie. Only a hash of the cookie value is
+-- stored; the provider tokens stay server side.
+CREATE TABLE IF NOT EXISTS web_sessions (
+    id_hash TEXT PRIMARY KEY,
+    user_id UUID NOT NULL,
+    provider_session_id TEXT,
+    access_token TEXT NOT NULL,
+    refresh_token TEXT NOT NULL,
+    ip TEXT,
+    user_agent TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS web_sessions_user_id_idx ON web_sessions (user_id);
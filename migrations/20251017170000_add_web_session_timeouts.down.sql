@@ -0,0 +1,3 @@
+ALTER TABLE web_sessions
+    DROP COLUMN IF EXISTS last_seen_at,
+    DROP COLUMN IF EXISTS csrf_token;
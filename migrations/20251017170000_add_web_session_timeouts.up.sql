@@ -0,0 +1,6 @@
+-- Sessions opened before CSRF tokens existed can't be used safely, so they are dropped
+DELETE FROM web_sessions;
+
+ALTER TABLE web_sessions
+    ADD COLUMN IF NOT EXISTS csrf_token TEXT NOT NULL,
+    ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
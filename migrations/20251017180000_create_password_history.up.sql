@@ -0,0 +1,10 @@
+-- Argon2 hashes of each user's most recent passwords, newest last, so a password change
+-- can refuse to go back to one of them. Trimmed to the policy's history size on every write.
+CREATE TABLE IF NOT EXISTS password_history (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    password_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS password_history_user_id_idx ON password_history (user_id);
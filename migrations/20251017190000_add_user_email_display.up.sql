@@ -0,0 +1,9 @@
+-- users.email now holds the canonical address (trimmed, lower-case ASCII domain, and a
+-- lower-case local part unless configured otherwise), so UNIQUE applies to that form.
+-- email_display keeps the address as the user typed it.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email_display TEXT;
+UPDATE users SET email_display = email WHERE email_display IS NULL;
+ALTER TABLE users ALTER COLUMN email_display SET NOT NULL;
+
+-- Rows written before canonicalisation; IDN domains are converted on the user's next update
+UPDATE users SET email = LOWER(BTRIM(email));
@@ -0,0 +1,18 @@
+-- Staff invitations. Only a hash of the emailed token is stored; a row is claimed by
+-- setting accepted_at, so each token works once and only before expires_at.
+CREATE TABLE IF NOT EXISTS invitations (
+    id UUID PRIMARY KEY,
+    token_hash TEXT NOT NULL UNIQUE,
+    email TEXT NOT NULL,
+    email_display TEXT NOT NULL,
+    role TEXT NOT NULL,
+    invited_by UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    accepted_at TIMESTAMPTZ,
+    accepted_user_id UUID
+);
+
+-- Inviting an address again replaces its pending invitation
+CREATE UNIQUE INDEX IF NOT EXISTS invitations_pending_email_idx
+    ON invitations (email) WHERE accepted_at IS NULL;
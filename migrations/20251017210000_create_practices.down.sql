@@ -0,0 +1,4 @@
+ALTER TABLE invitations DROP COLUMN IF EXISTS practice_id;
+DROP INDEX IF EXISTS users_practice_id_idx;
+ALTER TABLE users DROP COLUMN IF EXISTS practice_id;
+DROP TABLE IF EXISTS practices;
@@ -0,0 +1,13 @@
+-- Practices (clinics). Membership lives in Keycloak groups and is mirrored on the
+-- users row; a user outside any practice only ever sees other unassigned users.
+CREATE TABLE IF NOT EXISTS practices (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS practice_id UUID REFERENCES practices (id);
+CREATE INDEX IF NOT EXISTS users_practice_id_idx ON users (practice_id);
+
+-- Invited accounts join the practice of whoever invited them
+ALTER TABLE invitations ADD COLUMN IF NOT EXISTS practice_id UUID REFERENCES practices (id);
@@ -0,0 +1,16 @@
+-- Machine credentials for system integrations. Only a hash of the key is stored,
+-- next to a short prefix of it so owners can tell keys apart; scopes replace the
+-- role permissions a human caller would have.
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    prefix TEXT NOT NULL,
+    scopes TEXT[] NOT NULL,
+    practice_id UUID REFERENCES practices (id),
+    created_by UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
@@ -0,0 +1,2 @@
+-- Free-text justification an administrator gives, e.g. for disabling an account
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS reason TEXT;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS offboarding_steps;
+DROP TABLE IF EXISTS offboarding_jobs;
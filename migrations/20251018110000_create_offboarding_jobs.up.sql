@@ -0,0 +1,27 @@
+-- Staff offboarding runs as a job of ordered steps. Each step keeps its own status,
+-- so a job that stopped on a failed step resumes from there instead of starting over.
+CREATE TABLE IF NOT EXISTS offboarding_jobs (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    requested_by UUID,
+    reason TEXT NOT NULL,
+    reassign_to UUID,
+    status TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+-- A user has at most one unfinished job, which a second request resumes
+CREATE UNIQUE INDEX IF NOT EXISTS offboarding_jobs_unfinished_user_idx
+    ON offboarding_jobs (user_id) WHERE status <> 'completed';
+
+CREATE TABLE IF NOT EXISTS offboarding_steps (
+    job_id UUID NOT NULL REFERENCES offboarding_jobs (id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    step TEXT NOT NULL,
+    status TEXT NOT NULL,
+    detail TEXT,
+    attempts INT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (job_id, step)
+);
@@ -0,0 +1,11 @@
+-- Short-lived credentials handed out at login to users whose role requires MFA but
+-- who have no authenticator yet. They only reach the enrollment routes. Only token
+-- hashes are stored.
+CREATE TABLE IF NOT EXISTS mfa_enrollment_tokens (
+    token_hash TEXT PRIMARY KEY,
+    user_id UUID NOT NULL,
+    email TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS mfa_enrollment_tokens_user_id_idx ON mfa_enrollment_tokens (user_id);
@@ -0,0 +1 @@
+ALTER TABLE web_sessions DROP COLUMN IF EXISTS mfa_pending;
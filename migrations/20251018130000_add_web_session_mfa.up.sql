@@ -0,0 +1,3 @@
+-- Browser sign-ins wait here for the second factor before the session grants access
+ALTER TABLE web_sessions
+    ADD COLUMN IF NOT EXISTS mfa_pending BOOLEAN NOT NULL DEFAULT FALSE;
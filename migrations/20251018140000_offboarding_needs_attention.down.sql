@@ -0,0 +1,4 @@
+UPDATE offboarding_jobs SET status = 'completed' WHERE status = 'needs_attention';
+DROP INDEX IF EXISTS offboarding_jobs_unfinished_user_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS offboarding_jobs_unfinished_user_idx
+    ON offboarding_jobs (user_id) WHERE status <> 'completed';
@@ -0,0 +1,5 @@
+-- A job that finished with steps left to do by hand is "needs_attention", not
+-- "completed"; either way it is finished once completed_at is set
+DROP INDEX IF EXISTS offboarding_jobs_unfinished_user_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS offboarding_jobs_unfinished_user_idx
+    ON offboarding_jobs (user_id) WHERE completed_at IS NULL;
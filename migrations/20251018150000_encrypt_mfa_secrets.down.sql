@@ -0,0 +1,3 @@
+-- Encrypted secrets cannot be read without the key, so those factors are dropped
+DELETE FROM mfa_factors WHERE secret_nonce IS NOT NULL;
+ALTER TABLE mfa_factors DROP COLUMN IF EXISTS secret_nonce;
@@ -0,0 +1,4 @@
+-- TOTP secrets are encrypted with the MFA_SECRET_KEY setting: secret holds the
+-- base64 ciphertext and secret_nonce its AES-GCM nonce. Rows stored before this
+-- have no nonce and are encrypted when the server starts.
+ALTER TABLE mfa_factors ADD COLUMN IF NOT EXISTS secret_nonce BYTEA;
@@ -1,19 +1,60 @@
-use poem::web::Data;
+use poem::web::{Data, cookie::CookieJar};
 use poem_openapi::{
     OpenApi,
+    param::{Path, Query},
     payload::{Json, PlainText},
 };
 
 use crate::{
     domain::error::http_response::AppHttpResponse,
     routes::{
+        accept_invitation::{AcceptInvitationRequest, accept_invitation_impl},
+        assign_practice::{AssignPracticeRequest, assign_practice_impl},
+        authorize::authorize_impl,
+        callback::callback_impl,
+        change_password::{ChangePasswordRequest, change_password_impl},
+        confirm_totp::{ConfirmTotpRequest, confirm_totp_impl},
+        create_api_key::{CreateApiKeyRequest, create_api_key_impl},
+        create_invitation::{CreateInvitationRequest, create_invitation_impl},
+        create_practice::{CreatePracticeRequest, create_practice_impl},
         delete_user::{DeleteUserRequest, delete_user_impl},
+        disable_user::{DisableUserRequest, disable_user_impl},
+        enable_user::{EnableUserRequest, enable_user_impl},
+        enroll_totp::{EnrollTotpRequest, enroll_totp_impl},
+        forgot_password::{ForgotPasswordRequest, forgot_password_impl},
+        generate_recovery_codes::generate_recovery_codes_impl,
+        get_offboarding::get_offboarding_impl,
+        get_user::get_user_impl,
         get_user_id::{GetUserIdRequest, get_user_id_impl},
         health::health_check_impl,
+        list_api_keys::list_api_keys_impl,
+        list_mfa_factors::list_mfa_factors_impl,
+        list_practices::list_practices_impl,
+        list_user_sessions::list_user_sessions_impl,
+        list_users::{ListUsersParams, list_users_impl},
+        login::{LoginRequest, login_impl},
+        logout::{LogoutRequest, logout_impl},
+        logout_session::logout_session_impl,
+        offboard_user::{OffboardUserRequest, offboard_user_impl},
+        refresh::{RefreshRequest, refresh_impl},
+        remove_mfa_factor::{RemoveMfaFactorRequest, remove_mfa_factor_impl},
+        resend_verification::{ResendVerificationRequest, resend_verification_impl},
+        revoke_api_key::revoke_api_key_impl,
+        revoke_user_session::revoke_user_session_impl,
+        revoke_user_sessions::revoke_user_sessions_impl,
         signup::{SignupRequest, signup_impl},
+        unlock_user::unlock_user_impl,
+        update_user::{UpdateUserRequest, update_user_impl},
+        verify_mfa::{VerifyMfaRequest, verify_mfa_impl},
     },
     state::AppState,
-    utils::tracing::RequestContext,
+    utils::{
+        auth::{
+            require_delete_users, require_manage_api_keys, require_manage_practices,
+            require_manage_sessions, require_manage_users, require_read_users,
+        },
+        tracing::RequestContext,
+    },
 };
 
 #[derive(Debug)]
@@ -41,13 +82,179 @@ impl EHRApi {
         state: Data<&AppState>,
         payload: Json<SignupRequest>,
     ) -> AppHttpResponse {
-        match signup_impl(state, payload).await {
+        match signup_impl(&ctx, state, payload).await {
+            Ok(response) => AppHttpResponse::Created(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/invitations",
+        method = "post",
+        transform = "require_manage_users"
+    )]
+    #[tracing::instrument(name = "create_invitation", skip_all, fields(req_id=%ctx.request_id))]
+    async fn create_invitation(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        payload: Json<CreateInvitationRequest>,
+    ) -> AppHttpResponse {
+        match create_invitation_impl(&ctx, state, payload).await {
+            Ok(response) => AppHttpResponse::Created(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/invitations/accept", method = "post")]
+    #[tracing::instrument(name = "accept_invitation", skip_all, fields(req_id=%ctx.request_id))]
+    async fn accept_invitation(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        payload: Json<AcceptInvitationRequest>,
+    ) -> AppHttpResponse {
+        match accept_invitation_impl(&ctx, state, payload).await {
             Ok(response) => AppHttpResponse::Created(Json(response)),
             Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
         }
     }
 
-    #[oai(path = "/auth/get_user_id", method = "post")]
+    #[oai(path = "/auth/login", method = "post")]
+    #[tracing::instrument(name = "login", skip_all, fields(req_id=%ctx.request_id))]
+    async fn login(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        cookie_jar: &CookieJar,
+        payload: Json<LoginRequest>,
+    ) -> AppHttpResponse {
+        match login_impl(&ctx, state, cookie_jar, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/logout", method = "post")]
+    #[tracing::instrument(name = "logout", skip_all, fields(req_id=%ctx.request_id))]
+    async fn logout(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        payload: Json<LogoutRequest>,
+    ) -> AppHttpResponse {
+        match logout_impl(state, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/session", method = "delete")]
+    #[tracing::instrument(name = "logout_session", skip_all, fields(req_id=%ctx.request_id))]
+    async fn logout_session(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        cookie_jar: &CookieJar,
+    ) -> AppHttpResponse {
+        match logout_session_impl(state, cookie_jar).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/refresh", method = "post")]
+    #[tracing::instrument(name = "refresh", skip_all, fields(req_id=%ctx.request_id))]
+    async fn refresh(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        payload: Json<RefreshRequest>,
+    ) -> AppHttpResponse {
+        match refresh_impl(&ctx, state, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/authorize", method = "get")]
+    #[tracing::instrument(name = "authorize", skip_all, fields(req_id=%ctx.request_id))]
+    async fn authorize(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        return_to: Query<Option<String>>,
+    ) -> AppHttpResponse {
+        match authorize_impl(state, return_to).await {
+            Ok(location) => AppHttpResponse::Found(location),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/callback", method = "get")]
+    #[tracing::instrument(name = "callback", skip_all, fields(req_id=%ctx.request_id))]
+    async fn callback(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        cookie_jar: &CookieJar,
+        code: Query<Option<String>>,
+        #[oai(name = "state")] login_state: Query<String>,
+        error: Query<Option<String>>,
+    ) -> AppHttpResponse {
+        match callback_impl(&ctx, state, cookie_jar, code, login_state, error).await {
+            Ok(location) => AppHttpResponse::Found(location),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/password/forgot", method = "post")]
+    #[tracing::instrument(name = "forgot_password", skip_all, fields(req_id=%ctx.request_id))]
+    async fn forgot_password(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        payload: Json<ForgotPasswordRequest>,
+    ) -> AppHttpResponse {
+        match forgot_password_impl(state, payload).await {
+            Ok(response) => AppHttpResponse::Accepted(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/verify-email/resend", method = "post")]
+    #[tracing::instrument(name = "resend_verification", skip_all, fields(req_id=%ctx.request_id))]
+    async fn resend_verification(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        payload: Json<ResendVerificationRequest>,
+    ) -> AppHttpResponse {
+        match resend_verification_impl(state, payload).await {
+            Ok(response) => AppHttpResponse::Accepted(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/password/change", method = "post")]
+    #[tracing::instrument(name = "change_password", skip_all, fields(req_id=%ctx.request_id))]
+    async fn change_password(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        payload: Json<ChangePasswordRequest>,
+    ) -> AppHttpResponse {
+        match change_password_impl(&ctx, state, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/auth/get_user_id",
+        method = "post",
+        transform = "require_read_users"
+    )]
     #[tracing::instrument(name = "get_user_id", skip_all, fields(req_id=%ctx.request_id))]
     async fn get_user_id(
         &self,
@@ -55,13 +262,17 @@ impl EHRApi {
         state: Data<&AppState>,
         payload: Json<GetUserIdRequest>,
     ) -> AppHttpResponse {
-        match get_user_id_impl(state, payload).await {
+        match get_user_id_impl(&ctx, state, payload).await {
             Ok(response) => AppHttpResponse::Ok(Json(response)),
             Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
         }
     }
 
-    #[oai(path = "/auth/delete_user", method = "post")]
+    #[oai(
+        path = "/auth/delete_user",
+        method = "post",
+        transform = "require_delete_users"
+    )]
     #[tracing::instrument(name = "delete_user", skip_all, fields(req_id=%ctx.request_id))]
     async fn delete_user(
         &self,
@@ -69,7 +280,400 @@ impl EHRApi {
         state: Data<&AppState>,
         payload: Json<DeleteUserRequest>,
     ) -> AppHttpResponse {
-        match delete_user_impl(state, payload).await {
+        match delete_user_impl(&ctx, state, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/mfa/totp", method = "post")]
+    #[tracing::instrument(name = "enroll_totp", skip_all, fields(req_id=%ctx.request_id))]
+    async fn enroll_totp(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        payload: Json<EnrollTotpRequest>,
+    ) -> AppHttpResponse {
+        match enroll_totp_impl(&ctx, state, payload).await {
+            Ok(response) => AppHttpResponse::Created(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/mfa/totp/:id/confirm", method = "post")]
+    #[tracing::instrument(name = "confirm_totp", skip_all, fields(req_id=%ctx.request_id))]
+    async fn confirm_totp(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+        payload: Json<ConfirmTotpRequest>,
+    ) -> AppHttpResponse {
+        match confirm_totp_impl(&ctx, state, id, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/mfa/verify", method = "post")]
+    #[tracing::instrument(name = "verify_mfa", skip_all, fields(req_id=%ctx.request_id))]
+    async fn verify_mfa(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        cookie_jar: &CookieJar,
+        payload: Json<VerifyMfaRequest>,
+    ) -> AppHttpResponse {
+        match verify_mfa_impl(&ctx, state, cookie_jar, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/mfa/factors", method = "get")]
+    #[tracing::instrument(name = "list_mfa_factors", skip_all, fields(req_id=%ctx.request_id))]
+    async fn list_mfa_factors(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+    ) -> AppHttpResponse {
+        match list_mfa_factors_impl(&ctx, state).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/mfa/factors/:id", method = "delete")]
+    #[tracing::instrument(name = "remove_mfa_factor", skip_all, fields(req_id=%ctx.request_id))]
+    async fn remove_mfa_factor(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+        payload: Json<RemoveMfaFactorRequest>,
+    ) -> AppHttpResponse {
+        match remove_mfa_factor_impl(&ctx, state, id, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/auth/mfa/recovery-codes", method = "post")]
+    #[tracing::instrument(name = "generate_recovery_codes", skip_all, fields(req_id=%ctx.request_id))]
+    async fn generate_recovery_codes(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+    ) -> AppHttpResponse {
+        match generate_recovery_codes_impl(&ctx, state).await {
+            Ok(response) => AppHttpResponse::Created(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/users/:id", method = "patch")]
+    #[tracing::instrument(name = "update_user", skip_all, fields(req_id=%ctx.request_id))]
+    async fn update_user(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+        payload: Json<UpdateUserRequest>,
+    ) -> AppHttpResponse {
+        match update_user_impl(&ctx, state, id, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/users/:id/unlock",
+        method = "post",
+        transform = "require_manage_users"
+    )]
+    #[tracing::instrument(name = "unlock_user", skip_all, fields(req_id=%ctx.request_id))]
+    async fn unlock_user(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+    ) -> AppHttpResponse {
+        match unlock_user_impl(&ctx, state, id).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/users/:id/disable",
+        method = "post",
+        transform = "require_manage_users"
+    )]
+    #[tracing::instrument(name = "disable_user", skip_all, fields(req_id=%ctx.request_id))]
+    async fn disable_user(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+        payload: Json<DisableUserRequest>,
+    ) -> AppHttpResponse {
+        match disable_user_impl(&ctx, state, id, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/users/:id/enable",
+        method = "post",
+        transform = "require_manage_users"
+    )]
+    #[tracing::instrument(name = "enable_user", skip_all, fields(req_id=%ctx.request_id))]
+    async fn enable_user(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+        payload: Json<EnableUserRequest>,
+    ) -> AppHttpResponse {
+        match enable_user_impl(&ctx, state, id, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    // Starts or resumes the user's offboarding job
+    #[oai(
+        path = "/users/:id/offboard",
+        method = "post",
+        transform = "require_manage_users"
+    )]
+    #[tracing::instrument(name = "offboard_user", skip_all, fields(req_id=%ctx.request_id))]
+    async fn offboard_user(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+        payload: Json<OffboardUserRequest>,
+    ) -> AppHttpResponse {
+        match offboard_user_impl(&ctx, state, id, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/users/:id/offboard",
+        method = "get",
+        transform = "require_manage_users"
+    )]
+    #[tracing::instrument(name = "get_offboarding", skip_all, fields(req_id=%ctx.request_id))]
+    async fn get_offboarding(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+    ) -> AppHttpResponse {
+        match get_offboarding_impl(&ctx, state, id).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/users/:id/sessions",
+        method = "get",
+        transform = "require_manage_sessions"
+    )]
+    #[tracing::instrument(name = "list_user_sessions", skip_all, fields(req_id=%ctx.request_id))]
+    async fn list_user_sessions(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+    ) -> AppHttpResponse {
+        match list_user_sessions_impl(&ctx, state, id).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/users/:id/sessions",
+        method = "delete",
+        transform = "require_manage_sessions"
+    )]
+    #[tracing::instrument(name = "revoke_user_sessions", skip_all, fields(req_id=%ctx.request_id))]
+    async fn revoke_user_sessions(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+    ) -> AppHttpResponse {
+        match revoke_user_sessions_impl(&ctx, state, id).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/users/:id/sessions/:session_id",
+        method = "delete",
+        transform = "require_manage_sessions"
+    )]
+    #[tracing::instrument(name = "revoke_user_session", skip_all, fields(req_id=%ctx.request_id))]
+    async fn revoke_user_session(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+        session_id: Path<String>,
+    ) -> AppHttpResponse {
+        match revoke_user_session_impl(&ctx, state, id, session_id).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/users/:id/practice",
+        method = "put",
+        transform = "require_manage_practices"
+    )]
+    #[tracing::instrument(name = "assign_practice", skip_all, fields(req_id=%ctx.request_id))]
+    async fn assign_practice(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+        payload: Json<AssignPracticeRequest>,
+    ) -> AppHttpResponse {
+        match assign_practice_impl(&ctx, state, id, payload).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/practices",
+        method = "post",
+        transform = "require_manage_practices"
+    )]
+    #[tracing::instrument(name = "create_practice", skip_all, fields(req_id=%ctx.request_id))]
+    async fn create_practice(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        payload: Json<CreatePracticeRequest>,
+    ) -> AppHttpResponse {
+        match create_practice_impl(&ctx, state, payload).await {
+            Ok(response) => AppHttpResponse::Created(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/practices",
+        method = "get",
+        transform = "require_manage_practices"
+    )]
+    #[tracing::instrument(name = "list_practices", skip_all, fields(req_id=%ctx.request_id))]
+    async fn list_practices(&self, ctx: RequestContext, state: Data<&AppState>) -> AppHttpResponse {
+        match list_practices_impl(state).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/api-keys",
+        method = "post",
+        transform = "require_manage_api_keys"
+    )]
+    #[tracing::instrument(name = "create_api_key", skip_all, fields(req_id=%ctx.request_id))]
+    async fn create_api_key(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        payload: Json<CreateApiKeyRequest>,
+    ) -> AppHttpResponse {
+        match create_api_key_impl(&ctx, state, payload).await {
+            Ok(response) => AppHttpResponse::Created(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/api-keys",
+        method = "get",
+        transform = "require_manage_api_keys"
+    )]
+    #[tracing::instrument(name = "list_api_keys", skip_all, fields(req_id=%ctx.request_id))]
+    async fn list_api_keys(&self, ctx: RequestContext, state: Data<&AppState>) -> AppHttpResponse {
+        match list_api_keys_impl(&ctx, state).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(
+        path = "/api-keys/:id",
+        method = "delete",
+        transform = "require_manage_api_keys"
+    )]
+    #[tracing::instrument(name = "revoke_api_key", skip_all, fields(req_id=%ctx.request_id))]
+    async fn revoke_api_key(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+    ) -> AppHttpResponse {
+        match revoke_api_key_impl(&ctx, state, id).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/users", method = "get", transform = "require_read_users")]
+    #[tracing::instrument(name = "list_users", skip_all, fields(req_id=%ctx.request_id))]
+    // Query parameters are documented in the spec only as separate arguments
+    #[allow(clippy::too_many_arguments)]
+    async fn list_users(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        /// Text to find in names and email addresses
+        search: Query<Option<String>>,
+        role: Query<Option<String>>,
+        enabled: Query<Option<bool>>,
+        /// name, email, created_at or last_login_at; prefix with '-' to sort descending
+        sort: Query<Option<String>>,
+        page: Query<Option<u32>>,
+        per_page: Query<Option<u32>>,
+    ) -> AppHttpResponse {
+        let params = ListUsersParams {
+            search: search.0,
+            role: role.0,
+            enabled: enabled.0,
+            sort: sort.0,
+            page: page.0,
+            per_page: per_page.0,
+        };
+        match list_users_impl(&ctx, state, params).await {
+            Ok(response) => AppHttpResponse::Ok(Json(response)),
+            Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
+        }
+    }
+
+    #[oai(path = "/users/:id", method = "get")]
+    #[tracing::instrument(name = "get_user", skip_all, fields(req_id=%ctx.request_id))]
+    async fn get_user(
+        &self,
+        ctx: RequestContext,
+        state: Data<&AppState>,
+        id: Path<String>,
+    ) -> AppHttpResponse {
+        match get_user_impl(&ctx, state, id).await {
             Ok(response) => AppHttpResponse::Ok(Json(response)),
             Err(e) => AppHttpResponse::from_app_error(e, &ctx.request_id),
         }
@@ -1,12 +1,15 @@
 use thiserror::Error;
 use tracing_error::SpanTrace;
 
+use crate::domain::types::{password_policy::PasswordRule, permission::Permission, user::UserRole};
+
 #[derive(Debug, Error)]
 pub enum ValidationError {
     #[error("Invalid email")]
     InvalidEmail,
+    // Lists the policy rules the password broke; empty when the provider rejected it unexplained
     #[error("Invalid password")]
-    InvalidPassword,
+    InvalidPassword(Vec<PasswordRule>),
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 }
@@ -19,16 +22,85 @@ pub enum AuthProviderError {
     UserExists,
     #[error("User not found")]
     UserNotFound,
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Practice not found")]
+    PracticeNotFound,
+    #[error("Practice already exists")]
+    PracticeExists,
+    #[error("API key not found")]
+    ApiKeyNotFound,
+    #[error("Offboarding job not found")]
+    OffboardingNotFound,
+    #[error("Invalid credentials")]
+    InvalidCredentials,
+    #[error("Account disabled")]
+    AccountDisabled,
+    #[error("Email not verified")]
+    EmailNotVerified,
+    #[error("Account locked for {0} seconds")]
+    AccountLocked(u64),
+    #[error("Too many login attempts, retry in {0} seconds")]
+    TooManyAttempts(u64),
+    #[error("Authentication required")]
+    Unauthenticated,
+    #[error("Invalid access token: {0}")]
+    InvalidToken(String),
     #[error("Network error: {0}")]
     Network(String),
 }
 
+#[derive(Debug, Error)]
+pub enum AuthorizationError {
+    #[error("Missing permission: {}", .0.as_str())]
+    MissingPermission(Permission),
+    #[error("Cannot assign role: {}", .0.as_str())]
+    CannotAssignRole(UserRole),
+    #[error("Missing or invalid CSRF token")]
+    InvalidCsrfToken,
+    #[error("Self-service signup is disabled")]
+    SignupDisabled,
+    #[error("Invitations are disabled")]
+    InvitationsDisabled,
+    #[error("Account has accessed PHI and cannot be deleted")]
+    PhiHistory,
+}
+
+#[derive(Debug, Error)]
+pub enum MfaError {
+    #[error("A second factor is required")]
+    Required,
+    #[error("Invalid second factor code")]
+    InvalidCode,
+    #[error("MFA factor not found")]
+    FactorNotFound,
+    #[error("MFA factor already confirmed")]
+    AlreadyConfirmed,
+    #[error("No confirmed MFA factor")]
+    NotEnrolled,
+    #[error("MFA enrollment is required")]
+    EnrollmentRequired,
+}
+
+#[derive(Debug, Error)]
+pub enum MailerError {
+    #[error("Mail delivery failed: {0}")]
+    Delivery(String),
+}
+
 #[derive(Debug, Error)]
 pub enum DatabaseError {
     #[error("Postgres error: {0}")]
     Postgres(String),
 }
 
+impl DatabaseError {
+    // A failed query, described by what it was meant to do
+    pub fn context(action: &str, e: sqlx::Error) -> AppError {
+        DatabaseError::Postgres(format!("Failed to {action}: {e}")).into()
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error(transparent)]
@@ -36,7 +108,13 @@ pub enum AppError {
     #[error(transparent)]
     AuthProvider(#[from] AuthProviderError),
     #[error(transparent)]
+    Authorization(#[from] AuthorizationError),
+    #[error(transparent)]
     Database(#[from] DatabaseError),
+    #[error(transparent)]
+    Mfa(#[from] MfaError),
+    #[error(transparent)]
+    Mailer(#[from] MailerError),
     #[error("Internal server error")]
     Internal {
         #[source]
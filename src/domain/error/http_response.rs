@@ -2,8 +2,12 @@ use poem_openapi::{ApiResponse, Object, payload::Json};
 use serde::Serialize;
 use serde_json::Value;
 
-use crate::domain::error::app_error::{
-    AppError, AuthProviderError, DatabaseError, ValidationError,
+use crate::domain::{
+    error::app_error::{
+        AppError, AuthProviderError, AuthorizationError, DatabaseError, MailerError, MfaError,
+        ValidationError,
+    },
+    types::password_policy::PasswordRule,
 };
 
 #[derive(Object, Serialize, Debug)]
@@ -11,6 +15,16 @@ pub struct ErrorBody {
     pub code: String,
     pub message: String,
     pub request_id: String,
+    // Set for InvalidPassword when the password broke known policy rules
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_rules: Option<Vec<FailedRule>>,
+}
+
+#[derive(Object, Serialize, Debug)]
+pub struct FailedRule {
+    pub rule: String,
+    pub message: String,
 }
 
 #[derive(ApiResponse, Debug)]
@@ -19,12 +33,24 @@ pub enum AppHttpResponse {
     Ok(Json<Value>),
     #[oai(status = 201)]
     Created(Json<Value>),
+    #[oai(status = 202)]
+    Accepted(Json<Value>),
+    #[oai(status = 302)]
+    Found(#[oai(header = "Location")] String),
     #[oai(status = 400)]
     BadRequest(Json<ErrorBody>),
+    #[oai(status = 401)]
+    Unauthorized(Json<ErrorBody>),
+    #[oai(status = 403)]
+    Forbidden(Json<ErrorBody>),
     #[oai(status = 404)]
     NotFound(Json<ErrorBody>),
     #[oai(status = 409)]
     Conflict(Json<ErrorBody>),
+    #[oai(status = 423)]
+    Locked(Json<ErrorBody>),
+    #[oai(status = 429)]
+    TooManyRequests(Json<ErrorBody>),
     #[oai(status = 502)]
     BadGateway(Json<ErrorBody>),
     #[oai(status = 500)]
@@ -43,6 +69,7 @@ impl AppHttpResponse {
             code: code.to_string(),
             message: message.to_string(),
             request_id: request_id.to_string(),
+            failed_rules: None,
         })
     }
 
@@ -51,13 +78,35 @@ impl AppHttpResponse {
             AppError::Validation(ValidationError::InvalidEmail) => AppHttpResponse::BadRequest(
                 Self::body("InvalidEmail", "The provided email is invalid", request_id),
             ),
-            AppError::Validation(ValidationError::InvalidPassword) => {
+            AppError::Validation(ValidationError::InvalidPassword(rules)) if rules.is_empty() => {
                 AppHttpResponse::BadRequest(Self::body(
                     "InvalidPassword",
-                    "Password must be at least 8 characters long with at least one number and one special character",
+                    "The password does not meet the password policy",
                     request_id,
                 ))
             }
+            AppError::Validation(ValidationError::InvalidPassword(rules)) => {
+                let message = rules
+                    .iter()
+                    .map(PasswordRule::description)
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                let mut body = Self::body(
+                    "InvalidPassword",
+                    &format!("The password does not meet the password policy: {message}"),
+                    request_id,
+                );
+                body.failed_rules = Some(
+                    rules
+                        .iter()
+                        .map(|rule| FailedRule {
+                            rule: rule.code(),
+                            message: rule.description(),
+                        })
+                        .collect(),
+                );
+                AppHttpResponse::BadRequest(body)
+            }
             AppError::Validation(ValidationError::InvalidInput(msg)) => {
                 AppHttpResponse::BadRequest(Self::body("InvalidInput", &msg, request_id))
             }
@@ -67,15 +116,173 @@ impl AppHttpResponse {
             AppError::AuthProvider(AuthProviderError::UserNotFound) => AppHttpResponse::NotFound(
                 Self::body("UserNotFound", "The user was not found", request_id),
             ),
+            AppError::AuthProvider(AuthProviderError::SessionNotFound) => {
+                AppHttpResponse::NotFound(Self::body(
+                    "SessionNotFound",
+                    "The user has no such session",
+                    request_id,
+                ))
+            }
+            AppError::AuthProvider(AuthProviderError::PracticeNotFound) => {
+                AppHttpResponse::NotFound(Self::body(
+                    "PracticeNotFound",
+                    "The practice was not found",
+                    request_id,
+                ))
+            }
+            AppError::AuthProvider(AuthProviderError::PracticeExists) => {
+                AppHttpResponse::Conflict(Self::body(
+                    "PracticeExists",
+                    "A practice with this name already exists",
+                    request_id,
+                ))
+            }
+            AppError::AuthProvider(AuthProviderError::ApiKeyNotFound) => AppHttpResponse::NotFound(
+                Self::body("ApiKeyNotFound", "The API key was not found", request_id),
+            ),
+            AppError::AuthProvider(AuthProviderError::OffboardingNotFound) => {
+                AppHttpResponse::NotFound(Self::body(
+                    "OffboardingNotFound",
+                    "The user has not been offboarded",
+                    request_id,
+                ))
+            }
+            AppError::AuthProvider(AuthProviderError::InvalidCredentials) => {
+                AppHttpResponse::Unauthorized(Self::body(
+                    "InvalidCredentials",
+                    "The email or password is incorrect",
+                    request_id,
+                ))
+            }
+            AppError::AuthProvider(AuthProviderError::AccountDisabled) => {
+                AppHttpResponse::Forbidden(Self::body(
+                    "AccountDisabled",
+                    "The account is disabled",
+                    request_id,
+                ))
+            }
+            AppError::AuthProvider(AuthProviderError::EmailNotVerified) => {
+                AppHttpResponse::Forbidden(Self::body(
+                    "EmailNotVerified",
+                    "The email address has not been verified",
+                    request_id,
+                ))
+            }
+            AppError::AuthProvider(AuthProviderError::AccountLocked(secs)) => {
+                AppHttpResponse::Locked(Self::body(
+                    "AccountLocked",
+                    &format!(
+                        "The account is locked after too many failed logins; try again in {secs} seconds"
+                    ),
+                    request_id,
+                ))
+            }
+            AppError::AuthProvider(AuthProviderError::TooManyAttempts(secs)) => {
+                AppHttpResponse::TooManyRequests(Self::body(
+                    "TooManyAttempts",
+                    &format!(
+                        "Too many failed logins from this address; try again in {secs} seconds"
+                    ),
+                    request_id,
+                ))
+            }
+            AppError::AuthProvider(AuthProviderError::Unauthenticated) => {
+                AppHttpResponse::Unauthorized(Self::body(
+                    "Unauthenticated",
+                    "A valid bearer token is required",
+                    request_id,
+                ))
+            }
+            AppError::AuthProvider(AuthProviderError::InvalidToken(msg)) => {
+                AppHttpResponse::Unauthorized(Self::body("InvalidToken", &msg, request_id))
+            }
             AppError::AuthProvider(AuthProviderError::Upstream(msg)) => {
                 AppHttpResponse::BadGateway(Self::body("UpstreamError", &msg, request_id))
             }
             AppError::AuthProvider(AuthProviderError::Network(msg)) => {
                 AppHttpResponse::BadGateway(Self::body("NetworkError", &msg, request_id))
             }
+            AppError::Authorization(AuthorizationError::MissingPermission(permission)) => {
+                AppHttpResponse::Forbidden(Self::body(
+                    "MissingPermission",
+                    &format!("The caller lacks the '{}' permission", permission.as_str()),
+                    request_id,
+                ))
+            }
+            AppError::Authorization(AuthorizationError::CannotAssignRole(role)) => {
+                AppHttpResponse::Forbidden(Self::body(
+                    "CannotAssignRole",
+                    &format!(
+                        "The caller cannot assign or revoke the '{}' role",
+                        role.as_str()
+                    ),
+                    request_id,
+                ))
+            }
+            AppError::Authorization(AuthorizationError::InvalidCsrfToken) => {
+                AppHttpResponse::Forbidden(Self::body(
+                    "InvalidCsrfToken",
+                    "State-changing requests in a session must echo the CSRF token in X-CSRF-Token",
+                    request_id,
+                ))
+            }
+            AppError::Authorization(AuthorizationError::SignupDisabled) => {
+                AppHttpResponse::Forbidden(Self::body(
+                    "SignupDisabled",
+                    "Self-service signup is disabled; ask an administrator for an invitation",
+                    request_id,
+                ))
+            }
+            AppError::Authorization(AuthorizationError::InvitationsDisabled) => {
+                AppHttpResponse::Forbidden(Self::body(
+                    "InvitationsDisabled",
+                    "Invitations are not enabled on this server",
+                    request_id,
+                ))
+            }
+            AppError::Authorization(AuthorizationError::PhiHistory) => {
+                AppHttpResponse::Conflict(Self::body(
+                    "PhiHistory",
+                    "The account may have accessed patient data and must be disabled instead of deleted",
+                    request_id,
+                ))
+            }
+            AppError::Mfa(MfaError::Required) => AppHttpResponse::Unauthorized(Self::body(
+                "MfaRequired",
+                "A code from an authenticator app or a recovery code is required",
+                request_id,
+            )),
+            AppError::Mfa(MfaError::InvalidCode) => AppHttpResponse::Unauthorized(Self::body(
+                "InvalidMfaCode",
+                "The authentication code is incorrect or has already been used",
+                request_id,
+            )),
+            AppError::Mfa(MfaError::FactorNotFound) => AppHttpResponse::NotFound(Self::body(
+                "MfaFactorNotFound",
+                "The MFA factor was not found",
+                request_id,
+            )),
+            AppError::Mfa(MfaError::AlreadyConfirmed) => AppHttpResponse::Conflict(Self::body(
+                "MfaFactorAlreadyConfirmed",
+                "The MFA factor has already been confirmed",
+                request_id,
+            )),
+            AppError::Mfa(MfaError::NotEnrolled) => AppHttpResponse::Conflict(Self::body(
+                "MfaNotEnrolled",
+                "A confirmed MFA factor is required first",
+                request_id,
+            )),
+            AppError::Mfa(MfaError::EnrollmentRequired) => AppHttpResponse::Forbidden(Self::body(
+                "MfaEnrollmentRequired",
+                "Enroll an authenticator app before using the rest of the API",
+                request_id,
+            )),
             AppError::Database(DatabaseError::Postgres(msg)) => {
                 AppHttpResponse::InternalServerError(Self::body("DatabaseError", &msg, request_id))
             }
+            AppError::Mailer(MailerError::Delivery(msg)) => {
+                AppHttpResponse::BadGateway(Self::body("MailDeliveryError", &msg, request_id))
+            }
             AppError::Internal { source, .. } => AppHttpResponse::InternalServerError(Self::body(
                 "InternalServerError",
                 &source.to_string(),
@@ -1,8 +1,14 @@
+use secrecy::SecretString;
+use uuid::Uuid;
+
 use crate::domain::{
     error::app_error::AppResult,
     types::{
+        authorization::AuthorizationRequest,
         email::Email,
         password::Password,
+        session::UserSession,
+        token::AuthTokens,
         user::{User, UserUpdate},
     },
 };
@@ -10,10 +16,35 @@ use crate::domain::{
 #[async_trait::async_trait]
 pub trait AuthProvider {
     async fn retrieve_auth_token(&self) -> AppResult<String>;
-    async fn signup_user(&self, user: User) -> AppResult<()>;
-    async fn login_user(&self, email: Email, password: Password) -> AppResult<User>;
-    async fn logout_user(&self, user_id: String) -> AppResult<()>;
+    // Returns the provider's id for the new user
+    async fn signup_user(&self, user: User) -> AppResult<String>;
+    async fn login_user(&self, email: Email, password: Password) -> AppResult<(User, AuthTokens)>;
+    async fn logout_user(&self, refresh_token: SecretString) -> AppResult<()>;
+    // Stops the refresh token working; the user's other sessions carry on
+    async fn revoke_refresh_token(&self, refresh_token: SecretString) -> AppResult<()>;
+    // Exchanges a refresh token for a new pair; the presented token stops working
+    async fn refresh_user_tokens(&self, refresh_token: SecretString) -> AppResult<AuthTokens>;
+    async fn end_session(&self, session_id: String) -> AppResult<()>;
+    async fn list_user_sessions(&self, user_id: String) -> AppResult<Vec<UserSession>>;
+    // Ends every session the user holds
+    async fn end_all_sessions(&self, user_id: String) -> AppResult<()>;
     async fn delete_user(&self, user_id: String) -> AppResult<()>;
     async fn get_user_id(&self, email: Email) -> AppResult<Option<String>>;
+    async fn get_user(&self, user_id: String) -> AppResult<User>;
     async fn update_user(&self, user_update: UserUpdate) -> AppResult<()>;
+    // Emails the user a link to choose a new password
+    async fn send_password_reset(&self, email: Email) -> AppResult<()>;
+    // Where to send the browser to sign in with the authorization code flow
+    fn authorization_url(&self, request: &AuthorizationRequest) -> AppResult<String>;
+    // Redeems the code handed to the callback; the verifier proves this server started the flow
+    async fn exchange_authorization_code(
+        &self,
+        code: String,
+        code_verifier: SecretString,
+        redirect_uri: String,
+    ) -> AppResult<AuthTokens>;
+    // Emails an unverified user a verification link; a no-op once verified
+    async fn send_verification_email(&self, email: Email) -> AppResult<()>;
+    // Makes the practice available for membership; creating one that exists is a no-op
+    async fn create_practice(&self, practice_id: Uuid) -> AppResult<()>;
 }
@@ -0,0 +1,12 @@
+use crate::domain::{error::app_error::AppResult, types::email::Email};
+
+pub struct EmailMessage {
+    pub to: Email,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait::async_trait]
+pub trait Mailer {
+    async fn send(&self, message: EmailMessage) -> AppResult<()>;
+}
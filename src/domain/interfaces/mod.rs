@@ -1 +1,3 @@
 pub mod auth_provider;
+pub mod mailer;
+pub mod token_validator;
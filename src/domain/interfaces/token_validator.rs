@@ -0,0 +1,6 @@
+use crate::domain::{error::app_error::AppResult, types::auth_context::AuthContext};
+
+#[async_trait::async_trait]
+pub trait TokenValidator {
+    async fn validate_access_token(&self, token: &str) -> AppResult<AuthContext>;
+}
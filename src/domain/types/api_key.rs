@@ -0,0 +1,101 @@
+use uuid::Uuid;
+
+use crate::domain::{error::app_error::ValidationError, types::permission::Permission};
+
+// Marks a bearer credential as an API key rather than a JWT
+pub const API_KEY_PREFIX: &str = "lgrk_";
+
+// Characters of the secret kept in the clear so owners can tell keys apart
+const DISPLAY_PREFIX_LEN: usize = API_KEY_PREFIX.len() + 8;
+
+// A scope is `resource:action`, e.g. `patients:read`; permissions use the same shape
+pub fn validate_scope(scope: &str) -> Result<(), ValidationError> {
+    let valid_part =
+        |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase() || c == '_');
+    match scope.split_once(':') {
+        Some((resource, action)) if valid_part(resource) && valid_part(action) => {}
+        _ => Err(ValidationError::InvalidInput(format!(
+            "Invalid scope '{scope}', expected resource:action"
+        )))?,
+    }
+
+    // Keys must not be able to mint further keys, and deleting accounts takes an owner
+    if [Permission::ManageApiKeys, Permission::DeleteUsers]
+        .iter()
+        .any(|permission| permission.as_str() == scope)
+    {
+        Err(ValidationError::InvalidInput(format!(
+            "Scope '{scope}' cannot be granted to an API key"
+        )))?
+    }
+    Ok(())
+}
+
+pub fn display_prefix(key: &str) -> String {
+    key.chars().take(DISPLAY_PREFIX_LEN).collect()
+}
+
+// A stored key as owners see it; times are Unix seconds
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub last_used_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+}
+
+impl ApiKey {
+    pub fn response_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "api_key_id": self.id.to_string(),
+            "name": self.name,
+            "prefix": self.prefix,
+            "scopes": self.scopes,
+            "created_at": self.created_at,
+            "expires_at": self.expires_at,
+            "last_used_at": self.last_used_at,
+            "revoked_at": self.revoked_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts_resource_action_scopes() {
+        for scope in [
+            "patients:read",
+            "claims:write",
+            "users:read",
+            "lab_results:read",
+        ] {
+            assert!(validate_scope(scope).is_ok(), "Should accept: {scope}");
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_scopes() {
+        for scope in [
+            "",
+            "patients",
+            ":read",
+            "patients:",
+            "Patients:read",
+            "a:b:c",
+            "x: y",
+        ] {
+            assert!(validate_scope(scope).is_err(), "Should reject: {scope}");
+        }
+    }
+
+    #[test]
+    fn test_keys_cannot_manage_keys_or_delete_users() {
+        assert!(validate_scope(Permission::ManageApiKeys.as_str()).is_err());
+        assert!(validate_scope(Permission::DeleteUsers.as_str()).is_err());
+    }
+}
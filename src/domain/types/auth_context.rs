@@ -0,0 +1,171 @@
+use uuid::Uuid;
+
+use crate::domain::{
+    error::app_error::{AppResult, AuthorizationError, MfaError},
+    types::{permission::Permission, user::UserRole},
+};
+
+// What a caller signed in with only a password must do before the credential grants access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingMfa {
+    // The role requires MFA and no authenticator is enrolled yet
+    Enroll,
+    // An authenticator is enrolled and its code hasn't been given yet
+    Verify,
+}
+
+// Identity of the caller, taken from a validated access token
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub subject: String,
+    pub email: Option<String>,
+    pub realm_roles: Vec<String>,
+    // Practice the caller belongs to; records of other practices are out of reach
+    pub practice_id: Option<Uuid>,
+    // Set when the caller is an API key, whose scopes replace role permissions
+    pub scopes: Option<Vec<String>>,
+    // Set while sign-in is waiting on MFA; only the MFA routes accept such a caller
+    pub pending_mfa: Option<PendingMfa>,
+}
+
+impl AuthContext {
+    pub fn role(&self) -> Option<UserRole> {
+        UserRole::from_realm_roles(&self.realm_roles)
+    }
+
+    pub fn require_mfa_complete(&self) -> AppResult<()> {
+        match self.pending_mfa {
+            None => Ok(()),
+            Some(PendingMfa::Enroll) => Err(MfaError::EnrollmentRequired)?,
+            Some(PendingMfa::Verify) => Err(MfaError::Required)?,
+        }
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        if self.pending_mfa.is_some() {
+            return false;
+        }
+        if let Some(scopes) = &self.scopes {
+            return scopes.iter().any(|scope| scope == permission.as_str());
+        }
+        self.realm_roles
+            .iter()
+            .filter_map(|role| UserRole::from_realm_role(role))
+            .any(|role| role.has_permission(permission))
+    }
+
+    pub fn require_permission(&self, permission: Permission) -> AppResult<()> {
+        if self.has_permission(permission) {
+            return Ok(());
+        }
+        Err(AuthorizationError::MissingPermission(permission))?
+    }
+
+    // Users may always act on their own account; anyone else needs the permission
+    pub fn require_self_or(&self, user_id: &str, permission: Permission) -> AppResult<()> {
+        if self.subject == user_id {
+            return Ok(());
+        }
+        self.require_permission(permission)
+    }
+
+    // Assigning roles needs ManageUsers, and only an owner may grant or revoke the owner role
+    pub fn require_role_assignment(&self, role: UserRole) -> AppResult<()> {
+        self.require_permission(Permission::ManageUsers)?;
+        if role == UserRole::Owner && self.role() != Some(UserRole::Owner) {
+            Err(AuthorizationError::CannotAssignRole(role))?
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(roles: &[&str]) -> AuthContext {
+        AuthContext {
+            subject: "1b4e28ba-2fa1-11d2-883f-0016d3cca427".to_string(),
+            email: None,
+            realm_roles: roles.iter().map(|r| r.to_string()).collect(),
+            practice_id: None,
+            scopes: None,
+            pending_mfa: None,
+        }
+    }
+
+    #[test]
+    fn test_permission_granted_by_any_role() {
+        let auth = context(&["offline_access", "clinician", "admin"]);
+        assert!(auth.has_permission(Permission::ManageUsers));
+    }
+
+    #[test]
+    fn test_require_self_or_permission() {
+        let auth = context(&["clinician"]);
+        assert!(
+            auth.require_self_or(&auth.subject, Permission::ManageUsers)
+                .is_ok()
+        );
+        assert!(
+            auth.require_self_or("someone-else", Permission::ManageUsers)
+                .is_err()
+        );
+        assert!(
+            context(&["owner"])
+                .require_self_or("someone-else", Permission::ManageUsers)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_role_assignment() {
+        let admin = context(&["admin"]);
+        assert!(admin.require_role_assignment(UserRole::Clinician).is_ok());
+        assert!(admin.require_role_assignment(UserRole::Admin).is_ok());
+        assert!(matches!(
+            admin.require_role_assignment(UserRole::Owner),
+            Err(crate::domain::error::app_error::AppError::Authorization(
+                AuthorizationError::CannotAssignRole(UserRole::Owner)
+            ))
+        ));
+
+        assert!(
+            context(&["owner"])
+                .require_role_assignment(UserRole::Owner)
+                .is_ok()
+        );
+        assert!(
+            context(&["clinician"])
+                .require_role_assignment(UserRole::Clinician)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_no_permissions_without_ehr_role() {
+        let auth = context(&["offline_access", "uma_authorization"]);
+        assert!(!auth.has_permission(Permission::ReadUsers));
+    }
+
+    #[test]
+    fn test_api_key_scopes_replace_role_permissions() {
+        let auth = AuthContext {
+            scopes: Some(vec!["users:read".to_string(), "claims:write".to_string()]),
+            ..context(&["owner"])
+        };
+        assert!(auth.has_permission(Permission::ReadUsers));
+        assert!(!auth.has_permission(Permission::ManageUsers));
+    }
+
+    #[test]
+    fn test_pending_mfa_grants_nothing() {
+        let auth = AuthContext {
+            pending_mfa: Some(PendingMfa::Enroll),
+            ..context(&["owner"])
+        };
+        assert!(!auth.has_permission(Permission::ReadUsers));
+        assert!(auth.require_mfa_complete().is_err());
+        assert!(context(&["owner"]).require_mfa_complete().is_ok());
+    }
+}
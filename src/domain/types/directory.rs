@@ -0,0 +1,130 @@
+use std::str::FromStr;
+
+use uuid::Uuid;
+
+use crate::domain::{error::app_error::ValidationError, types::user::UserRole};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 25;
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    // Last name, then first name
+    Name,
+    Email,
+    CreatedAt,
+    LastLoginAt,
+}
+
+// Written as the field name, prefixed with '-' for descending order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserSort {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl Default for UserSort {
+    fn default() -> Self {
+        Self {
+            field: SortField::Name,
+            descending: false,
+        }
+    }
+}
+
+impl FromStr for UserSort {
+    type Err = ValidationError;
+
+    fn from_str(sort: &str) -> Result<Self, Self::Err> {
+        let (descending, field) = match sort.strip_prefix('-') {
+            Some(field) => (true, field),
+            None => (false, sort),
+        };
+        let field = match field {
+            "name" => SortField::Name,
+            "email" => SortField::Email,
+            "created_at" => SortField::CreatedAt,
+            "last_login_at" => SortField::LastLoginAt,
+            other => Err(ValidationError::InvalidInput(format!(
+                "Cannot sort users by: {other}"
+            )))?,
+        };
+        Ok(Self { field, descending })
+    }
+}
+
+pub struct UserQuery {
+    // Matched against names and email addresses, case-insensitively
+    pub search: Option<String>,
+    pub role: Option<UserRole>,
+    pub enabled: Option<bool>,
+    pub sort: UserSort,
+    // Starts at 1
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl UserQuery {
+    pub fn offset(&self) -> u64 {
+        u64::from(self.page.saturating_sub(1)) * u64::from(self.per_page)
+    }
+}
+
+// A user as the directory lists them; times are Unix seconds
+pub struct DirectoryEntry {
+    pub user_id: Uuid,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub role: Option<String>,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub last_login_at: Option<i64>,
+}
+
+impl DirectoryEntry {
+    pub fn response_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "user_id": self.user_id.to_string(),
+            "first_name": self.first_name,
+            "last_name": self.last_name,
+            "email": self.email,
+            "role": self.role,
+            "enabled": self.enabled,
+            "created_at": self.created_at,
+            "last_login_at": self.last_login_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sort() {
+        assert_eq!("name".parse::<UserSort>().unwrap(), UserSort::default());
+        assert_eq!(
+            "-last_login_at".parse::<UserSort>().unwrap(),
+            UserSort {
+                field: SortField::LastLoginAt,
+                descending: true,
+            }
+        );
+        assert!("password".parse::<UserSort>().is_err());
+        assert!("--email".parse::<UserSort>().is_err());
+    }
+
+    #[test]
+    fn test_offset_counts_from_first_page() {
+        let query = UserQuery {
+            search: None,
+            role: None,
+            enabled: None,
+            sort: UserSort::default(),
+            page: 3,
+            per_page: 25,
+        };
+        assert_eq!(query.offset(), 50);
+    }
+}
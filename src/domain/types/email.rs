@@ -5,9 +5,12 @@ use validator::{Validate, ValidateEmail};
 
 use crate::domain::error::app_error::{AppResult, ValidationError};
 
+// `inner` is the canonical form every comparison and lookup uses; `display` is the
+// address as the user typed it, trimmed, for showing back to them
 #[derive(Debug, Clone)]
 pub struct Email {
     inner: SecretString,
+    display: SecretString,
 }
 
 impl AsRef<SecretString> for Email {
@@ -33,7 +36,10 @@ impl Eq for Email {}
 impl Validate for Email {
     #[tracing::instrument(name = "email_validation", skip_all)]
     fn validate(&self) -> std::result::Result<(), validator::ValidationErrors> {
-        if !self.inner.expose_secret().validate_email() {
+        // A canonical address canonicalises to itself
+        if canonicalize(self.inner.expose_secret(), false).as_deref()
+            != Some(self.inner.expose_secret())
+        {
             return Err(validator::ValidationErrors::new());
         }
 
@@ -41,16 +47,61 @@ impl Validate for Email {
     }
 }
 
+// RFC 5322 atext plus any non-ASCII character (RFC 6531), with dots only between atoms
+fn valid_local_part(local: &str) -> bool {
+    !local.is_empty()
+        && local.len() <= 64
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && local.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || "!#$%&'*+/=?^_`{|}~-.".contains(c)
+                || (!c.is_ascii() && !c.is_whitespace() && !c.is_control())
+        })
+}
+
+// Trims the address, maps the domain to lower-case ASCII (punycode for IDNs) and, when
+// asked, lower-cases the local part. None if the address is not valid.
+fn canonicalize(email: &str, case_insensitive_local_part: bool) -> Option<String> {
+    let (local, domain) = email.trim().rsplit_once('@')?;
+    if !valid_local_part(local) {
+        return None;
+    }
+
+    let domain = idna::domain_to_ascii(domain).ok()?;
+    // The local part was checked above, so only the domain is left to the validator
+    if !format!("user@{domain}").validate_email() {
+        return None;
+    }
+
+    let local = if case_insensitive_local_part {
+        local.to_lowercase()
+    } else {
+        local.to_string()
+    };
+    Some(format!("{local}@{domain}"))
+}
+
 impl Email {
+    // Canonicalises with a case-insensitive local part, as Keycloak matches addresses
     #[tracing::instrument(name = "email_creation", skip_all)]
     pub fn new(email: String) -> AppResult<Self> {
-        let email = Email {
-            inner: SecretString::from(email),
-        };
-        email
-            .validate()
-            .map_err(|_| ValidationError::InvalidEmail)?;
-        Ok(email)
+        Self::parse(email, true)
+    }
+
+    #[tracing::instrument(name = "email_parsing", skip_all)]
+    pub fn parse(email: String, case_insensitive_local_part: bool) -> AppResult<Self> {
+        let canonical = canonicalize(&email, case_insensitive_local_part)
+            .ok_or(ValidationError::InvalidEmail)?;
+        Ok(Email {
+            inner: SecretString::from(canonical),
+            display: SecretString::from(email.trim().to_string()),
+        })
+    }
+
+    pub fn display(&self) -> &SecretString {
+        &self.display
     }
 }
 
@@ -84,6 +135,9 @@ mod tests {
             "",
             " ",
             "test @example.com",
+            ".test@example.com",
+            "te..st@example.com",
+            "test@exa mple.com",
         ];
 
         for email_str in invalid_emails {
@@ -132,6 +186,46 @@ mod tests {
         assert_eq!(email1, email2);
     }
 
+    #[test]
+    fn test_email_canonical_form() {
+        let cases = vec![
+            ("  Foo@Example.COM ", "foo@example.com"),
+            ("first+Last@Sub.Example.org", "first+last@sub.example.org"),
+            ("user@bücher.example", "user@xn--bcher-kva.example"),
+            ("Ünïcode@例え.jp", "ünïcode@xn--r8jz45g.jp"),
+        ];
+
+        for (input, canonical) in cases {
+            let email = Email::new(input.to_string()).unwrap();
+            assert_eq!(email.as_ref().expose_secret(), canonical);
+            assert_eq!(email.display().expose_secret(), input.trim());
+            assert!(email.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_email_equality_uses_canonical_form() {
+        let email1 = Email::new("Foo@Example.com".to_string()).unwrap();
+        let email2 = Email::new("foo@EXAMPLE.COM".to_string()).unwrap();
+        let idn1 = Email::new("user@BÜCHER.example".to_string()).unwrap();
+        let idn2 = Email::new("user@xn--bcher-kva.example".to_string()).unwrap();
+
+        assert_eq!(email1, email2);
+        assert_eq!(idn1, idn2);
+        assert_eq!(HashSet::from([email1, email2]).len(), 1);
+    }
+
+    #[test]
+    fn test_email_case_sensitive_local_part() {
+        let email = Email::parse("Foo.Bar@Example.com".to_string(), false).unwrap();
+        assert_eq!(email.as_ref().expose_secret(), "Foo.Bar@example.com");
+
+        assert_ne!(
+            email,
+            Email::parse("foo.bar@example.com".to_string(), false).unwrap()
+        );
+    }
+
     #[test]
     fn test_email_debug() {
         let email = Email::new("test@example.com".to_string()).unwrap();
@@ -1,3 +1,13 @@
+pub mod api_key;
+pub mod auth_context;
+pub mod authorization;
+pub mod directory;
 pub mod email;
+pub mod offboarding;
 pub mod password;
+pub mod password_policy;
+pub mod permission;
+pub mod practice;
+pub mod session;
+pub mod token;
 pub mod user;
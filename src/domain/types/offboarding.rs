@@ -0,0 +1,138 @@
+use uuid::Uuid;
+
+// The steps of offboarding a member of staff, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffboardingStep {
+    DisableAccount,
+    RevokeSessions,
+    RevokeApiKeys,
+    ReassignTasks,
+    ReassignAppointments,
+    TransferPatients,
+}
+
+impl OffboardingStep {
+    pub const ALL: [OffboardingStep; 6] = [
+        OffboardingStep::DisableAccount,
+        OffboardingStep::RevokeSessions,
+        OffboardingStep::RevokeApiKeys,
+        OffboardingStep::ReassignTasks,
+        OffboardingStep::ReassignAppointments,
+        OffboardingStep::TransferPatients,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OffboardingStep::DisableAccount => "disable_account",
+            OffboardingStep::RevokeSessions => "revoke_sessions",
+            OffboardingStep::RevokeApiKeys => "revoke_api_keys",
+            OffboardingStep::ReassignTasks => "reassign_tasks",
+            OffboardingStep::ReassignAppointments => "reassign_appointments",
+            OffboardingStep::TransferPatients => "transfer_patients",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|step| step.as_str() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepStatus {
+    Pending,
+    Completed,
+    // Not handled by this service; left to be done by hand
+    Skipped,
+    // Retried when the job is resumed
+    Failed,
+}
+
+impl StepStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "pending",
+            StepStatus::Completed => "completed",
+            StepStatus::Skipped => "skipped",
+            StepStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    InProgress,
+    Failed,
+    Completed,
+    // Every step ran, but some were skipped and still need doing by hand
+    NeedsAttention,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::InProgress => "in_progress",
+            JobStatus::Failed => "failed",
+            JobStatus::Completed => "completed",
+            JobStatus::NeedsAttention => "needs_attention",
+        }
+    }
+
+    // A finished job is not resumed; the next request starts a new one
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::NeedsAttention)
+    }
+}
+
+// Times are Unix seconds
+pub struct StepState {
+    pub step: String,
+    pub status: String,
+    pub detail: Option<String>,
+    pub attempts: i32,
+    pub updated_at: i64,
+}
+
+pub struct OffboardingJob {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub reason: String,
+    pub reassign_to: Option<Uuid>,
+    pub status: String,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    pub steps: Vec<StepState>,
+}
+
+impl OffboardingJob {
+    pub fn response_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "job_id": self.id.to_string(),
+            "user_id": self.user_id.to_string(),
+            "reason": self.reason,
+            "reassign_to": self.reassign_to.map(|id| id.to_string()),
+            "status": self.status,
+            "created_at": self.created_at,
+            "completed_at": self.completed_at,
+            "steps": self.steps.iter().map(|step| serde_json::json!({
+                "step": step.step,
+                "status": step.status,
+                "detail": step.detail,
+                "attempts": step.attempts,
+                "updated_at": step.updated_at,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_names_round_trip() {
+        for step in OffboardingStep::ALL {
+            assert_eq!(OffboardingStep::from_name(step.as_str()), Some(step));
+        }
+        assert_eq!(OffboardingStep::from_name("delete_account"), None);
+    }
+}
@@ -0,0 +1,412 @@
+use std::{collections::HashSet, str::FromStr, sync::OnceLock};
+
+use secrecy::ExposeSecret;
+
+use crate::domain::{error::app_error::ValidationError, types::password::Password};
+
+// Common and breached passwords, one per line; lines starting with '#' are comments
+const BREACHED_PASSWORDS: &str = include_str!("../../../data/breached_passwords.txt");
+
+fn breached_passwords() -> &'static HashSet<String> {
+    static LIST: OnceLock<HashSet<String>> = OnceLock::new();
+    LIST.get_or_init(|| {
+        BREACHED_PASSWORDS
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect()
+    })
+}
+
+// Names and email fragments shorter than this are too common to ban
+const MIN_PERSONAL_INFO_LEN: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CharacterClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    // Anything that is not a letter or a number
+    Symbol,
+}
+
+impl CharacterClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CharacterClass::Lowercase => "lowercase",
+            CharacterClass::Uppercase => "uppercase",
+            CharacterClass::Digit => "digit",
+            CharacterClass::Symbol => "symbol",
+        }
+    }
+
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharacterClass::Lowercase => c.is_lowercase(),
+            CharacterClass::Uppercase => c.is_uppercase(),
+            CharacterClass::Digit => c.is_ascii_digit(),
+            CharacterClass::Symbol => !c.is_alphanumeric(),
+        }
+    }
+
+    fn keycloak_policy(&self) -> &'static str {
+        match self {
+            CharacterClass::Lowercase => "lowerCase(1)",
+            CharacterClass::Uppercase => "upperCase(1)",
+            CharacterClass::Digit => "digits(1)",
+            CharacterClass::Symbol => "specialChars(1)",
+        }
+    }
+}
+
+impl FromStr for CharacterClass {
+    type Err = ValidationError;
+
+    fn from_str(class: &str) -> Result<Self, Self::Err> {
+        match class {
+            "lowercase" => Ok(CharacterClass::Lowercase),
+            "uppercase" => Ok(CharacterClass::Uppercase),
+            "digit" => Ok(CharacterClass::Digit),
+            "symbol" => Ok(CharacterClass::Symbol),
+            other => Err(ValidationError::InvalidInput(format!(
+                "Unknown character class: {other}"
+            ))),
+        }
+    }
+}
+
+// A policy rule a password failed; reported back so clients can show every problem at once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordRule {
+    MinLength(usize),
+    MaxLength(usize),
+    RequiresClass(CharacterClass),
+    ContainsPersonalInfo,
+    RecentlyUsed(u32),
+    Breached,
+}
+
+impl PasswordRule {
+    pub fn code(&self) -> String {
+        match self {
+            PasswordRule::MinLength(_) => "min_length".into(),
+            PasswordRule::MaxLength(_) => "max_length".into(),
+            PasswordRule::RequiresClass(class) => format!("requires_{}", class.as_str()),
+            PasswordRule::ContainsPersonalInfo => "personal_info".into(),
+            PasswordRule::RecentlyUsed(_) => "recently_used".into(),
+            PasswordRule::Breached => "breached".into(),
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            PasswordRule::MinLength(min) => format!("Must be at least {min} characters long"),
+            PasswordRule::MaxLength(max) => format!("Must be at most {max} characters long"),
+            PasswordRule::RequiresClass(CharacterClass::Lowercase) => {
+                "Must contain a lowercase letter".into()
+            }
+            PasswordRule::RequiresClass(CharacterClass::Uppercase) => {
+                "Must contain an uppercase letter".into()
+            }
+            PasswordRule::RequiresClass(CharacterClass::Digit) => "Must contain a number".into(),
+            PasswordRule::RequiresClass(CharacterClass::Symbol) => {
+                "Must contain a special character".into()
+            }
+            PasswordRule::ContainsPersonalInfo => {
+                "Must not contain your email address or name".into()
+            }
+            PasswordRule::RecentlyUsed(count) => {
+                format!("Must not match any of your last {count} passwords")
+            }
+            PasswordRule::Breached => "Must not be a commonly used or breached password".into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PasswordPolicy {
+    // Lengths count characters, not bytes
+    pub min_length: usize,
+    pub max_length: usize,
+    pub required_classes: Vec<CharacterClass>,
+    pub forbid_personal_info: bool,
+    // How many previous passwords a user may not reuse; 0 turns the check off
+    pub history_size: u32,
+    pub reject_breached: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            required_classes: vec![CharacterClass::Digit, CharacterClass::Symbol],
+            forbid_personal_info: true,
+            history_size: 5,
+            reject_breached: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    // Every rule that can be judged from the password alone; reuse needs the stored history
+    #[tracing::instrument(name = "password_policy_check", skip_all)]
+    pub fn check(&self, password: &Password, personal_info: &[&str]) -> Vec<PasswordRule> {
+        let password = password.as_ref().expose_secret();
+        let mut failed = Vec::new();
+
+        let length = password.chars().count();
+        if length < self.min_length {
+            failed.push(PasswordRule::MinLength(self.min_length));
+        }
+        if length > self.max_length {
+            failed.push(PasswordRule::MaxLength(self.max_length));
+        }
+
+        for class in &self.required_classes {
+            if !password.chars().any(|c| class.matches(c)) {
+                failed.push(PasswordRule::RequiresClass(*class));
+            }
+        }
+
+        let lowered = password.to_lowercase();
+        if self.forbid_personal_info
+            && personal_info
+                .iter()
+                .map(|info| info.trim().to_lowercase())
+                .any(|info| {
+                    info.chars().count() >= MIN_PERSONAL_INFO_LEN && lowered.contains(&info)
+                })
+        {
+            failed.push(PasswordRule::ContainsPersonalInfo);
+        }
+
+        if self.reject_breached && breached_passwords().contains(&lowered) {
+            failed.push(PasswordRule::Breached);
+        }
+
+        failed
+    }
+
+    // The same rules as a Keycloak realm passwordPolicy, for passwords set on Keycloak's
+    // own pages. Keycloak has no match for names or the breached list; those stay ours.
+    pub fn keycloak_policy(&self) -> String {
+        let mut rules = vec![
+            format!("length({})", self.min_length),
+            format!("maxLength({})", self.max_length),
+        ];
+        rules.extend(
+            self.required_classes
+                .iter()
+                .map(|class| class.keycloak_policy().to_string()),
+        );
+        if self.forbid_personal_info {
+            rules.extend(["notUsername".to_string(), "notEmail".to_string()]);
+        }
+        if self.history_size > 0 {
+            rules.push(format!("passwordHistory({})", self.history_size));
+        }
+        rules.join(" and ")
+    }
+}
+
+// The parts of a user's identity a password may not contain
+pub fn personal_info<'a>(email: &'a str, first_name: &'a str, last_name: &'a str) -> Vec<&'a str> {
+    let local_part = email.split('@').next().unwrap_or_default();
+    vec![email, local_part, first_name, last_name]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(password: &str) -> Vec<PasswordRule> {
+        let password = Password::new(password.to_string()).unwrap();
+        PasswordPolicy::default().check(&password, &[])
+    }
+
+    #[test]
+    fn test_valid_passwords_pass_default_policy() {
+        let valid_passwords = vec![
+            "MySecure123@",
+            "Complex9#Pass",
+            "Test1234$",
+            "Strong5%Word",
+            "Abc123!@#",
+            "Pass123!",
+            "Test45@#",
+            "Word9!Ab",
+        ];
+
+        for password in valid_passwords {
+            assert!(check(password).is_empty(), "Should accept: {password}");
+        }
+    }
+
+    #[test]
+    fn test_password_with_various_special_characters() {
+        let special_char_passwords = vec![
+            "Test123@",
+            "Pass9#word",
+            "Secure5$pass",
+            "Valid8%word",
+            "Good2^test",
+            "Nice7&word",
+            "Cool3*pass",
+            "Best1+word",
+            "Top4=pass",
+            "New6|word",
+            "Big8\\test",
+            "Fast2/word",
+            "High5?pass",
+            "Low9<word",
+            "Mid3>test",
+            "End1~pass",
+            "Start7`word",
+        ];
+
+        for password in special_char_passwords {
+            assert!(check(password).is_empty(), "Should accept: {password}");
+        }
+    }
+
+    #[test]
+    fn test_password_too_short() {
+        for password in ["Pass1!", "Abc1@", "1!"] {
+            assert!(
+                check(password).contains(&PasswordRule::MinLength(8)),
+                "Should reject short password: {password}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_password_too_long() {
+        let password = format!("{}1!", "a".repeat(127));
+        assert_eq!(check(&password), vec![PasswordRule::MaxLength(128)]);
+    }
+
+    #[test]
+    fn test_password_no_digit() {
+        for password in ["MySecure@Test", "Complex#Pass", "NoNumbers!@#"] {
+            assert_eq!(
+                check(password),
+                vec![PasswordRule::RequiresClass(CharacterClass::Digit)],
+                "Should reject password without digit: {password}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_password_no_special_character() {
+        for password in ["MySecure123Test", "Complex9Pass", "OnlyAlphaNum123"] {
+            assert_eq!(
+                check(password),
+                vec![PasswordRule::RequiresClass(CharacterClass::Symbol)],
+                "Should reject password without special character: {password}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_password_with_unicode_characters() {
+        for password in ["Password1🔒", "Test123→", "Passw9♠️"] {
+            assert!(check(password).is_empty(), "Should accept: {password}");
+        }
+    }
+
+    #[test]
+    fn test_length_counts_characters() {
+        // Six characters, well over eight bytes
+        assert!(check("Ünï9→🔒").contains(&PasswordRule::MinLength(8)));
+    }
+
+    #[test]
+    fn test_reports_every_failed_rule() {
+        assert_eq!(
+            check("abc"),
+            vec![
+                PasswordRule::MinLength(8),
+                PasswordRule::RequiresClass(CharacterClass::Digit),
+                PasswordRule::RequiresClass(CharacterClass::Symbol),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_realm_file_matches_default_policy() {
+        let realm: serde_json::Value =
+            serde_json::from_str(include_str!("../../../keycloak/ehr-realm.json")).unwrap();
+        assert_eq!(
+            realm["passwordPolicy"],
+            PasswordPolicy::default().keycloak_policy()
+        );
+    }
+
+    #[test]
+    fn test_required_classes_are_configurable() {
+        let policy = PasswordPolicy {
+            required_classes: vec![CharacterClass::Lowercase, CharacterClass::Uppercase],
+            ..Default::default()
+        };
+        let password = Password::new("lowercase only".to_string()).unwrap();
+
+        assert_eq!(
+            policy.check(&password, &[]),
+            vec![PasswordRule::RequiresClass(CharacterClass::Uppercase)]
+        );
+    }
+
+    #[test]
+    fn test_rejects_personal_info() {
+        let policy = PasswordPolicy::default();
+        let info = personal_info("jane.doe@example.com", "Jane", "Doe");
+
+        for password in ["Jane2024!!", "x!jane.doe99", "9#JANE.DOE@example.com"] {
+            let password = Password::new(password.to_string()).unwrap();
+            assert_eq!(
+                policy.check(&password, &info),
+                vec![PasswordRule::ContainsPersonalInfo]
+            );
+        }
+
+        // Two-letter names are too short to ban
+        let info = personal_info("jo@example.com", "Jo", "Li");
+        let password = Password::new("Jolie#2024".to_string()).unwrap();
+        assert!(policy.check(&password, &info).is_empty());
+    }
+
+    #[test]
+    fn test_rejects_breached_passwords_case_insensitively() {
+        assert_eq!(check("P@ssw0rd"), vec![PasswordRule::Breached]);
+        assert_eq!(check("p@SSW0RD"), vec![PasswordRule::Breached]);
+    }
+
+    #[test]
+    fn test_disabled_rules_are_skipped() {
+        let policy = PasswordPolicy {
+            min_length: 1,
+            required_classes: Vec::new(),
+            forbid_personal_info: false,
+            reject_breached: false,
+            ..Default::default()
+        };
+        let password = Password::new("password".to_string()).unwrap();
+
+        assert!(policy.check(&password, &["password"]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_character_class() {
+        for class in [
+            CharacterClass::Lowercase,
+            CharacterClass::Uppercase,
+            CharacterClass::Digit,
+            CharacterClass::Symbol,
+        ] {
+            assert_eq!(class.as_str().parse::<CharacterClass>().unwrap(), class);
+        }
+        assert!("emoji".parse::<CharacterClass>().is_err());
+    }
+}
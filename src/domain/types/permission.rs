@@ -0,0 +1,163 @@
+use crate::domain::types::user::UserRole;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    ReadUsers,
+    ManageUsers,
+    // List and revoke other users' sessions
+    ManageSessions,
+    // Create practices and move users between them
+    ManagePractices,
+    // Create, list and revoke API keys for system integrations
+    ManageApiKeys,
+    // Permanently remove accounts; everyone else can only disable them
+    DeleteUsers,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::ReadUsers => "users:read",
+            Permission::ManageUsers => "users:manage",
+            Permission::ManageSessions => "sessions:manage",
+            Permission::ManagePractices => "practices:manage",
+            Permission::ManageApiKeys => "api_keys:manage",
+            Permission::DeleteUsers => "users:delete",
+        }
+    }
+}
+
+pub struct RolePolicy {
+    pub role: UserRole,
+    pub permissions: &'static [Permission],
+    // Whether logging in takes a second factor on top of the password
+    pub requires_mfa: bool,
+}
+
+// Single source of truth for what each EHR role may do
+pub const ROLE_POLICIES: [RolePolicy; 4] = [
+    RolePolicy {
+        role: UserRole::Owner,
+        permissions: &[
+            Permission::ReadUsers,
+            Permission::ManageUsers,
+            Permission::ManageSessions,
+            Permission::ManagePractices,
+            Permission::ManageApiKeys,
+            Permission::DeleteUsers,
+        ],
+        requires_mfa: true,
+    },
+    RolePolicy {
+        role: UserRole::Admin,
+        permissions: &[Permission::ReadUsers, Permission::ManageUsers],
+        requires_mfa: true,
+    },
+    RolePolicy {
+        role: UserRole::Biller,
+        permissions: &[Permission::ReadUsers],
+        requires_mfa: false,
+    },
+    RolePolicy {
+        role: UserRole::Clinician,
+        permissions: &[Permission::ReadUsers],
+        requires_mfa: false,
+    },
+];
+
+impl UserRole {
+    pub fn policy(&self) -> &'static RolePolicy {
+        ROLE_POLICIES
+            .iter()
+            .find(|policy| policy.role == *self)
+            .expect("Every role must have an entry in ROLE_POLICIES")
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.policy().permissions.contains(&permission)
+    }
+
+    pub fn requires_mfa(&self) -> bool {
+        self.policy().requires_mfa
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_role_has_a_policy() {
+        for role in UserRole::ALL {
+            assert_eq!(role.policy().role, role);
+        }
+        assert_eq!(ROLE_POLICIES.len(), UserRole::ALL.len());
+    }
+
+    #[test]
+    fn test_manage_users_requires_owner_or_admin() {
+        assert!(UserRole::Owner.has_permission(Permission::ManageUsers));
+        assert!(UserRole::Admin.has_permission(Permission::ManageUsers));
+        assert!(!UserRole::Biller.has_permission(Permission::ManageUsers));
+        assert!(!UserRole::Clinician.has_permission(Permission::ManageUsers));
+    }
+
+    #[test]
+    fn test_manage_sessions_is_owner_only() {
+        for role in UserRole::ALL {
+            assert_eq!(
+                role.has_permission(Permission::ManageSessions),
+                role == UserRole::Owner,
+                "{role:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_manage_practices_is_owner_only() {
+        for role in UserRole::ALL {
+            assert_eq!(
+                role.has_permission(Permission::ManagePractices),
+                role == UserRole::Owner,
+                "{role:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_manage_api_keys_is_owner_only() {
+        for role in UserRole::ALL {
+            assert_eq!(
+                role.has_permission(Permission::ManageApiKeys),
+                role == UserRole::Owner,
+                "{role:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_delete_users_is_owner_only() {
+        for role in UserRole::ALL {
+            assert_eq!(
+                role.has_permission(Permission::DeleteUsers),
+                role == UserRole::Owner,
+                "{role:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_mfa_required_for_owner_and_admin_only() {
+        assert!(UserRole::Owner.requires_mfa());
+        assert!(UserRole::Admin.requires_mfa());
+        assert!(!UserRole::Biller.requires_mfa());
+        assert!(!UserRole::Clinician.requires_mfa());
+    }
+
+    #[test]
+    fn test_all_roles_can_read_users() {
+        for role in UserRole::ALL {
+            assert!(role.has_permission(Permission::ReadUsers), "{role:?}");
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use uuid::Uuid;
+
+// Each practice is a Keycloak group named with this prefix and the practice id, and
+// membership reaches access tokens through the `groups` claim
+const GROUP_PREFIX: &str = "practice-";
+
+// A clinic; every user record belongs to at most one
+#[derive(Debug, Clone)]
+pub struct Practice {
+    pub id: Uuid,
+    pub name: String,
+}
+
+impl Practice {
+    pub fn response_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "practice_id": self.id.to_string(),
+            "name": self.name,
+        })
+    }
+}
+
+pub fn group_name(practice_id: Uuid) -> String {
+    format!("{GROUP_PREFIX}{practice_id}")
+}
+
+// The practice named by a user's group memberships, if any
+pub fn from_groups<S: AsRef<str>>(groups: &[S]) -> Option<Uuid> {
+    groups.iter().find_map(|group| {
+        // Full group paths arrive with a leading slash
+        let name = group.as_ref().trim_start_matches('/');
+        name.strip_prefix(GROUP_PREFIX)
+            .and_then(|id| Uuid::parse_str(id).ok())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_practice_round_trips_through_group_name() {
+        let id = Uuid::new_v4();
+        assert_eq!(from_groups(&[group_name(id)]), Some(id));
+        assert_eq!(from_groups(&[format!("/{}", group_name(id))]), Some(id));
+    }
+
+    #[test]
+    fn test_other_groups_are_ignored() {
+        let id = Uuid::new_v4();
+        let groups = [
+            "billing-team".to_string(),
+            "practice-not-a-uuid".to_string(),
+        ];
+        assert_eq!(from_groups(&groups), None);
+
+        let groups = ["billing-team".to_string(), group_name(id)];
+        assert_eq!(from_groups(&groups), Some(id));
+    }
+}
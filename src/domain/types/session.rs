@@ -0,0 +1,18 @@
+// A session the user holds with the auth provider; times are Unix seconds
+pub struct UserSession {
+    pub session_id: String,
+    pub ip_address: Option<String>,
+    pub started_at: u64,
+    pub last_access_at: u64,
+}
+
+impl UserSession {
+    pub fn response_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "session_id": self.session_id,
+            "ip_address": self.ip_address,
+            "started_at": self.started_at,
+            "last_access_at": self.last_access_at,
+        })
+    }
+}
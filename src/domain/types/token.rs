@@ -0,0 +1,117 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+
+use crate::domain::error::app_error::{AppResult, AuthProviderError};
+
+pub struct AuthTokens {
+    pub access_token: SecretString,
+    pub refresh_token: SecretString,
+    pub id_token: Option<SecretString>,
+    pub token_type: String,
+    pub expires_in: u64,
+    pub refresh_expires_in: u64,
+    // Provider session the tokens belong to, if the provider reports one
+    pub session_id: Option<String>,
+}
+
+impl AuthTokens {
+    pub fn response_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "access_token": self.access_token.expose_secret(),
+            "refresh_token": self.refresh_token.expose_secret(),
+            "id_token": self.id_token.as_ref().map(|t| t.expose_secret()),
+            "token_type": self.token_type,
+            "expires_in": self.expires_in,
+            "refresh_expires_in": self.refresh_expires_in,
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RealmAccess {
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    pub preferred_username: Option<String>,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+    pub email_verified: Option<bool>,
+    // Echoes the nonce of the authorization request that produced an ID token
+    pub nonce: Option<String>,
+    #[serde(default)]
+    pub realm_access: RealmAccess,
+    // Group names, which carry practice membership
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+impl TokenClaims {
+    // Reads the payload of a JWT without checking its signature. Only use this on
+    // tokens received directly from the auth provider's token endpoint.
+    pub fn decode_unverified(token: &str) -> AppResult<Self> {
+        let payload = token.split('.').nth(1).ok_or(AuthProviderError::Upstream(
+            "Malformed token returned by auth provider".to_string(),
+        ))?;
+        let bytes = URL_SAFE_NO_PAD.decode(payload).map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to decode token payload: {e}"))
+        })?;
+        let claims = serde_json::from_slice(&bytes).map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to parse token claims: {e}"))
+        })?;
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_token(payload: serde_json::Value) -> String {
+        format!(
+            "{}.{}.signature",
+            URL_SAFE_NO_PAD.encode(br#"{"alg":"RS256","typ":"JWT"}"#),
+            URL_SAFE_NO_PAD.encode(payload.to_string())
+        )
+    }
+
+    #[test]
+    fn test_decode_unverified_claims() {
+        let token = encode_token(serde_json::json!({
+            "sub": "1b4e28ba-2fa1-11d2-883f-0016d3cca427",
+            "email": "test@example.com",
+            "given_name": "Test",
+            "family_name": "User",
+            "realm_access": { "roles": ["clinician", "offline_access"] }
+        }));
+
+        let claims = TokenClaims::decode_unverified(&token).unwrap();
+
+        assert_eq!(claims.sub, "1b4e28ba-2fa1-11d2-883f-0016d3cca427");
+        assert_eq!(claims.email.as_deref(), Some("test@example.com"));
+        assert_eq!(
+            claims.realm_access.roles,
+            vec!["clinician", "offline_access"]
+        );
+    }
+
+    #[test]
+    fn test_decode_unverified_without_realm_access() {
+        let token = encode_token(serde_json::json!({ "sub": "abc" }));
+
+        let claims = TokenClaims::decode_unverified(&token).unwrap();
+
+        assert!(claims.realm_access.roles.is_empty());
+    }
+
+    #[test]
+    fn test_decode_unverified_rejects_malformed_token() {
+        assert!(TokenClaims::decode_unverified("not-a-token").is_err());
+        assert!(TokenClaims::decode_unverified("a.%%%.c").is_err());
+    }
+}
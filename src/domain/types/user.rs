@@ -1,15 +1,27 @@
-use crate::domain::types::{email::Email, password::Password};
+use std::str::FromStr;
+
+use crate::domain::{
+    error::app_error::ValidationError,
+    types::{email::Email, password::Password},
+};
 
 use secrecy::ExposeSecret;
+use uuid::Uuid;
 
+#[derive(Clone)]
 pub struct User {
     pub user_id: Option<String>,
     pub username: String,
     pub email: Email,
-    pub password: Password,
+    // Only known when the user is being created; never read back from the provider
+    pub password: Option<Password>,
     pub first_name: String,
     pub last_name: String,
     pub role: Option<UserRole>,
+    pub email_verified: bool,
+    pub practice_id: Option<Uuid>,
+    // Disabled accounts cannot sign in but keep their identity for the audit trail
+    pub enabled: bool,
 }
 
 impl User {
@@ -25,10 +37,13 @@ impl User {
             user_id: None,
             username,
             email,
-            password,
+            password: Some(password),
             first_name,
             last_name,
             role,
+            email_verified: false,
+            practice_id: None,
+            enabled: true,
         }
     }
 
@@ -40,7 +55,7 @@ impl User {
             self.email = email;
         }
         if let Some(password) = update.password {
-            self.password = password;
+            self.password = Some(password);
         }
         if let Some(first_name) = update.first_name {
             self.first_name = first_name;
@@ -49,11 +64,33 @@ impl User {
             self.last_name = last_name;
         }
         if let Some(role) = update.role {
-            self.role = Some(role);
+            self.role = role;
+        }
+        if let Some(email_verified) = update.email_verified {
+            self.email_verified = email_verified;
+        }
+        if let Some(practice_id) = update.practice_id {
+            self.practice_id = practice_id;
+        }
+        if let Some(enabled) = update.enabled {
+            self.enabled = enabled;
         }
     }
 
+    // Unverified users get Keycloak's VERIFY_EMAIL required action, which blocks login
     pub fn signup_json(&self, enabled: bool, verified: bool) -> serde_json::Value {
+        let credentials: Vec<serde_json::Value> = self
+            .password
+            .iter()
+            .map(|password| {
+                serde_json::json!({
+                    "type": "password",
+                    "value": password.as_ref().expose_secret(),
+                    "temporary": false
+                })
+            })
+            .collect();
+
         serde_json::json!({
             "username": self.username,
             "email": self.email.as_ref().expose_secret(),
@@ -61,27 +98,241 @@ impl User {
             "lastName": self.last_name,
             "enabled": enabled,
             "emailVerified": verified,
-            "credentials": [{
-                "type": "password",
-                "value": self.password.as_ref().expose_secret(),
-                "temporary": false
-            }],
+            "requiredActions": if verified { vec![] } else { vec!["VERIFY_EMAIL"] },
+            "credentials": credentials,
+        })
+    }
+
+    // Public view of the user returned by the API
+    pub fn response_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "user_id": self.user_id,
+            "email": self.email.display().expose_secret(),
+            "first_name": self.first_name,
+            "last_name": self.last_name,
+            "role": self.role.map(|r| r.as_str()),
+            "email_verified": self.email_verified,
+            "practice_id": self.practice_id.map(|id| id.to_string()),
+            "enabled": self.enabled,
         })
     }
 }
 
+#[derive(Default)]
 pub struct UserUpdate {
     pub user_id: Option<String>,
     pub email: Option<Email>,
     pub password: Option<Password>,
     pub first_name: Option<String>,
     pub last_name: Option<String>,
-    pub role: Option<UserRole>,
+    // Some(None) takes the user's EHR role away
+    pub role: Option<Option<UserRole>>,
+    pub email_verified: Option<bool>,
+    // Moves the user into this practice; Some(None) removes them from any practice
+    pub practice_id: Option<Option<Uuid>>,
+    pub enabled: Option<bool>,
 }
 
+impl UserUpdate {
+    // Partial user representation for the profile fields being changed, if any
+    pub fn profile_json(&self) -> Option<serde_json::Value> {
+        let mut profile = serde_json::Map::new();
+        if let Some(email) = &self.email {
+            profile.insert("email".into(), email.as_ref().expose_secret().into());
+        }
+        if let Some(first_name) = &self.first_name {
+            profile.insert("firstName".into(), first_name.clone().into());
+        }
+        if let Some(last_name) = &self.last_name {
+            profile.insert("lastName".into(), last_name.clone().into());
+        }
+        if let Some(email_verified) = self.email_verified {
+            profile.insert("emailVerified".into(), email_verified.into());
+            // Verifying on the user's behalf also lifts the pending VERIFY_EMAIL action,
+            // while an unproven address gets one
+            let actions: &[&str] = if email_verified {
+                &[]
+            } else {
+                &["VERIFY_EMAIL"]
+            };
+            profile.insert("requiredActions".into(), serde_json::json!(actions));
+        }
+        if let Some(enabled) = self.enabled {
+            profile.insert("enabled".into(), enabled.into());
+        }
+
+        (!profile.is_empty()).then_some(serde_json::Value::Object(profile))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum UserRole {
     Owner,
     Admin,
     Biller,
     Clinician,
 }
+
+impl UserRole {
+    // Ordered from most to least privileged
+    pub const ALL: [UserRole; 4] = [
+        UserRole::Owner,
+        UserRole::Admin,
+        UserRole::Biller,
+        UserRole::Clinician,
+    ];
+
+    // Name of the matching realm role in keycloak/ehr-realm.json
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Owner => "owner",
+            UserRole::Admin => "admin",
+            UserRole::Biller => "biller",
+            UserRole::Clinician => "clinician",
+        }
+    }
+
+    pub fn from_realm_role(role: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.as_str() == role)
+    }
+
+    // Picks the most privileged EHR role out of a token's realm roles
+    pub fn from_realm_roles<S: AsRef<str>>(roles: &[S]) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|r| roles.iter().any(|role| role.as_ref() == r.as_str()))
+    }
+}
+
+impl FromStr for UserRole {
+    type Err = ValidationError;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        Self::from_realm_role(role)
+            .ok_or_else(|| ValidationError::InvalidInput(format!("Unknown role: {role}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_update() -> UserUpdate {
+        UserUpdate {
+            user_id: Some("1b4e28ba-2fa1-11d2-883f-0016d3cca427".to_string()),
+            email: None,
+            password: None,
+            first_name: None,
+            last_name: None,
+            role: None,
+            email_verified: None,
+            practice_id: None,
+            enabled: None,
+        }
+    }
+
+    #[test]
+    fn test_profile_json_only_contains_changed_fields() {
+        let update = UserUpdate {
+            email: Some(Email::new("new@example.com".to_string()).unwrap()),
+            last_name: Some("Smith".to_string()),
+            ..empty_update()
+        };
+
+        assert_eq!(
+            update.profile_json(),
+            Some(serde_json::json!({
+                "email": "new@example.com",
+                "lastName": "Smith"
+            }))
+        );
+    }
+
+    #[test]
+    fn test_profile_json_none_without_profile_changes() {
+        let update = UserUpdate {
+            password: Some(Password::new("Password1!".to_string()).unwrap()),
+            ..empty_update()
+        };
+
+        assert!(update.profile_json().is_none());
+    }
+
+    #[test]
+    fn test_profile_json_marking_unverified_requires_verification() {
+        let update = UserUpdate {
+            email_verified: Some(false),
+            ..empty_update()
+        };
+
+        assert_eq!(
+            update.profile_json(),
+            Some(serde_json::json!({
+                "emailVerified": false,
+                "requiredActions": ["VERIFY_EMAIL"]
+            }))
+        );
+    }
+
+    #[test]
+    fn test_profile_json_marking_verified_clears_required_actions() {
+        let update = UserUpdate {
+            email_verified: Some(true),
+            ..empty_update()
+        };
+
+        assert_eq!(
+            update.profile_json(),
+            Some(serde_json::json!({
+                "emailVerified": true,
+                "requiredActions": []
+            }))
+        );
+    }
+
+    #[test]
+    fn test_role_round_trip() {
+        for role in UserRole::ALL {
+            assert_eq!(UserRole::from_realm_role(role.as_str()), Some(role));
+        }
+        assert_eq!(UserRole::from_realm_role("offline_access"), None);
+    }
+
+    #[test]
+    fn test_role_from_str() {
+        assert_eq!("biller".parse::<UserRole>().ok(), Some(UserRole::Biller));
+        assert!("superuser".parse::<UserRole>().is_err());
+        assert!("Admin".parse::<UserRole>().is_err());
+    }
+
+    #[test]
+    fn test_signup_json_without_password_has_no_credentials() {
+        let mut user = User::new(
+            "test@example.com".to_string(),
+            Email::new("test@example.com".to_string()).unwrap(),
+            Password::new("Password1!".to_string()).unwrap(),
+            "Test".to_string(),
+            "User".to_string(),
+            None,
+        );
+        assert_eq!(
+            user.signup_json(true, true)["credentials"][0]["value"],
+            "Password1!"
+        );
+
+        user.password = None;
+        assert_eq!(
+            user.signup_json(true, true)["credentials"],
+            serde_json::json!([])
+        );
+    }
+
+    #[test]
+    fn test_from_realm_roles_picks_most_privileged() {
+        let roles = vec!["default-roles-ehr", "clinician", "admin"];
+        assert_eq!(UserRole::from_realm_roles(&roles), Some(UserRole::Admin));
+
+        let roles: Vec<String> = vec!["offline_access".into()];
+        assert_eq!(UserRole::from_realm_roles(&roles), None);
+    }
+}
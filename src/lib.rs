@@ -5,7 +5,7 @@ use poem::{
     EndpointExt, Route, Server,
     http::Method,
     listener::{self, Listener, RustlsCertificate, RustlsConfig},
-    middleware::{Cors, Tracing},
+    middleware::{CookieJarManager, Cors, Tracing},
 };
 use poem_openapi::OpenApiService;
 use secrecy::ExposeSecret;
@@ -13,9 +13,18 @@ use sqlx::postgres::PgPoolOptions;
 use tokio::sync::RwLock;
 
 use crate::{
-    services::keycloak_auth_provider::{KeycloakEndpoints, KeycloakUserStore},
+    domain::{interfaces::mailer::Mailer, types::password_policy::PasswordPolicy},
+    services::{
+        in_memory_auth_provider::InMemoryAuthProvider,
+        jwks_token_validator::JwksTokenValidator,
+        keycloak_auth_provider::{KeycloakEndpoints, KeycloakUserStore},
+        smtp_mailer::SmtpMailer,
+    },
     state::AppState,
-    utils::config::AppSettings,
+    utils::{
+        auth::BearerAuth,
+        config::{AppSettings, AuthProviderKind},
+    },
 };
 
 pub mod api;
@@ -39,19 +48,60 @@ impl EHRApp {
             .await
             .expect("Failed to connect to the database");
 
-        let auth_provider = KeycloakUserStore::new(
-            reqwest::Client::new(),
-            KeycloakEndpoints::from_config(&config),
-        );
-
-        let state = AppState::new(
-            Arc::new(RwLock::new(auth_provider)),
-            Arc::new(RwLock::new(db)),
-        );
+        let mailer: Arc<dyn Mailer + Send + Sync> =
+            Arc::new(SmtpMailer::from_config(&config).expect("Invalid SMTP settings"));
+
+        let state = match config.auth_provider {
+            AuthProviderKind::Keycloak => {
+                let http_client = reqwest::Client::new();
+                let endpoints = KeycloakEndpoints::from_config(&config);
+
+                let token_validator = JwksTokenValidator::new(
+                    http_client.clone(),
+                    endpoints.jwks_endpoint.clone(),
+                    config.keycloak_issuer.clone(),
+                    config.keycloak_audience.clone(),
+                );
+
+                let auth_provider = KeycloakUserStore::new(http_client, endpoints);
+
+                let realm_policy = config.password_policy.keycloak_policy();
+                if realm_policy != PasswordPolicy::default().keycloak_policy() {
+                    tracing::warn!(
+                        %realm_policy,
+                        "Password policy differs from keycloak/ehr-realm.json; set the realm's passwordPolicy to match"
+                    );
+                }
+
+                AppState::new(
+                    Arc::new(RwLock::new(auth_provider)),
+                    Arc::new(RwLock::new(db)),
+                    Arc::new(token_validator),
+                    mailer,
+                    Arc::new(config.clone()),
+                )
+            }
+            AuthProviderKind::InMemory => {
+                // The provider issues opaque tokens, so it validates them as well
+                let auth_provider = InMemoryAuthProvider::new(mailer.clone());
+
+                AppState::new(
+                    Arc::new(RwLock::new(auth_provider.clone())),
+                    Arc::new(RwLock::new(db)),
+                    Arc::new(auth_provider),
+                    mailer,
+                    Arc::new(config.clone()),
+                )
+            }
+        };
 
         EHRApp { config, state }
     }
 
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
     pub async fn run(&self) -> Result<()> {
         tracing::info!("Starting EHR API server on {}", self.config.app_address());
 
@@ -60,6 +110,13 @@ impl EHRApp {
             .await
             .expect("Failed to run database migrations");
 
+        let sealed = services::mfa::seal_plaintext_secrets(&self.state)
+            .await
+            .expect("Failed to encrypt stored TOTP secrets");
+        if sealed > 0 {
+            tracing::info!(sealed, "Encrypted stored TOTP secrets");
+        }
+
         // OpenAPI
         let api_service = OpenApiService::new(EHRApi, "EHR API", "1.0")
             .server(format!("http://{}/api", self.config.app_address()));
@@ -70,14 +127,24 @@ impl EHRApp {
         let cors = Cors::new()
             .allow_origin("https://localhost:3000")
             .allow_origin("https://127.0.0.1:3000")
-            .allow_methods(vec![Method::GET, Method::POST, Method::PUT, Method::DELETE])
-            .allow_headers(vec!["Authorization", "Content-Type"])
+            .allow_methods(vec![
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ])
+            .allow_headers(vec!["Authorization", "Content-Type", "X-CSRF-Token"])
+            // The session cookie only rides along on credentialed requests
+            .allow_credentials(true)
             .expose_headers(vec!["Content-Length"])
             .max_age(3600);
 
         let app = Route::new()
             .nest("/api", api_service)
             .nest("/docs", ui)
+            .with(BearerAuth)
+            .with(CookieJarManager::new())
             .with(cors)
             .with(Tracing)
             .data(self.state.clone());
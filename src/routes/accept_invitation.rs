@@ -0,0 +1,44 @@
+use poem::web::Data;
+use poem_openapi::{Object, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::{error::app_error::AppResult, types::password::Password},
+    services::invitations::{self, InvitationAcceptance},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct AcceptInvitationRequest {
+    /// Token from the invitation link
+    pub token: String,
+    pub password: String,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+pub async fn accept_invitation_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    payload: Json<AcceptInvitationRequest>,
+) -> AppResult<Value> {
+    let password = Password::new(payload.password.clone())?;
+
+    let user_id = invitations::accept(
+        &state,
+        ctx,
+        InvitationAcceptance {
+            token: payload.token.clone(),
+            password,
+            first_name: payload.first_name.clone(),
+            last_name: payload.last_name.clone(),
+        },
+    )
+    .await?;
+
+    Ok(serde_json::json!({
+        "user_id": user_id,
+        "message": "Invitation accepted successfully"
+    }))
+}
@@ -0,0 +1,34 @@
+use poem::web::Data;
+use poem_openapi::{Object, param::Path, payload::Json};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    domain::error::app_error::{AppResult, AuthProviderError},
+    services::practices,
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct AssignPracticeRequest {
+    pub practice_id: String,
+}
+
+pub async fn assign_practice_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    user_id: Path<String>,
+    payload: Json<AssignPracticeRequest>,
+) -> AppResult<Value> {
+    let practice_id =
+        Uuid::parse_str(&payload.practice_id).map_err(|_| AuthProviderError::PracticeNotFound)?;
+
+    practices::assign(&state, ctx, &user_id, practice_id).await?;
+
+    Ok(serde_json::json!({
+        "user_id": user_id.0,
+        "practice_id": practice_id.to_string(),
+        "message": "User moved to practice successfully"
+    }))
+}
@@ -0,0 +1,12 @@
+use poem::web::Data;
+use poem_openapi::param::Query;
+
+use crate::{domain::error::app_error::AppResult, services::authorization_code, state::AppState};
+
+// Returns the provider's sign-in URL for the browser to be redirected to
+pub async fn authorize_impl(
+    state: Data<&AppState>,
+    return_to: Query<Option<String>>,
+) -> AppResult<String> {
+    authorization_code::begin(&state, return_to.0).await
+}
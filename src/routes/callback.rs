@@ -0,0 +1,28 @@
+use poem::web::{Data, cookie::CookieJar};
+use poem_openapi::param::Query;
+
+use crate::{
+    domain::error::app_error::AppResult,
+    services::{authorization_code, web_sessions},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+// Opens the session and returns where to send the browser next
+pub async fn callback_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    cookie_jar: &CookieJar,
+    code: Query<Option<String>>,
+    login_state: Query<String>,
+    error: Query<Option<String>>,
+) -> AppResult<String> {
+    if let Some(error) = &error.0 {
+        tracing::warn!(%error, "Auth provider reported a failed sign-in");
+    }
+
+    let sign_in = authorization_code::complete(&state, ctx, &login_state, code.0).await?;
+    web_sessions::set_cookies(cookie_jar, &sign_in.session);
+
+    Ok(sign_in.return_to)
+}
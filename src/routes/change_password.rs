@@ -0,0 +1,58 @@
+use poem::web::Data;
+use poem_openapi::{Object, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, ValidationError},
+        types::{password::Password, user::UserUpdate},
+    },
+    services::{login_throttle, user_sync},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+pub async fn change_password_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    payload: Json<ChangePasswordRequest>,
+) -> AppResult<Value> {
+    let auth = ctx.require_auth()?;
+
+    let new_password = Password::new(payload.new_password.clone())?;
+    if payload.new_password == payload.current_password {
+        return Err(AppError::Validation(ValidationError::InvalidInput(
+            "New password must differ from the current password".to_string(),
+        )));
+    }
+
+    let user = state
+        .auth_provider
+        .read()
+        .await
+        .get_user(auth.subject.clone())
+        .await?;
+    login_throttle::verify_current_password(&state, ctx, &user, &payload.current_password).await?;
+
+    // Goes through the sync so the policy sees this user's history and the new password joins it
+    user_sync::update_user(
+        &state,
+        &user,
+        UserUpdate {
+            user_id: Some(auth.subject.clone()),
+            password: Some(new_password),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    Ok(serde_json::json!({
+        "message": "Password changed successfully"
+    }))
+}
@@ -0,0 +1,36 @@
+use poem::web::Data;
+use poem_openapi::{Object, param::Path, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::{
+        error::app_error::{AppResult, MfaError},
+        types::auth_context::PendingMfa,
+    },
+    services::mfa,
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct ConfirmTotpRequest {
+    /// Current code shown by the authenticator app
+    pub code: String,
+}
+
+pub async fn confirm_totp_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    factor_id: Path<String>,
+    payload: Json<ConfirmTotpRequest>,
+) -> AppResult<Value> {
+    let auth = ctx.require_auth_at(PendingMfa::Enroll)?;
+    let factor_id = uuid::Uuid::parse_str(&factor_id).map_err(|_| MfaError::FactorNotFound)?;
+
+    mfa::confirm_totp(&state, ctx, &auth.subject, factor_id, &payload.code).await?;
+
+    Ok(serde_json::json!({
+        "factor_id": factor_id.to_string(),
+        "message": "Authenticator enrolled successfully"
+    }))
+}
@@ -0,0 +1,47 @@
+use poem::web::Data;
+use poem_openapi::{Object, payload::Json};
+use secrecy::ExposeSecret;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::{AppResult, ValidationError},
+    services::api_keys,
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct CreateApiKeyRequest {
+    /// Names the integration the key is for, e.g. "Lab interface"
+    pub name: String,
+    /// Scopes of the form resource:action, e.g. patients:read
+    pub scopes: Vec<String>,
+    /// Lifetime in seconds; defaults to 90 days and may not exceed a year
+    pub expires_in: Option<u64>,
+}
+
+pub async fn create_api_key_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    payload: Json<CreateApiKeyRequest>,
+) -> AppResult<Value> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        Err(ValidationError::InvalidInput(
+            "API key name cannot be empty".to_string(),
+        ))?;
+    }
+
+    let created = api_keys::create(
+        &state,
+        ctx,
+        name.to_string(),
+        payload.scopes.clone(),
+        payload.expires_in,
+    )
+    .await?;
+
+    let mut response = created.api_key.response_json();
+    response["key"] = created.key.expose_secret().into();
+    Ok(response)
+}
@@ -0,0 +1,42 @@
+use poem::web::Data;
+use poem_openapi::{Object, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::{
+        error::app_error::AppResult,
+        types::{email::Email, user::UserRole},
+    },
+    services::invitations,
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct CreateInvitationRequest {
+    pub email: String,
+    /// EHR realm role the account is created with
+    pub role: String,
+}
+
+pub async fn create_invitation_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    payload: Json<CreateInvitationRequest>,
+) -> AppResult<Value> {
+    let email = Email::parse(
+        payload.email.clone(),
+        state.settings.email_case_insensitive_local_part,
+    )?;
+    let role = payload.role.parse::<UserRole>()?;
+    ctx.require_auth()?.require_role_assignment(role)?;
+
+    let invitation = invitations::create(&state, ctx, email, role).await?;
+
+    Ok(serde_json::json!({
+        "invitation_id": invitation.id.to_string(),
+        "role": role.as_str(),
+        "expires_in": invitation.expires_in,
+        "message": "Invitation sent successfully"
+    }))
+}
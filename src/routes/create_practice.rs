@@ -0,0 +1,32 @@
+use poem::web::Data;
+use poem_openapi::{Object, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::{AppResult, ValidationError},
+    services::practices,
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct CreatePracticeRequest {
+    pub name: String,
+}
+
+pub async fn create_practice_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    payload: Json<CreatePracticeRequest>,
+) -> AppResult<Value> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        Err(ValidationError::InvalidInput(
+            "Practice name cannot be empty".to_string(),
+        ))?;
+    }
+
+    let practice = practices::create(&state, ctx, name.to_string()).await?;
+
+    Ok(practice.response_json())
+}
@@ -4,7 +4,9 @@ use serde_json::Value;
 
 use crate::{
     domain::error::app_error::{AppError, AppResult},
+    services::{account_status, practices, user_sync},
     state::AppState,
+    utils::tracing::RequestContext,
 };
 
 #[derive(Object, Debug)]
@@ -13,6 +15,7 @@ pub struct DeleteUserRequest {
 }
 
 pub async fn delete_user_impl(
+    ctx: &RequestContext,
     state: Data<&AppState>,
     payload: Json<DeleteUserRequest>,
 ) -> AppResult<Value> {
@@ -24,12 +27,11 @@ pub async fn delete_user_impl(
         ));
     }
 
-    state
-        .auth_provider
-        .read()
-        .await
-        .delete_user(payload.user_id.clone())
-        .await?;
+    practices::require_same_practice(&state, ctx.require_auth()?, &payload.user_id).await?;
+    // Anyone tied to past chart entries has to stay on record; disable them instead
+    account_status::require_no_phi_history(&state, &payload.user_id).await?;
+
+    user_sync::delete_user(&state, payload.user_id.clone()).await?;
 
     Ok(serde_json::json!({
         "user_id": payload.user_id,
@@ -0,0 +1,31 @@
+use poem::web::Data;
+use poem_openapi::{Object, param::Path, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::AppResult, services::account_status, state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct EnableUserRequest {
+    /// Why the account is being enabled again; kept in the audit log
+    pub reason: String,
+}
+
+pub async fn enable_user_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    user_id: Path<String>,
+    payload: Json<EnableUserRequest>,
+) -> AppResult<Value> {
+    let reason = account_status::parse_reason(&payload.reason)?;
+
+    account_status::set_enabled(&state, ctx, &user_id, true, reason).await?;
+
+    Ok(serde_json::json!({
+        "user_id": user_id.0,
+        "enabled": true,
+        "message": "User enabled successfully"
+    }))
+}
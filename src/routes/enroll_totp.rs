@@ -0,0 +1,45 @@
+use poem::web::Data;
+use poem_openapi::{Object, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, ValidationError},
+        types::auth_context::PendingMfa,
+    },
+    services::mfa,
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct EnrollTotpRequest {
+    /// Name to tell this authenticator apart from others, e.g. "Work phone"
+    pub label: Option<String>,
+}
+
+pub async fn enroll_totp_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    payload: Json<EnrollTotpRequest>,
+) -> AppResult<Value> {
+    let auth = ctx.require_auth_at(PendingMfa::Enroll)?;
+
+    let label = match payload.label.as_deref().map(str::trim) {
+        Some("") => Err(AppError::Validation(ValidationError::InvalidInput(
+            "Label cannot be empty".to_string(),
+        )))?,
+        Some(label) => label,
+        None => "Authenticator app",
+    };
+    let account_name = auth.email.as_deref().unwrap_or(&auth.subject);
+
+    let enrollment = mfa::enroll_totp(&state, &auth.subject, account_name, label).await?;
+
+    Ok(serde_json::json!({
+        "factor_id": enrollment.factor_id.to_string(),
+        "secret": enrollment.secret,
+        "provisioning_uri": enrollment.provisioning_uri,
+        "message": "Add the authenticator, then confirm it with a code to finish enrolling"
+    }))
+}
@@ -0,0 +1,42 @@
+use poem::web::Data;
+use poem_openapi::{Object, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, AuthProviderError},
+        types::email::Email,
+    },
+    state::AppState,
+};
+
+#[derive(Object, Debug)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+pub async fn forgot_password_impl(
+    state: Data<&AppState>,
+    payload: Json<ForgotPasswordRequest>,
+) -> AppResult<Value> {
+    let email = Email::parse(
+        payload.email.clone(),
+        state.settings.email_case_insensitive_local_part,
+    )?;
+
+    // The response must not reveal whether an account exists, so failures are only logged
+    match state
+        .auth_provider
+        .read()
+        .await
+        .send_password_reset(email)
+        .await
+    {
+        Ok(()) | Err(AppError::AuthProvider(AuthProviderError::UserNotFound)) => {}
+        Err(e) => tracing::error!(error = %e, "Failed to start password reset"),
+    }
+
+    Ok(serde_json::json!({
+        "message": "If the account exists, a password reset email has been sent"
+    }))
+}
@@ -0,0 +1,21 @@
+use poem::web::Data;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::AppResult, services::mfa, state::AppState,
+    utils::tracing::RequestContext,
+};
+
+pub async fn generate_recovery_codes_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+) -> AppResult<Value> {
+    let auth = ctx.require_auth()?;
+
+    let codes = mfa::generate_recovery_codes(&state, ctx, &auth.subject).await?;
+
+    Ok(serde_json::json!({
+        "recovery_codes": codes,
+        "message": "Store these codes somewhere safe; each can be used once and they replace any earlier codes"
+    }))
+}
@@ -0,0 +1,18 @@
+use poem::web::Data;
+use poem_openapi::param::Path;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::AppResult, services::offboarding, state::AppState,
+    utils::tracing::RequestContext,
+};
+
+pub async fn get_offboarding_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    user_id: Path<String>,
+) -> AppResult<Value> {
+    let job = offboarding::latest(&state, ctx, &user_id).await?;
+
+    Ok(job.response_json())
+}
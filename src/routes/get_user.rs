@@ -0,0 +1,24 @@
+use poem::web::Data;
+use poem_openapi::param::Path;
+use serde_json::Value;
+
+use crate::{
+    domain::{error::app_error::AppResult, types::permission::Permission},
+    services::practices,
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+pub async fn get_user_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    user_id: Path<String>,
+) -> AppResult<Value> {
+    let auth = ctx.require_auth()?;
+    auth.require_self_or(&user_id, Permission::ReadUsers)?;
+    practices::require_same_practice(&state, auth, &user_id).await?;
+
+    let user = state.auth_provider.read().await.get_user(user_id.0).await?;
+
+    Ok(user.response_json())
+}
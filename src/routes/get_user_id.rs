@@ -7,7 +7,9 @@ use crate::{
         error::app_error::{AppError, AppResult},
         types::email::Email,
     },
+    services::practices,
     state::AppState,
+    utils::tracing::RequestContext,
 };
 
 #[derive(Object, Debug)]
@@ -16,15 +18,23 @@ pub struct GetUserIdRequest {
 }
 
 pub async fn get_user_id_impl(
+    ctx: &RequestContext,
     state: Data<&AppState>,
     payload: Json<GetUserIdRequest>,
 ) -> AppResult<Value> {
-    let email = Email::new(payload.email.clone())?;
+    let email = Email::parse(
+        payload.email.clone(),
+        state.settings.email_case_insensitive_local_part,
+    )?;
 
     match state.auth_provider.read().await.get_user_id(email).await? {
-        Some(user_id) => Ok(serde_json::json!({
-            "user_id": user_id
-        })),
+        // Addresses in other practices look unregistered
+        Some(user_id) => {
+            practices::require_same_practice(&state, ctx.require_auth()?, &user_id).await?;
+            Ok(serde_json::json!({
+                "user_id": user_id
+            }))
+        }
         None => Err(AppError::AuthProvider(
             crate::domain::error::app_error::AuthProviderError::UserNotFound,
         )),
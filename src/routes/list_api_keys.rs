@@ -0,0 +1,15 @@
+use poem::web::Data;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::AppResult, services::api_keys, state::AppState,
+    utils::tracing::RequestContext,
+};
+
+pub async fn list_api_keys_impl(ctx: &RequestContext, state: Data<&AppState>) -> AppResult<Value> {
+    let keys = api_keys::list(&state, ctx.require_auth()?).await?;
+
+    Ok(serde_json::json!({
+        "api_keys": keys.iter().map(|key| key.response_json()).collect::<Vec<_>>(),
+    }))
+}
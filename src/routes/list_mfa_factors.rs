@@ -0,0 +1,20 @@
+use poem::web::Data;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::AppResult, services::mfa, state::AppState,
+    utils::tracing::RequestContext,
+};
+
+pub async fn list_mfa_factors_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+) -> AppResult<Value> {
+    let auth = ctx.require_auth()?;
+
+    let factors = mfa::list_factors(&state, &auth.subject).await?;
+
+    Ok(serde_json::json!({
+        "factors": factors.iter().map(|factor| factor.response_json()).collect::<Vec<_>>()
+    }))
+}
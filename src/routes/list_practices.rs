@@ -0,0 +1,12 @@
+use poem::web::Data;
+use serde_json::Value;
+
+use crate::{domain::error::app_error::AppResult, services::practices, state::AppState};
+
+pub async fn list_practices_impl(state: Data<&AppState>) -> AppResult<Value> {
+    let practices = practices::list(&state).await?;
+
+    Ok(serde_json::json!({
+        "practices": practices.iter().map(|p| p.response_json()).collect::<Vec<_>>(),
+    }))
+}
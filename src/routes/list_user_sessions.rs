@@ -0,0 +1,24 @@
+use poem::web::Data;
+use poem_openapi::param::Path;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::AppResult,
+    services::{practices, user_sessions},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+pub async fn list_user_sessions_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    user_id: Path<String>,
+) -> AppResult<Value> {
+    practices::require_same_practice(&state, ctx.require_auth()?, &user_id).await?;
+    let sessions = user_sessions::list(&state, &user_id).await?;
+
+    Ok(serde_json::json!({
+        "user_id": user_id.0,
+        "sessions": sessions.iter().map(|s| s.response_json()).collect::<Vec<_>>(),
+    }))
+}
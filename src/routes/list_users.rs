@@ -0,0 +1,66 @@
+use poem::web::Data;
+use serde_json::Value;
+
+use crate::{
+    domain::{
+        error::app_error::{AppResult, ValidationError},
+        types::{
+            directory::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, UserQuery, UserSort},
+            user::UserRole,
+        },
+    },
+    services::user_directory,
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+// Query string of GET /users, as received
+pub struct ListUsersParams {
+    pub search: Option<String>,
+    pub role: Option<String>,
+    pub enabled: Option<bool>,
+    pub sort: Option<String>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+pub async fn list_users_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    params: ListUsersParams,
+) -> AppResult<Value> {
+    let page = params.page.unwrap_or(1);
+    let per_page = params.per_page.unwrap_or(DEFAULT_PAGE_SIZE);
+    if page == 0 || per_page == 0 || per_page > MAX_PAGE_SIZE {
+        Err(ValidationError::InvalidInput(format!(
+            "page must be at least 1 and per_page between 1 and {MAX_PAGE_SIZE}"
+        )))?;
+    }
+
+    let query = UserQuery {
+        search: params.search,
+        role: params
+            .role
+            .as_deref()
+            .map(str::parse::<UserRole>)
+            .transpose()?,
+        enabled: params.enabled,
+        sort: params
+            .sort
+            .as_deref()
+            .map(str::parse::<UserSort>)
+            .transpose()?
+            .unwrap_or_default(),
+        page,
+        per_page,
+    };
+
+    let result = user_directory::search(&state, ctx.require_auth()?, &query).await?;
+
+    Ok(serde_json::json!({
+        "users": result.users.iter().map(|user| user.response_json()).collect::<Vec<_>>(),
+        "page": page,
+        "per_page": per_page,
+        "total": result.total,
+    }))
+}
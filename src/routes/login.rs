@@ -0,0 +1,136 @@
+use poem::web::{Data, cookie::CookieJar};
+use poem_openapi::{Object, payload::Json};
+use secrecy::ExposeSecret;
+use serde_json::Value;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, AuthProviderError, MfaError},
+        types::{email::Email, password::Password, token::AuthTokens, user::User},
+    },
+    services::{login_throttle, mfa, refresh_tokens, user_sync, web_sessions},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+    /// Authenticator app code or unused recovery code; required once the account has MFA enrolled
+    pub mfa_code: Option<String>,
+    /// Keep the tokens server side and sign the browser in with a session cookie instead
+    pub session: Option<bool>,
+}
+
+// Anyone with a confirmed authenticator must use it; returns whether they have one
+async fn second_factor(
+    ctx: &RequestContext,
+    state: &AppState,
+    user_id: &str,
+    code: Option<&str>,
+) -> AppResult<bool> {
+    if !mfa::has_confirmed_factor(state, user_id).await? {
+        return Ok(false);
+    }
+    let code = code.ok_or(MfaError::Required)?;
+    mfa::verify_code(state, ctx, user_id, code).await?;
+    Ok(true)
+}
+
+// The provider opens a session for every password login; ends one that won't be handed out
+async fn discard_session(state: &AppState, tokens: AuthTokens) {
+    if let Some(session_id) = tokens.session_id
+        && let Err(e) = state
+            .auth_provider
+            .read()
+            .await
+            .end_session(session_id)
+            .await
+    {
+        tracing::warn!(error = %e, "Failed to end provider session");
+    }
+}
+
+// Checks the password and, where enrolled, the second factor; returns whether MFA is enrolled
+async fn authenticate(
+    ctx: &RequestContext,
+    state: &AppState,
+    email: Email,
+    payload: &LoginRequest,
+) -> AppResult<(User, AuthTokens, bool)> {
+    // An empty password can never match a stored credential
+    let password = Password::new(payload.password.clone())
+        .map_err(|_| AuthProviderError::InvalidCredentials)?;
+
+    let (user, tokens) = state
+        .auth_provider
+        .read()
+        .await
+        .login_user(email, password)
+        .await?;
+    let user_id = user.user_id.as_deref().unwrap_or_default();
+
+    match second_factor(ctx, state, user_id, payload.mfa_code.as_deref()).await {
+        Ok(enrolled) => Ok((user, tokens, enrolled)),
+        Err(e) => {
+            discard_session(state, tokens).await;
+            Err(e)
+        }
+    }
+}
+
+pub async fn login_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    cookie_jar: &CookieJar,
+    payload: Json<LoginRequest>,
+) -> AppResult<Value> {
+    let email = Email::parse(
+        payload.email.clone(),
+        state.settings.email_case_insensitive_local_part,
+    )?;
+    login_throttle::check(&state, ctx, &email).await?;
+
+    let (user, tokens, enrolled) = match authenticate(ctx, &state, email.clone(), &payload).await {
+        Ok(authenticated) => authenticated,
+        // Wrong guesses at either factor count towards a lockout
+        Err(
+            e @ (AppError::AuthProvider(AuthProviderError::InvalidCredentials)
+            | AppError::Mfa(MfaError::InvalidCode)),
+        ) => {
+            let locked = login_throttle::record_failure(&state, ctx, &email).await?;
+            return Err(locked.unwrap_or(e));
+        }
+        Err(e) => return Err(e),
+    };
+    login_throttle::record_success(&state, &email).await?;
+
+    let user_id = user.user_id.as_deref().unwrap_or_default();
+    // Roles that require MFA get no tokens until an authenticator is enrolled, only a
+    // credential the enrollment routes accept; they then log in again with a code
+    if !enrolled && user.role.is_some_and(|role| role.requires_mfa()) {
+        discard_session(&state, tokens).await;
+        let token =
+            mfa::issue_enrollment_token(&state, user_id, email.as_ref().expose_secret()).await?;
+        return Ok(serde_json::json!({
+            "mfa_enrollment_required": true,
+            "enrollment_token": token.expose_secret(),
+            "expires_in": mfa::ENROLLMENT_TOKEN_TTL_SECS,
+            "user": user.response_json(),
+        }));
+    }
+
+    user_sync::record_login(&state, user_id).await?;
+    let mut response = if payload.session.unwrap_or(false) {
+        let session = web_sessions::create(&state, ctx, user_id, &tokens, false).await?;
+        web_sessions::set_cookies(cookie_jar, &session);
+        serde_json::json!({ "csrf_token": session.csrf_token })
+    } else {
+        refresh_tokens::start_family(&state, user_id, &tokens).await?;
+        tokens.response_json()
+    };
+    response["user"] = user.response_json();
+    response["mfa_enrollment_required"] = false.into();
+    Ok(response)
+}
@@ -0,0 +1,33 @@
+use poem::web::Data;
+use poem_openapi::{Object, payload::Json};
+use secrecy::SecretString;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::{AppError, AppResult, ValidationError},
+    state::AppState,
+};
+
+#[derive(Object, Debug)]
+pub struct LogoutRequest {
+    pub refresh_token: String,
+}
+
+pub async fn logout_impl(state: Data<&AppState>, payload: Json<LogoutRequest>) -> AppResult<Value> {
+    if payload.refresh_token.trim().is_empty() {
+        return Err(AppError::Validation(ValidationError::InvalidInput(
+            "Refresh token cannot be empty".to_string(),
+        )));
+    }
+
+    state
+        .auth_provider
+        .read()
+        .await
+        .logout_user(SecretString::from(payload.refresh_token.clone()))
+        .await?;
+
+    Ok(serde_json::json!({
+        "message": "User logged out successfully"
+    }))
+}
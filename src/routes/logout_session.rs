@@ -0,0 +1,24 @@
+use poem::web::{Data, cookie::CookieJar};
+use secrecy::SecretString;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::AppResult,
+    services::web_sessions::{self, SESSION_COOKIE},
+    state::AppState,
+};
+
+// BearerAuth has already checked the CSRF token of a live session by the time this runs
+pub async fn logout_session_impl(
+    state: Data<&AppState>,
+    cookie_jar: &CookieJar,
+) -> AppResult<Value> {
+    if let Some(cookie) = cookie_jar.get(SESSION_COOKIE) {
+        web_sessions::end(&state, &SecretString::from(cookie.value_str())).await?;
+    }
+    web_sessions::clear_cookies(cookie_jar);
+
+    Ok(serde_json::json!({
+        "message": "Session ended successfully"
+    }))
+}
@@ -1,4 +1,38 @@
+pub mod accept_invitation;
+pub mod assign_practice;
+pub mod authorize;
+pub mod callback;
+pub mod change_password;
+pub mod confirm_totp;
+pub mod create_api_key;
+pub mod create_invitation;
+pub mod create_practice;
 pub mod delete_user;
+pub mod disable_user;
+pub mod enable_user;
+pub mod enroll_totp;
+pub mod forgot_password;
+pub mod generate_recovery_codes;
+pub mod get_offboarding;
+pub mod get_user;
 pub mod get_user_id;
 pub mod health;
+pub mod list_api_keys;
+pub mod list_mfa_factors;
+pub mod list_practices;
+pub mod list_user_sessions;
+pub mod list_users;
+pub mod login;
+pub mod logout;
+pub mod logout_session;
+pub mod offboard_user;
+pub mod refresh;
+pub mod remove_mfa_factor;
+pub mod resend_verification;
+pub mod revoke_api_key;
+pub mod revoke_user_session;
+pub mod revoke_user_sessions;
 pub mod signup;
+pub mod unlock_user;
+pub mod update_user;
+pub mod verify_mfa;
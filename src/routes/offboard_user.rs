@@ -0,0 +1,33 @@
+use poem::web::Data;
+use poem_openapi::{Object, param::Path, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::AppResult,
+    services::{account_status, offboarding},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct OffboardUserRequest {
+    /// Why the user is leaving; kept in the audit log
+    pub reason: String,
+    /// Member of the same practice who takes over the user's work; recorded on the job
+    /// for the handover steps this service cannot run yet
+    pub reassign_to: Option<String>,
+}
+
+pub async fn offboard_user_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    user_id: Path<String>,
+    payload: Json<OffboardUserRequest>,
+) -> AppResult<Value> {
+    let payload = payload.0;
+    let reason = account_status::parse_reason(&payload.reason)?;
+
+    let job = offboarding::offboard(&state, ctx, &user_id, reason, payload.reassign_to).await?;
+
+    Ok(job.response_json())
+}
@@ -0,0 +1,37 @@
+use poem::web::Data;
+use poem_openapi::{Object, payload::Json};
+use secrecy::SecretString;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::{AppError, AppResult, ValidationError},
+    services::refresh_tokens,
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+pub async fn refresh_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    payload: Json<RefreshRequest>,
+) -> AppResult<Value> {
+    if payload.refresh_token.trim().is_empty() {
+        return Err(AppError::Validation(ValidationError::InvalidInput(
+            "Refresh token cannot be empty".to_string(),
+        )));
+    }
+
+    let tokens = refresh_tokens::rotate(
+        &state,
+        ctx,
+        SecretString::from(payload.refresh_token.clone()),
+    )
+    .await?;
+
+    Ok(tokens.response_json())
+}
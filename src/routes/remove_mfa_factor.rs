@@ -0,0 +1,56 @@
+use poem::web::Data;
+use poem_openapi::{Object, param::Path, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::{AppError, AppResult, MfaError},
+    services::{login_throttle, mfa},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct RemoveMfaFactorRequest {
+    /// Authenticator app code or unused recovery code; required to remove a confirmed authenticator
+    pub code: Option<String>,
+}
+
+pub async fn remove_mfa_factor_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    factor_id: Path<String>,
+    payload: Json<RemoveMfaFactorRequest>,
+) -> AppResult<Value> {
+    let auth = ctx.require_auth()?;
+    let factor_id = uuid::Uuid::parse_str(&factor_id).map_err(|_| MfaError::FactorNotFound)?;
+    let user = state
+        .auth_provider
+        .read()
+        .await
+        .get_user(auth.subject.clone())
+        .await?;
+    login_throttle::check(&state, ctx, &user.email).await?;
+
+    match mfa::remove_factor(
+        &state,
+        ctx,
+        &auth.subject,
+        factor_id,
+        payload.code.as_deref(),
+    )
+    .await
+    {
+        Ok(()) => {}
+        // Wrong guesses count towards the same lockout as failed logins
+        Err(e @ AppError::Mfa(MfaError::InvalidCode)) => {
+            let locked = login_throttle::record_failure(&state, ctx, &user.email).await?;
+            return Err(locked.unwrap_or(e));
+        }
+        Err(e) => return Err(e),
+    }
+
+    Ok(serde_json::json!({
+        "factor_id": factor_id.to_string(),
+        "message": "Authenticator removed successfully"
+    }))
+}
@@ -0,0 +1,42 @@
+use poem::web::Data;
+use poem_openapi::{Object, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, AuthProviderError},
+        types::email::Email,
+    },
+    state::AppState,
+};
+
+#[derive(Object, Debug)]
+pub struct ResendVerificationRequest {
+    pub email: String,
+}
+
+pub async fn resend_verification_impl(
+    state: Data<&AppState>,
+    payload: Json<ResendVerificationRequest>,
+) -> AppResult<Value> {
+    let email = Email::parse(
+        payload.email.clone(),
+        state.settings.email_case_insensitive_local_part,
+    )?;
+
+    // Same as forgot password: the response must not reveal whether an account exists
+    match state
+        .auth_provider
+        .read()
+        .await
+        .send_verification_email(email)
+        .await
+    {
+        Ok(()) | Err(AppError::AuthProvider(AuthProviderError::UserNotFound)) => {}
+        Err(e) => tracing::error!(error = %e, "Failed to resend verification email"),
+    }
+
+    Ok(serde_json::json!({
+        "message": "If the account exists and is unverified, a verification email has been sent"
+    }))
+}
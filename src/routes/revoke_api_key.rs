@@ -0,0 +1,21 @@
+use poem::web::Data;
+use poem_openapi::param::Path;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::AppResult, services::api_keys, state::AppState,
+    utils::tracing::RequestContext,
+};
+
+pub async fn revoke_api_key_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    key_id: Path<String>,
+) -> AppResult<Value> {
+    api_keys::revoke(&state, ctx, &key_id).await?;
+
+    Ok(serde_json::json!({
+        "api_key_id": key_id.0,
+        "message": "API key revoked successfully"
+    }))
+}
@@ -0,0 +1,27 @@
+use poem::web::Data;
+use poem_openapi::param::Path;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::AppResult,
+    services::{practices, user_sessions},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+pub async fn revoke_user_session_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    user_id: Path<String>,
+    session_id: Path<String>,
+) -> AppResult<Value> {
+    practices::require_same_practice(&state, ctx.require_auth()?, &user_id).await?;
+
+    user_sessions::revoke(&state, ctx, &user_id, &session_id).await?;
+
+    Ok(serde_json::json!({
+        "user_id": user_id.0,
+        "session_id": session_id.0,
+        "message": "Session revoked successfully"
+    }))
+}
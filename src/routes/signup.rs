@@ -1,13 +1,21 @@
 use poem::web::Data;
 use poem_openapi::{Object, payload::Json};
+use secrecy::ExposeSecret;
 use serde_json::Value;
 
 use crate::{
     domain::{
-        error::app_error::AppResult,
-        types::{email::Email, password::Password, user::User},
+        error::app_error::{AppResult, AuthorizationError},
+        types::{
+            email::Email,
+            password::Password,
+            permission::Permission,
+            user::{User, UserRole},
+        },
     },
+    services::user_sync,
     state::AppState,
+    utils::tracing::RequestContext,
 };
 
 #[derive(Object, Debug)]
@@ -16,25 +24,68 @@ pub struct SignupRequest {
     pub password: String,
     pub first_name: String,
     pub last_name: String,
+    /// EHR realm role to assign; requires a caller who can manage users
+    pub role: Option<String>,
+    /// Creates the user with an already verified email; requires a caller who can manage users
+    pub email_verified: Option<bool>,
 }
 
-pub async fn signup_impl(state: Data<&AppState>, payload: Json<SignupRequest>) -> AppResult<Value> {
-    let email = Email::new(payload.email.clone())?;
+pub async fn signup_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    payload: Json<SignupRequest>,
+) -> AppResult<Value> {
+    // Without open signup, only callers who manage users may create accounts here
+    if !state.settings.registration.open_signup
+        && !ctx
+            .auth
+            .as_ref()
+            .is_some_and(|auth| auth.has_permission(Permission::ManageUsers))
+    {
+        Err(AuthorizationError::SignupDisabled)?;
+    }
+
+    let email = Email::parse(
+        payload.email.clone(),
+        state.settings.email_case_insensitive_local_part,
+    )?;
 
     let password = Password::new(payload.password.clone())?;
 
-    let user = User::new(
-        payload.email.clone(),
+    let role = payload
+        .role
+        .as_deref()
+        .map(str::parse::<UserRole>)
+        .transpose()?;
+    if let Some(role) = role {
+        ctx.require_auth()?.require_role_assignment(role)?;
+    }
+
+    let mut user = User::new(
+        email.as_ref().expose_secret().to_string(),
         email,
         password,
         payload.first_name.clone(),
         payload.last_name.clone(),
-        None,
+        role,
     );
+    if payload.email_verified == Some(true) {
+        // Staff created by an admin can skip verification; self-service signups cannot
+        ctx.require_auth()?
+            .require_permission(Permission::ManageUsers)?;
+        user.email_verified = true;
+    }
+    // Staff created by a manager join the manager's practice
+    if let Some(auth) = &ctx.auth
+        && auth.has_permission(Permission::ManageUsers)
+    {
+        user.practice_id = auth.practice_id;
+    }
 
-    state.auth_provider.write().await.signup_user(user).await?;
+    let user_id = user_sync::create_user(&state, user).await?;
 
     Ok(serde_json::json!({
+        "user_id": user_id,
         "message": "User signed up successfully"
     }))
 }
@@ -0,0 +1,32 @@
+use poem::web::Data;
+use poem_openapi::param::Path;
+use serde_json::Value;
+
+use crate::{
+    domain::error::app_error::AppResult,
+    services::{login_throttle, practices},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+pub async fn unlock_user_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    user_id: Path<String>,
+) -> AppResult<Value> {
+    practices::require_same_practice(&state, ctx.require_auth()?, &user_id).await?;
+
+    let user = state
+        .auth_provider
+        .read()
+        .await
+        .get_user(user_id.0.clone())
+        .await?;
+
+    login_throttle::unlock(&state, ctx, &user_id, &user.email).await?;
+
+    Ok(serde_json::json!({
+        "user_id": user_id.0,
+        "message": "User unlocked successfully"
+    }))
+}
@@ -0,0 +1,147 @@
+use poem::web::Data;
+use poem_openapi::{Object, param::Path, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, ValidationError},
+        types::{
+            email::Email,
+            password::Password,
+            permission::Permission,
+            user::{UserRole, UserUpdate},
+        },
+    },
+    services::{login_throttle, practices, user_sync},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct UpdateUserRequest {
+    pub email: Option<String>,
+    /// Sets another user's password; users change their own at /auth/password/change
+    pub password: Option<String>,
+    /// Required to change your own email address
+    pub current_password: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    /// EHR realm role replacing the user's current one; requires a caller who can manage users
+    pub role: Option<String>,
+    /// Marks the email address as verified or not; requires a caller who can manage users
+    pub email_verified: Option<bool>,
+}
+
+fn non_empty(field: &str, value: &Option<String>) -> AppResult<Option<String>> {
+    match value {
+        Some(v) if v.trim().is_empty() => Err(AppError::Validation(ValidationError::InvalidInput(
+            format!("{field} cannot be empty"),
+        ))),
+        _ => Ok(value.clone()),
+    }
+}
+
+pub async fn update_user_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    user_id: Path<String>,
+    payload: Json<UpdateUserRequest>,
+) -> AppResult<Value> {
+    let auth = ctx.require_auth()?;
+    auth.require_self_or(&user_id, Permission::ManageUsers)?;
+    practices::require_same_practice(&state, auth, &user_id).await?;
+    // A stolen token alone must not be enough to take the account over
+    let is_self = auth.subject == user_id.0;
+    if is_self && payload.password.is_some() {
+        Err(ValidationError::InvalidInput(
+            "Change your own password at /auth/password/change".to_string(),
+        ))?;
+    }
+
+    let role = payload
+        .role
+        .as_deref()
+        .map(str::parse::<UserRole>)
+        .transpose()?;
+
+    let mut update = UserUpdate {
+        user_id: Some(user_id.0.clone()),
+        email: payload
+            .email
+            .clone()
+            .map(|email| Email::parse(email, state.settings.email_case_insensitive_local_part))
+            .transpose()?,
+        password: payload.password.clone().map(Password::new).transpose()?,
+        first_name: non_empty("First name", &payload.first_name)?,
+        last_name: non_empty("Last name", &payload.last_name)?,
+        role: role.map(Some),
+        email_verified: payload.email_verified,
+        // Moving between practices has its own endpoint and permission
+        practice_id: None,
+        // As does disabling an account
+        enabled: None,
+    };
+
+    if update.profile_json().is_none() && update.password.is_none() && update.role.is_none() {
+        return Err(AppError::Validation(ValidationError::InvalidInput(
+            "No fields to update".to_string(),
+        )));
+    }
+
+    // Users must not be able to skip verification of their own address
+    if update.email_verified.is_some() {
+        auth.require_permission(Permission::ManageUsers)?;
+    }
+    // A new address stays unverified until its owner proves it, unless a manager vouches for it
+    let reverify = update
+        .email
+        .clone()
+        .filter(|_| update.email_verified.is_none());
+    if reverify.is_some() {
+        update.email_verified = Some(false);
+    }
+
+    let current = state
+        .auth_provider
+        .read()
+        .await
+        .get_user(user_id.0.clone())
+        .await?;
+    // Only an owner may change an owner's account
+    if !is_self && let Some(previous) = current.role {
+        auth.require_role_assignment(previous)?;
+    }
+    if is_self && update.email.is_some() {
+        let current_password = payload.current_password.as_deref().ok_or_else(|| {
+            ValidationError::InvalidInput(
+                "current_password is required to change your email address".to_string(),
+            )
+        })?;
+        login_throttle::verify_current_password(&state, ctx, &current, current_password).await?;
+    }
+    if let Some(role) = role {
+        auth.require_role_assignment(role)?;
+        // Taking the owner role away is as privileged as granting it
+        if let Some(previous) = current.role.filter(|previous| *previous != role) {
+            auth.require_role_assignment(previous)?;
+        }
+    }
+
+    user_sync::update_user(&state, &current, update).await?;
+
+    if let Some(email) = reverify
+        && let Err(e) = state
+            .auth_provider
+            .read()
+            .await
+            .send_verification_email(email)
+            .await
+    {
+        tracing::error!(error = %e, "Failed to send verification email");
+    }
+
+    Ok(serde_json::json!({
+        "user_id": user_id.0,
+        "message": "User updated successfully"
+    }))
+}
@@ -0,0 +1,66 @@
+use poem::web::{Data, cookie::CookieJar};
+use poem_openapi::{Object, payload::Json};
+use secrecy::SecretString;
+use serde_json::Value;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, AuthProviderError, MfaError, ValidationError},
+        types::{auth_context::PendingMfa, email::Email},
+    },
+    services::{
+        login_throttle, mfa, user_sync,
+        web_sessions::{self, SESSION_COOKIE},
+    },
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+#[derive(Object, Debug)]
+pub struct VerifyMfaRequest {
+    /// Authenticator app code or unused recovery code
+    pub code: String,
+}
+
+// Gives the second factor for a browser sign-in, whose session is of no use until then
+pub async fn verify_mfa_impl(
+    ctx: &RequestContext,
+    state: Data<&AppState>,
+    cookie_jar: &CookieJar,
+    payload: Json<VerifyMfaRequest>,
+) -> AppResult<Value> {
+    let auth = ctx.require_auth_at(PendingMfa::Verify)?;
+    // Only a session cookie can be pending at this step
+    let session_id = match cookie_jar.get(SESSION_COOKIE) {
+        Some(cookie) if auth.pending_mfa.is_some() => SecretString::from(cookie.value_str()),
+        _ => Err(ValidationError::InvalidInput(
+            "No sign-in is waiting for a code".to_string(),
+        ))?,
+    };
+    let email = Email::parse(
+        auth.email
+            .clone()
+            .ok_or(AuthProviderError::Unauthenticated)?,
+        state.settings.email_case_insensitive_local_part,
+    )?;
+    login_throttle::check(&state, ctx, &email).await?;
+
+    match mfa::verify_code(&state, ctx, &auth.subject, &payload.code).await {
+        Ok(()) => login_throttle::record_success(&state, &email).await?,
+        // Wrong guesses count towards the same lockout as failed logins
+        Err(e @ AppError::Mfa(MfaError::InvalidCode)) => {
+            let locked = login_throttle::record_failure(&state, ctx, &email).await?;
+            return Err(locked.unwrap_or(e));
+        }
+        Err(e) => return Err(e),
+    }
+
+    user_sync::record_login(&state, &auth.subject).await?;
+    let session = web_sessions::complete_mfa(&state, &session_id).await?;
+    web_sessions::set_cookies(cookie_jar, &session);
+
+    Ok(serde_json::json!({
+        "csrf_token": session.csrf_token,
+        "message": "Signed in successfully"
+    }))
+}
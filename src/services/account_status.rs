@@ -0,0 +1,141 @@
+// Deactivation keeps an account's identity, and with it the audit trail tying the
+// user to past chart entries, while stopping them from signing in. Permanent
+// deletion is reserved for accounts that never touched PHI.
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{
+            AppResult, AuthProviderError, AuthorizationError, DatabaseError, ValidationError,
+        },
+        types::{
+            auth_context::AuthContext,
+            user::{User, UserUpdate},
+        },
+    },
+    services::{
+        audit::{self, AuditAction, AuditEvent, PHI_RESOURCE_TYPES},
+        practices, user_sessions, user_sync,
+    },
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+const MAX_REASON_LEN: usize = 500;
+
+// The justification recorded with a status change; auditors need more than a blank
+pub fn parse_reason(reason: &str) -> AppResult<String> {
+    let reason = reason.trim();
+    if reason.is_empty() || reason.chars().count() > MAX_REASON_LEN {
+        Err(ValidationError::InvalidInput(format!(
+            "A reason of 1 to {MAX_REASON_LEN} characters is required"
+        )))?;
+    }
+    Ok(reason.to_string())
+}
+
+// Fails unless the caller may change the user's status, returning the user as it stands
+#[tracing::instrument(skip_all)]
+pub async fn authorize_change(
+    state: &AppState,
+    auth: &AuthContext,
+    user_id: &str,
+    enabled: bool,
+) -> AppResult<User> {
+    if !enabled && auth.subject == user_id {
+        Err(ValidationError::InvalidInput(
+            "You cannot disable your own account".to_string(),
+        ))?;
+    }
+    practices::require_same_practice(state, auth, user_id).await?;
+
+    let current = state
+        .auth_provider
+        .read()
+        .await
+        .get_user(user_id.to_string())
+        .await?;
+    // Only an owner may lock out an owner
+    if let Some(role) = current.role {
+        auth.require_role_assignment(role)?;
+    }
+    Ok(current)
+}
+
+// Sets the flag and audits the change, leaving any sessions the user holds alone
+#[tracing::instrument(skip_all)]
+pub async fn change_status(
+    state: &AppState,
+    ctx: &RequestContext,
+    current: &User,
+    enabled: bool,
+    reason: String,
+) -> AppResult<()> {
+    let user_id = current.user_id.clone().unwrap_or_default();
+    if current.enabled != enabled {
+        let update = UserUpdate {
+            user_id: Some(user_id.clone()),
+            enabled: Some(enabled),
+            ..Default::default()
+        };
+        user_sync::update_user(state, current, update).await?;
+    }
+
+    let event = AuditEvent {
+        user_id: Uuid::parse_str(&ctx.require_auth()?.subject).ok(),
+        action: if enabled {
+            AuditAction::AccountEnabled
+        } else {
+            AuditAction::AccountDisabled
+        },
+        resource_type: "user",
+        resource_id: Some(user_id),
+        reason: Some(reason),
+    };
+    audit::record(&state.db.read().await.clone(), ctx, event).await
+}
+
+// Disabling also ends every session the user holds, so access stops straight away
+#[tracing::instrument(skip_all)]
+pub async fn set_enabled(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+    enabled: bool,
+    reason: String,
+) -> AppResult<()> {
+    let current = authorize_change(state, ctx.require_auth()?, user_id, enabled).await?;
+    change_status(state, ctx, &current, enabled, reason).await?;
+    if !enabled {
+        user_sessions::revoke_all(state, ctx, user_id).await?;
+    }
+    Ok(())
+}
+
+// Fails with PhiHistory once the account may have accessed patient data: any audited
+// access, or any sign-in at all, since not every module that shows PHI audits reads
+#[tracing::instrument(skip_all)]
+pub async fn require_no_phi_history(state: &AppState, user_id: &str) -> AppResult<()> {
+    let id = Uuid::parse_str(user_id).map_err(|_| AuthProviderError::UserNotFound)?;
+    let resource_types: Vec<String> = PHI_RESOURCE_TYPES.iter().map(|t| t.to_string()).collect();
+
+    let touched: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM audit_logs WHERE user_id = $1 AND resource_type = ANY($2)
+        ) OR EXISTS (
+            SELECT 1 FROM users WHERE id = $1 AND last_login_at IS NOT NULL
+        )
+        "#,
+    )
+    .bind(id)
+    .bind(&resource_types)
+    .fetch_one(&*state.db.read().await)
+    .await
+    .map_err(|e| DatabaseError::context("check PHI history", e))?;
+
+    if touched {
+        Err(AuthorizationError::PhiHistory)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,261 @@
+// API keys let integrations such as the lab interface or billing jobs call the API
+// without a human login. Keys are shown once at creation and only a hash is kept;
+// each key carries scopes, expires, records when it was last used and can be revoked.
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{
+            AppError, AppResult, AuthProviderError, DatabaseError, ValidationError,
+        },
+        types::{
+            api_key::{self, API_KEY_PREFIX, ApiKey},
+            auth_context::AuthContext,
+        },
+    },
+    services::audit::{self, AuditAction, AuditEvent},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+const DEFAULT_TTL_SECS: u64 = 90 * 24 * 60 * 60;
+const MAX_TTL_SECS: u64 = 365 * 24 * 60 * 60;
+
+pub struct NewApiKey {
+    pub api_key: ApiKey,
+    // The only time the key is available in the clear
+    pub key: SecretString,
+}
+
+type ApiKeyRow = (
+    Uuid,
+    String,
+    String,
+    Vec<String>,
+    i64,
+    i64,
+    Option<i64>,
+    Option<i64>,
+);
+
+const API_KEY_COLUMNS: &str = r#"
+    id, name, prefix, scopes,
+    EXTRACT(EPOCH FROM created_at)::BIGINT,
+    EXTRACT(EPOCH FROM expires_at)::BIGINT,
+    EXTRACT(EPOCH FROM last_used_at)::BIGINT,
+    EXTRACT(EPOCH FROM revoked_at)::BIGINT
+"#;
+
+fn from_row(row: ApiKeyRow) -> ApiKey {
+    let (id, name, prefix, scopes, created_at, expires_at, last_used_at, revoked_at) = row;
+    ApiKey {
+        id,
+        name,
+        prefix,
+        scopes,
+        created_at,
+        expires_at,
+        last_used_at,
+        revoked_at,
+    }
+}
+
+fn hash_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn create(
+    state: &AppState,
+    ctx: &RequestContext,
+    name: String,
+    mut scopes: Vec<String>,
+    expires_in: Option<u64>,
+) -> AppResult<NewApiKey> {
+    let auth = ctx.require_auth()?;
+    let created_by = Uuid::parse_str(&auth.subject).map_err(AppError::internal)?;
+
+    if scopes.is_empty() {
+        Err(ValidationError::InvalidInput(
+            "An API key needs at least one scope".to_string(),
+        ))?;
+    }
+    for scope in &scopes {
+        api_key::validate_scope(scope)?;
+    }
+    scopes.sort();
+    scopes.dedup();
+
+    let expires_in = expires_in.unwrap_or(DEFAULT_TTL_SECS);
+    if expires_in == 0 || expires_in > MAX_TTL_SECS {
+        Err(ValidationError::InvalidInput(format!(
+            "API keys must expire within {} days",
+            MAX_TTL_SECS / (24 * 60 * 60)
+        )))?;
+    }
+
+    let id = Uuid::new_v4();
+    let key = format!(
+        "{API_KEY_PREFIX}{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+
+    let db = state.db.read().await.clone();
+    let row: ApiKeyRow = sqlx::query_as(&format!(
+        r#"
+        INSERT INTO api_keys
+            (id, name, key_hash, prefix, scopes, practice_id, created_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, NOW() + make_interval(secs => $8))
+        RETURNING {API_KEY_COLUMNS}
+        "#
+    ))
+    .bind(id)
+    .bind(&name)
+    .bind(hash_key(&key))
+    .bind(api_key::display_prefix(&key))
+    .bind(&scopes)
+    .bind(auth.practice_id)
+    .bind(created_by)
+    .bind(expires_in as f64)
+    .fetch_one(&db)
+    .await
+    .map_err(|e| DatabaseError::context("store API key", e))?;
+
+    let event = AuditEvent {
+        user_id: Some(created_by),
+        action: AuditAction::ApiKeyCreated,
+        resource_type: "api_key",
+        resource_id: Some(id.to_string()),
+        reason: None,
+    };
+    audit::record(&db, ctx, event).await?;
+
+    Ok(NewApiKey {
+        api_key: from_row(row),
+        key: SecretString::from(key),
+    })
+}
+
+// Keys of the caller's practice, newest first, including expired and revoked ones
+#[tracing::instrument(skip_all)]
+pub async fn list(state: &AppState, auth: &AuthContext) -> AppResult<Vec<ApiKey>> {
+    let db = state.db.read().await.clone();
+    let rows: Vec<ApiKeyRow> = sqlx::query_as(&format!(
+        r#"
+        SELECT {API_KEY_COLUMNS} FROM api_keys
+        WHERE practice_id IS NOT DISTINCT FROM $1
+        ORDER BY created_at DESC
+        "#
+    ))
+    .bind(auth.practice_id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| DatabaseError::context("list API keys", e))?;
+
+    Ok(rows.into_iter().map(from_row).collect())
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn revoke(state: &AppState, ctx: &RequestContext, key_id: &str) -> AppResult<()> {
+    let auth = ctx.require_auth()?;
+    let id = Uuid::parse_str(key_id).map_err(|_| AuthProviderError::ApiKeyNotFound)?;
+
+    let db = state.db.read().await.clone();
+    // Revoking twice is harmless, so only an unknown key is an error
+    let revoked = sqlx::query(
+        r#"
+        UPDATE api_keys SET revoked_at = COALESCE(revoked_at, NOW())
+        WHERE id = $1 AND practice_id IS NOT DISTINCT FROM $2
+        "#,
+    )
+    .bind(id)
+    .bind(auth.practice_id)
+    .execute(&db)
+    .await
+    .map_err(|e| DatabaseError::context("revoke API key", e))?
+    .rows_affected()
+        > 0;
+    if !revoked {
+        Err(AuthProviderError::ApiKeyNotFound)?;
+    }
+
+    let event = AuditEvent {
+        user_id: Uuid::parse_str(&auth.subject).ok(),
+        action: AuditAction::ApiKeyRevoked,
+        resource_type: "api_key",
+        resource_id: Some(id.to_string()),
+        reason: None,
+    };
+    audit::record(&db, ctx, event).await?;
+
+    Ok(())
+}
+
+// Revokes every live key the user created, returning how many there were
+#[tracing::instrument(skip_all)]
+pub async fn revoke_created_by(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+) -> AppResult<usize> {
+    let auth = ctx.require_auth()?;
+    let created_by = Uuid::parse_str(user_id).map_err(|_| AuthProviderError::UserNotFound)?;
+
+    let db = state.db.read().await.clone();
+    let ids: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        UPDATE api_keys SET revoked_at = NOW()
+        WHERE created_by = $1 AND revoked_at IS NULL
+        RETURNING id
+        "#,
+    )
+    .bind(created_by)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| DatabaseError::context("revoke API keys", e))?;
+
+    for id in &ids {
+        let event = AuditEvent {
+            user_id: Uuid::parse_str(&auth.subject).ok(),
+            action: AuditAction::ApiKeyRevoked,
+            resource_type: "api_key",
+            resource_id: Some(id.to_string()),
+            reason: None,
+        };
+        audit::record(&db, ctx, event).await?;
+    }
+
+    Ok(ids.len())
+}
+
+// Resolves a presented key to the identity it acts as, stamping its last use
+#[tracing::instrument(skip_all)]
+pub async fn authenticate(state: &AppState, key: &SecretString) -> AppResult<AuthContext> {
+    let db = state.db.read().await.clone();
+    let (id, scopes, practice_id): (Uuid, Vec<String>, Option<Uuid>) = sqlx::query_as(
+        r#"
+        UPDATE api_keys SET last_used_at = NOW()
+        WHERE key_hash = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        RETURNING id, scopes, practice_id
+        "#,
+    )
+    .bind(hash_key(key.expose_secret()))
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| DatabaseError::context("authenticate API key", e))?
+    .ok_or(AuthProviderError::InvalidToken(
+        "Unknown, expired or revoked API key".to_string(),
+    ))?;
+
+    Ok(AuthContext {
+        subject: id.to_string(),
+        email: None,
+        realm_roles: Vec::new(),
+        practice_id,
+        scopes: Some(scopes),
+        pending_mfa: None,
+    })
+}
@@ -0,0 +1,103 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    domain::error::app_error::{AppResult, DatabaseError},
+    utils::tracing::RequestContext,
+};
+
+// Resource types under which reads and writes of patient data are audited; an account
+// with any such event has touched PHI. Nothing in this service writes them yet: the
+// clinical modules must audit under these names, and until they do, hard delete is
+// also refused to anyone who has ever signed in (account_status::require_no_phi_history).
+pub const PHI_RESOURCE_TYPES: &[&str] = &[
+    "patient",
+    "encounter",
+    "chart_entry",
+    "clinical_note",
+    "lab_result",
+    "prescription",
+    "claim",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    RefreshTokenReuse,
+    MfaFactorEnrolled,
+    MfaFactorRemoved,
+    MfaRecoveryCodesGenerated,
+    MfaRecoveryCodeUsed,
+    LoginLockout,
+    LoginUnlock,
+    SessionRevoked,
+    AllSessionsRevoked,
+    InvitationCreated,
+    InvitationAccepted,
+    PracticeCreated,
+    UserPracticeChanged,
+    ApiKeyCreated,
+    ApiKeyRevoked,
+    AccountDisabled,
+    AccountEnabled,
+    UserOffboarded,
+    UserOffboardingNeedsAttention,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::RefreshTokenReuse => "refresh_token_reuse",
+            AuditAction::MfaFactorEnrolled => "mfa_factor_enrolled",
+            AuditAction::MfaFactorRemoved => "mfa_factor_removed",
+            AuditAction::MfaRecoveryCodesGenerated => "mfa_recovery_codes_generated",
+            AuditAction::MfaRecoveryCodeUsed => "mfa_recovery_code_used",
+            AuditAction::LoginLockout => "login_lockout",
+            AuditAction::LoginUnlock => "login_unlock",
+            AuditAction::SessionRevoked => "session_revoked",
+            AuditAction::AllSessionsRevoked => "all_sessions_revoked",
+            AuditAction::InvitationCreated => "invitation_created",
+            AuditAction::InvitationAccepted => "invitation_accepted",
+            AuditAction::PracticeCreated => "practice_created",
+            AuditAction::UserPracticeChanged => "user_practice_changed",
+            AuditAction::ApiKeyCreated => "api_key_created",
+            AuditAction::ApiKeyRevoked => "api_key_revoked",
+            AuditAction::AccountDisabled => "account_disabled",
+            AuditAction::AccountEnabled => "account_enabled",
+            AuditAction::UserOffboarded => "user_offboarded",
+            AuditAction::UserOffboardingNeedsAttention => "user_offboarding_needs_attention",
+        }
+    }
+}
+
+pub struct AuditEvent {
+    pub user_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub resource_type: &'static str,
+    pub resource_id: Option<String>,
+    // Why an administrator took the action, where one is asked for
+    pub reason: Option<String>,
+}
+
+// Appends an event to audit_logs, tagged with the caller's address and user agent
+#[tracing::instrument(skip_all, fields(action = event.action.as_str()))]
+pub async fn record(db: &PgPool, ctx: &RequestContext, event: AuditEvent) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_logs
+            (user_id, action, resource_type, resource_id, reason, ip, user_agent)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(event.user_id)
+    .bind(event.action.as_str())
+    .bind(event.resource_type)
+    .bind(event.resource_id)
+    .bind(event.reason)
+    .bind(ctx.ip.as_deref())
+    .bind(ctx.user_agent.as_deref())
+    .execute(db)
+    .await
+    .map_err(|e| DatabaseError::context("write audit event", e))?;
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+// Authorization code + PKCE sign-in for the web frontend. begin() remembers the
+// state, nonce and verifier of each attempt; complete() redeems them exactly once
+// and opens a web session for the signed-in user. Users with an authenticator, and
+// roles that require one, get a session pending MFA: the frontend sees MfaRequired
+// or MfaEnrollmentRequired and finishes at /auth/mfa/verify.
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::{
+    domain::{
+        error::app_error::{AppResult, AuthProviderError, DatabaseError, ValidationError},
+        types::{authorization::AuthorizationRequest, token::TokenClaims, user::UserRole},
+    },
+    services::{
+        mfa, user_sync,
+        web_sessions::{self, NewWebSession},
+    },
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+// How long the user has to finish signing in at the provider
+const AUTHORIZATION_TTL_SECS: i64 = 10 * 60;
+
+pub struct CompletedSignIn {
+    pub session: NewWebSession,
+    pub return_to: String,
+}
+
+// Only paths on this origin, so the callback can't be used as an open redirect
+fn validate_return_to(return_to: Option<String>) -> AppResult<String> {
+    let return_to = return_to.unwrap_or_else(|| "/".to_string());
+    if !return_to.starts_with('/') || return_to.starts_with("//") || return_to.contains('\\') {
+        Err(ValidationError::InvalidInput(
+            "return_to must be a path on this site".to_string(),
+        ))?;
+    }
+    Ok(return_to)
+}
+
+// Returns the provider URL to send the browser to
+#[tracing::instrument(skip_all)]
+pub async fn begin(state: &AppState, return_to: Option<String>) -> AppResult<String> {
+    let return_to = validate_return_to(return_to)?;
+    let request = AuthorizationRequest::new(state.settings.oidc_redirect_uri.clone());
+    let url = state
+        .auth_provider
+        .read()
+        .await
+        .authorization_url(&request)?;
+
+    let db = state.db.read().await.clone();
+    // Abandoned sign-ins are never redeemed, so sweep them here
+    sqlx::query(
+        "DELETE FROM oidc_authorizations WHERE created_at < NOW() - make_interval(secs => $1)",
+    )
+    .bind(AUTHORIZATION_TTL_SECS as f64)
+    .execute(&db)
+    .await
+    .map_err(|e| DatabaseError::context("remove expired sign-ins", e))?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO oidc_authorizations (state, nonce, code_verifier, return_to)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(&request.state)
+    .bind(&request.nonce)
+    .bind(request.code_verifier.expose_secret())
+    .bind(&return_to)
+    .execute(&db)
+    .await
+    .map_err(|e| DatabaseError::context("store sign-in", e))?;
+
+    Ok(url)
+}
+
+// `code` is None when the provider reported an error instead of issuing one
+#[tracing::instrument(skip_all)]
+pub async fn complete(
+    state: &AppState,
+    ctx: &RequestContext,
+    login_state: &str,
+    code: Option<String>,
+) -> AppResult<CompletedSignIn> {
+    // Claiming the row up front makes every state single use, whatever happens next
+    let claimed: Option<(String, String, String, bool)> = sqlx::query_as(
+        r#"
+        DELETE FROM oidc_authorizations
+        WHERE state = $1
+        RETURNING nonce, code_verifier, return_to,
+            created_at > NOW() - make_interval(secs => $2)
+        "#,
+    )
+    .bind(login_state)
+    .bind(AUTHORIZATION_TTL_SECS as f64)
+    .fetch_optional(&state.db.read().await.clone())
+    .await
+    .map_err(|e| DatabaseError::context("claim sign-in", e))?;
+
+    let Some((nonce, code_verifier, return_to, true)) = claimed else {
+        return Err(ValidationError::InvalidInput(
+            "Sign-in state is unknown or expired".to_string(),
+        ))?;
+    };
+    let code = code.ok_or(ValidationError::InvalidInput(
+        "Sign-in was not completed".to_string(),
+    ))?;
+
+    let provider = state.auth_provider.read().await;
+    let tokens = provider
+        .exchange_authorization_code(
+            code,
+            SecretString::from(code_verifier),
+            state.settings.oidc_redirect_uri.clone(),
+        )
+        .await?;
+
+    // The ID token came straight from the token endpoint, so only the nonce is left to check
+    let claims = match tokens.id_token.as_ref() {
+        Some(id_token) => TokenClaims::decode_unverified(id_token.expose_secret())?,
+        None => Err(AuthProviderError::Upstream(
+            "Auth provider did not return an ID token".to_string(),
+        ))?,
+    };
+    if claims.nonce.as_deref() != Some(nonce.as_str()) {
+        if let Some(session_id) = tokens.session_id.clone()
+            && let Err(e) = provider.end_session(session_id).await
+        {
+            tracing::warn!(error = %e, "Failed to end session after nonce mismatch");
+        }
+        return Err(AuthProviderError::InvalidToken(
+            "ID token nonce does not match the sign-in".to_string(),
+        ))?;
+    }
+    drop(provider);
+
+    let access = TokenClaims::decode_unverified(tokens.access_token.expose_secret())?;
+    let mfa_pending = mfa::has_confirmed_factor(state, &claims.sub).await?
+        || UserRole::from_realm_roles(&access.realm_access.roles)
+            .is_some_and(|role| role.requires_mfa());
+    // A pending sign-in is recorded once the code is given
+    if !mfa_pending {
+        user_sync::record_login(state, &claims.sub).await?;
+    }
+    let session = web_sessions::create(state, ctx, &claims.sub, &tokens, mfa_pending).await?;
+    Ok(CompletedSignIn { session, return_to })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_return_to_defaults_to_root() {
+        assert_eq!(validate_return_to(None).unwrap(), "/");
+        assert_eq!(
+            validate_return_to(Some("/patients?page=2".to_string())).unwrap(),
+            "/patients?page=2"
+        );
+    }
+
+    #[test]
+    fn test_return_to_rejects_other_origins() {
+        for target in [
+            "https://evil.example",
+            "//evil.example",
+            "/\\evil.example",
+            "patients",
+        ] {
+            assert!(validate_return_to(Some(target.to_string())).is_err());
+        }
+    }
+}
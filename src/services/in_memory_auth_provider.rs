@@ -0,0 +1,754 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use secrecy::{ExposeSecret, SecretString};
+use uuid::Uuid;
+
+use crate::domain::{
+    error::app_error::{AppResult, AuthProviderError, ValidationError},
+    interfaces::{
+        auth_provider::AuthProvider,
+        mailer::{EmailMessage, Mailer},
+        token_validator::TokenValidator,
+    },
+    types::{
+        auth_context::AuthContext,
+        authorization::AuthorizationRequest,
+        email::Email,
+        password::Password,
+        session::UserSession,
+        token::AuthTokens,
+        user::{User, UserUpdate},
+    },
+};
+
+// Same lifetimes the dev realm hands out
+const ACCESS_TOKEN_LIFETIME: Duration = Duration::from_secs(300);
+const REFRESH_TOKEN_LIFETIME: Duration = Duration::from_secs(1800);
+
+struct IssuedToken {
+    user_id: String,
+    session_id: String,
+    expires_at: Instant,
+}
+
+// Unix seconds, as Keycloak reports them
+struct SessionTimes {
+    started_at: u64,
+    last_access_at: u64,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[derive(Default)]
+struct Store {
+    users: HashMap<String, User>,
+    access_tokens: HashMap<String, IssuedToken>,
+    refresh_tokens: HashMap<String, IssuedToken>,
+    // A session lives on for as long as one of its refresh tokens does
+    sessions: HashMap<String, SessionTimes>,
+    practices: HashSet<Uuid>,
+}
+
+impl Store {
+    fn require_practice(&self, practice_id: Option<Uuid>) -> AppResult<()> {
+        match practice_id {
+            Some(id) if !self.practices.contains(&id) => Err(AuthProviderError::PracticeNotFound)?,
+            _ => Ok(()),
+        }
+    }
+
+    fn find_by_email(&self, email: &Email) -> Option<&User> {
+        self.users.values().find(|user| user.email == *email)
+    }
+
+    // Ends every session the user holds, as Keycloak does on logout
+    fn revoke_sessions(&mut self, user_id: &str) {
+        self.access_tokens
+            .retain(|_, token| token.user_id != user_id);
+        self.refresh_tokens
+            .retain(|_, token| token.user_id != user_id);
+        self.prune_sessions();
+    }
+
+    // Forgets the times of sessions no refresh token belongs to anymore
+    fn prune_sessions(&mut self) {
+        let live: HashSet<&String> = self
+            .refresh_tokens
+            .values()
+            .map(|token| &token.session_id)
+            .collect();
+        self.sessions
+            .retain(|session_id, _| live.contains(session_id));
+    }
+
+    fn issue_tokens(&mut self, user_id: &str, session_id: &str) -> AuthTokens {
+        let now_secs = unix_now();
+        self.sessions
+            .entry(session_id.to_string())
+            .or_insert(SessionTimes {
+                started_at: now_secs,
+                last_access_at: now_secs,
+            })
+            .last_access_at = now_secs;
+
+        let access_token = InMemoryAuthProvider::new_token();
+        let refresh_token = InMemoryAuthProvider::new_token();
+        let now = Instant::now();
+        let issued = |lifetime| IssuedToken {
+            user_id: user_id.to_string(),
+            session_id: session_id.to_string(),
+            expires_at: now + lifetime,
+        };
+        self.access_tokens
+            .insert(access_token.clone(), issued(ACCESS_TOKEN_LIFETIME));
+        self.refresh_tokens
+            .insert(refresh_token.clone(), issued(REFRESH_TOKEN_LIFETIME));
+
+        AuthTokens {
+            access_token: SecretString::from(access_token),
+            refresh_token: SecretString::from(refresh_token),
+            id_token: Some(SecretString::from(InMemoryAuthProvider::new_token())),
+            token_type: "Bearer".to_string(),
+            expires_in: ACCESS_TOKEN_LIFETIME.as_secs(),
+            refresh_expires_in: REFRESH_TOKEN_LIFETIME.as_secs(),
+            session_id: Some(session_id.to_string()),
+        }
+    }
+}
+
+// HashMap-backed identity store for local development and tests. Tokens are
+// opaque random strings, so the same instance must also validate them.
+#[derive(Clone)]
+pub struct InMemoryAuthProvider {
+    store: Arc<Mutex<Store>>,
+    // Sends the emails Keycloak would otherwise send itself
+    mailer: Arc<dyn Mailer + Send + Sync>,
+}
+
+impl InMemoryAuthProvider {
+    pub fn new(mailer: Arc<dyn Mailer + Send + Sync>) -> Self {
+        Self {
+            store: Arc::default(),
+            mailer,
+        }
+    }
+
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().expect("In-memory auth store poisoned")
+    }
+
+    // There is no verification page without Keycloak; the email only stands in for its own
+    async fn send_verify_email(&self, to: Email) -> AppResult<()> {
+        self.mailer
+            .send(EmailMessage {
+                to,
+                subject: "Verify your email".to_string(),
+                body: "Please confirm that this is the email address of your EHR account \
+                       by following the link in this email."
+                    .to_string(),
+            })
+            .await
+    }
+
+    fn new_token() -> String {
+        format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        )
+    }
+}
+
+// Copy of a stored user without its password
+fn public_user(user: &User) -> User {
+    User {
+        password: None,
+        ..user.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthProvider for InMemoryAuthProvider {
+    async fn retrieve_auth_token(&self) -> AppResult<String> {
+        Ok("in-memory-admin-token".to_string())
+    }
+
+    async fn signup_user(&self, mut user: User) -> AppResult<String> {
+        let user_id = uuid::Uuid::new_v4().to_string();
+        let verify = (!user.email_verified).then(|| user.email.clone());
+        {
+            let mut store = self.store();
+            if store.find_by_email(&user.email).is_some() {
+                Err(AuthProviderError::UserExists)?
+            }
+            store.require_practice(user.practice_id)?;
+
+            user.user_id = Some(user_id.clone());
+            store.users.insert(user_id.clone(), user);
+        }
+
+        // The account exists either way; the user can ask for the email again if this fails
+        if let Some(email) = verify
+            && let Err(e) = self.send_verify_email(email).await
+        {
+            tracing::error!(error = %e, "Failed to send verification email");
+        }
+
+        Ok(user_id)
+    }
+
+    async fn login_user(&self, email: Email, password: Password) -> AppResult<(User, AuthTokens)> {
+        let mut store = self.store();
+        let user = store
+            .find_by_email(&email)
+            .filter(|user| user.password.as_ref() == Some(&password))
+            .map(public_user)
+            .ok_or(AuthProviderError::InvalidCredentials)?;
+        if !user.enabled {
+            Err(AuthProviderError::AccountDisabled)?
+        }
+        if !user.email_verified {
+            Err(AuthProviderError::EmailNotVerified)?
+        }
+        let user_id = user.user_id.clone().unwrap_or_default();
+        let tokens = store.issue_tokens(&user_id, &uuid::Uuid::new_v4().to_string());
+
+        Ok((user, tokens))
+    }
+
+    async fn logout_user(&self, refresh_token: SecretString) -> AppResult<()> {
+        let mut store = self.store();
+        if let Some(token) = store.refresh_tokens.remove(refresh_token.expose_secret()) {
+            store.revoke_sessions(&token.user_id);
+        }
+        Ok(())
+    }
+
+    async fn revoke_refresh_token(&self, refresh_token: SecretString) -> AppResult<()> {
+        self.store()
+            .refresh_tokens
+            .remove(refresh_token.expose_secret());
+        Ok(())
+    }
+
+    async fn refresh_user_tokens(&self, refresh_token: SecretString) -> AppResult<AuthTokens> {
+        let mut store = self.store();
+        // Rotation: the presented token is spent whether or not it was still valid
+        let issued = store
+            .refresh_tokens
+            .remove(refresh_token.expose_secret())
+            .filter(|token| Instant::now() < token.expires_at)
+            .ok_or(AuthProviderError::InvalidToken(
+                "Refresh token is invalid or expired".to_string(),
+            ))?;
+
+        Ok(store.issue_tokens(&issued.user_id, &issued.session_id))
+    }
+
+    // There is no login page to send a browser to
+    fn authorization_url(&self, _request: &AuthorizationRequest) -> AppResult<String> {
+        Err(AuthProviderError::Upstream(
+            "Browser sign-in is not supported by the in-memory provider".to_string(),
+        ))?
+    }
+
+    async fn exchange_authorization_code(
+        &self,
+        _code: String,
+        _code_verifier: SecretString,
+        _redirect_uri: String,
+    ) -> AppResult<AuthTokens> {
+        Err(AuthProviderError::Upstream(
+            "Browser sign-in is not supported by the in-memory provider".to_string(),
+        ))?
+    }
+
+    async fn list_user_sessions(&self, user_id: String) -> AppResult<Vec<UserSession>> {
+        let store = self.store();
+        if !store.users.contains_key(&user_id) {
+            Err(AuthProviderError::UserNotFound)?;
+        }
+
+        let now = Instant::now();
+        let mut sessions: Vec<UserSession> = store
+            .refresh_tokens
+            .values()
+            .filter(|token| token.user_id == user_id && now < token.expires_at)
+            .filter_map(|token| {
+                let times = store.sessions.get(&token.session_id)?;
+                Some(UserSession {
+                    session_id: token.session_id.clone(),
+                    ip_address: None,
+                    started_at: times.started_at,
+                    last_access_at: times.last_access_at,
+                })
+            })
+            .collect();
+        sessions.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        sessions.dedup_by(|a, b| a.session_id == b.session_id);
+        Ok(sessions)
+    }
+
+    async fn end_all_sessions(&self, user_id: String) -> AppResult<()> {
+        let mut store = self.store();
+        if !store.users.contains_key(&user_id) {
+            Err(AuthProviderError::UserNotFound)?;
+        }
+        store.revoke_sessions(&user_id);
+        Ok(())
+    }
+
+    async fn end_session(&self, session_id: String) -> AppResult<()> {
+        let mut store = self.store();
+        store
+            .access_tokens
+            .retain(|_, token| token.session_id != session_id);
+        store
+            .refresh_tokens
+            .retain(|_, token| token.session_id != session_id);
+        store.prune_sessions();
+        Ok(())
+    }
+
+    async fn delete_user(&self, user_id: String) -> AppResult<()> {
+        let mut store = self.store();
+        store
+            .users
+            .remove(&user_id)
+            .ok_or(AuthProviderError::UserNotFound)?;
+        store.revoke_sessions(&user_id);
+        Ok(())
+    }
+
+    async fn get_user_id(&self, email: Email) -> AppResult<Option<String>> {
+        Ok(self
+            .store()
+            .find_by_email(&email)
+            .and_then(|user| user.user_id.clone()))
+    }
+
+    async fn get_user(&self, user_id: String) -> AppResult<User> {
+        self.store()
+            .users
+            .get(&user_id)
+            .map(public_user)
+            .ok_or(AuthProviderError::UserNotFound.into())
+    }
+
+    async fn update_user(&self, user_update: UserUpdate) -> AppResult<()> {
+        let user_id = user_update
+            .user_id
+            .clone()
+            .ok_or(ValidationError::InvalidInput(
+                "User ID is required for updates".to_string(),
+            ))?;
+
+        let mut store = self.store();
+        if let Some(email) = &user_update.email
+            && store
+                .find_by_email(email)
+                .is_some_and(|user| user.user_id.as_deref() != Some(user_id.as_str()))
+        {
+            Err(AuthProviderError::UserExists)?
+        }
+        store.require_practice(user_update.practice_id.flatten())?;
+
+        store
+            .users
+            .get_mut(&user_id)
+            .ok_or(AuthProviderError::UserNotFound)?
+            .update_user(user_update);
+        Ok(())
+    }
+
+    async fn send_password_reset(&self, email: Email) -> AppResult<()> {
+        let to = self
+            .store()
+            .find_by_email(&email)
+            .map(|user| user.email.clone())
+            .ok_or(AuthProviderError::UserNotFound)?;
+
+        // There is no reset page without Keycloak; the email only stands in for its own
+        self.mailer
+            .send(EmailMessage {
+                to,
+                subject: "Update your password".to_string(),
+                body: "Someone asked to update the password of your EHR account. \
+                       If this was you, follow the link in this email to choose a new one."
+                    .to_string(),
+            })
+            .await
+    }
+
+    async fn send_verification_email(&self, email: Email) -> AppResult<()> {
+        let user = self
+            .store()
+            .find_by_email(&email)
+            .map(public_user)
+            .ok_or(AuthProviderError::UserNotFound)?;
+        if user.email_verified {
+            return Ok(());
+        }
+
+        self.send_verify_email(user.email).await
+    }
+
+    async fn create_practice(&self, practice_id: Uuid) -> AppResult<()> {
+        self.store().practices.insert(practice_id);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenValidator for InMemoryAuthProvider {
+    async fn validate_access_token(&self, token: &str) -> AppResult<AuthContext> {
+        let store = self.store();
+        let issued = store
+            .access_tokens
+            .get(token)
+            .ok_or(AuthProviderError::InvalidToken(
+                "Unknown access token".to_string(),
+            ))?;
+        if Instant::now() >= issued.expires_at {
+            Err(AuthProviderError::InvalidToken(
+                "Token has expired".to_string(),
+            ))?
+        }
+
+        let user = store
+            .users
+            .get(&issued.user_id)
+            .ok_or(AuthProviderError::InvalidToken(
+                "Token subject no longer exists".to_string(),
+            ))?;
+
+        Ok(AuthContext {
+            subject: issued.user_id.clone(),
+            email: Some(user.email.as_ref().expose_secret().to_string()),
+            realm_roles: user
+                .role
+                .iter()
+                .map(|role| role.as_str().to_string())
+                .collect(),
+            practice_id: user.practice_id,
+            scopes: None,
+            pending_mfa: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{error::app_error::AppError, types::user::UserRole};
+
+    #[derive(Default)]
+    struct RecordingMailer {
+        sent: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Mailer for RecordingMailer {
+        async fn send(&self, message: EmailMessage) -> AppResult<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push(message.to.as_ref().expose_secret().to_string());
+            Ok(())
+        }
+    }
+
+    fn provider() -> InMemoryAuthProvider {
+        InMemoryAuthProvider::new(Arc::new(RecordingMailer::default()))
+    }
+
+    // Already verified, so it can log in straight away
+    fn user(email: &str) -> User {
+        let mut user = User::new(
+            email.to_string(),
+            Email::new(email.to_string()).unwrap(),
+            Password::new("Password1!".to_string()).unwrap(),
+            "Test".to_string(),
+            "User".to_string(),
+            Some(UserRole::Clinician),
+        );
+        user.email_verified = true;
+        user
+    }
+
+    async fn login(provider: &InMemoryAuthProvider, email: &str) -> AppResult<AuthTokens> {
+        provider
+            .login_user(
+                Email::new(email.to_string()).unwrap(),
+                Password::new("Password1!".to_string()).unwrap(),
+            )
+            .await
+            .map(|(_, tokens)| tokens)
+    }
+
+    #[tokio::test]
+    async fn test_signup_rejects_duplicate_email() {
+        let provider = provider();
+        provider
+            .signup_user(user("test@example.com"))
+            .await
+            .unwrap();
+
+        let result = provider.signup_user(user("TEST@example.com")).await;
+        assert!(matches!(
+            result,
+            Err(crate::domain::error::app_error::AppError::AuthProvider(
+                AuthProviderError::UserExists
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_login_issues_tokens_that_validate() {
+        let provider = provider();
+        let user_id = provider
+            .signup_user(user("test@example.com"))
+            .await
+            .unwrap();
+
+        let tokens = login(&provider, "test@example.com").await.unwrap();
+        let auth = provider
+            .validate_access_token(tokens.access_token.expose_secret())
+            .await
+            .unwrap();
+
+        assert_eq!(auth.subject, user_id);
+        assert_eq!(auth.role(), Some(UserRole::Clinician));
+        assert!(provider.validate_access_token("forged").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_emails_known_users_only() {
+        let mailer = Arc::new(RecordingMailer::default());
+        let provider = InMemoryAuthProvider::new(mailer.clone());
+        provider
+            .signup_user(user("test@example.com"))
+            .await
+            .unwrap();
+
+        provider
+            .send_password_reset(Email::new("TEST@example.com".to_string()).unwrap())
+            .await
+            .unwrap();
+        assert!(
+            provider
+                .send_password_reset(Email::new("unknown@example.com".to_string()).unwrap())
+                .await
+                .is_err()
+        );
+
+        assert_eq!(*mailer.sent.lock().unwrap(), vec!["test@example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_unverified_user_is_emailed_and_cannot_log_in() {
+        let mailer = Arc::new(RecordingMailer::default());
+        let provider = InMemoryAuthProvider::new(mailer.clone());
+        let user_id = provider
+            .signup_user(User {
+                email_verified: false,
+                ..user("test@example.com")
+            })
+            .await
+            .unwrap();
+        assert_eq!(*mailer.sent.lock().unwrap(), vec!["test@example.com"]);
+
+        assert!(matches!(
+            login(&provider, "test@example.com").await,
+            Err(crate::domain::error::app_error::AppError::AuthProvider(
+                AuthProviderError::EmailNotVerified
+            ))
+        ));
+
+        provider
+            .update_user(UserUpdate {
+                user_id: Some(user_id),
+                email_verified: Some(true),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        login(&provider, "test@example.com").await.unwrap();
+
+        // Once verified there is nothing left to send
+        provider
+            .send_verification_email(Email::new("test@example.com".to_string()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(mailer.sent.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_login_rejects_wrong_password() {
+        let provider = provider();
+        provider
+            .signup_user(user("test@example.com"))
+            .await
+            .unwrap();
+
+        let result = provider
+            .login_user(
+                Email::new("test@example.com".to_string()).unwrap(),
+                Password::new("Different2@".to_string()).unwrap(),
+            )
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_logout_revokes_sessions() {
+        let provider = provider();
+        provider
+            .signup_user(user("test@example.com"))
+            .await
+            .unwrap();
+        let tokens = login(&provider, "test@example.com").await.unwrap();
+
+        provider
+            .logout_user(tokens.refresh_token.clone())
+            .await
+            .unwrap();
+        assert!(
+            provider
+                .validate_access_token(tokens.access_token.expose_secret())
+                .await
+                .is_err()
+        );
+
+        // Logging out twice is not an error
+        provider.logout_user(tokens.refresh_token).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_and_end_session_revokes() {
+        let provider = provider();
+        provider
+            .signup_user(user("test@example.com"))
+            .await
+            .unwrap();
+        let tokens = login(&provider, "test@example.com").await.unwrap();
+
+        let rotated = provider
+            .refresh_user_tokens(tokens.refresh_token.clone())
+            .await
+            .unwrap();
+        assert_eq!(rotated.session_id, tokens.session_id);
+        assert!(
+            provider
+                .refresh_user_tokens(tokens.refresh_token)
+                .await
+                .is_err()
+        );
+
+        provider
+            .end_session(rotated.session_id.clone().unwrap())
+            .await
+            .unwrap();
+        assert!(
+            provider
+                .validate_access_token(rotated.access_token.expose_secret())
+                .await
+                .is_err()
+        );
+        assert!(
+            provider
+                .refresh_user_tokens(rotated.refresh_token)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sessions_listed_until_ended() {
+        let provider = provider();
+        let user_id = provider
+            .signup_user(user("test@example.com"))
+            .await
+            .unwrap();
+        let first = login(&provider, "test@example.com").await.unwrap();
+        let second = login(&provider, "test@example.com").await.unwrap();
+        provider
+            .refresh_user_tokens(first.refresh_token.clone())
+            .await
+            .unwrap();
+
+        let sessions = provider.list_user_sessions(user_id.clone()).await.unwrap();
+        assert_eq!(sessions.len(), 2);
+
+        provider
+            .end_session(first.session_id.clone().unwrap())
+            .await
+            .unwrap();
+        let sessions = provider.list_user_sessions(user_id.clone()).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(Some(&sessions[0].session_id), second.session_id.as_ref());
+
+        provider.end_all_sessions(user_id.clone()).await.unwrap();
+        assert!(
+            provider
+                .list_user_sessions(user_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(matches!(
+            provider.list_user_sessions("unknown".to_string()).await,
+            Err(AppError::AuthProvider(AuthProviderError::UserNotFound))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_user_changes_role_and_checks_email() {
+        let provider = provider();
+        let user_id = provider
+            .signup_user(user("test@example.com"))
+            .await
+            .unwrap();
+        provider
+            .signup_user(user("taken@example.com"))
+            .await
+            .unwrap();
+
+        let update = |email: Option<&str>, role| UserUpdate {
+            user_id: Some(user_id.clone()),
+            email: email.map(|e| Email::new(e.to_string()).unwrap()),
+            role,
+            ..Default::default()
+        };
+
+        assert!(
+            provider
+                .update_user(update(Some("taken@example.com"), None))
+                .await
+                .is_err()
+        );
+        provider
+            .update_user(update(None, Some(Some(UserRole::Biller))))
+            .await
+            .unwrap();
+
+        let stored = provider.get_user(user_id.clone()).await.unwrap();
+        assert_eq!(stored.role, Some(UserRole::Biller));
+        assert!(stored.password.is_none());
+
+        provider
+            .update_user(update(None, Some(None)))
+            .await
+            .unwrap();
+        assert_eq!(provider.get_user(user_id).await.unwrap().role, None);
+    }
+}
@@ -0,0 +1,211 @@
+// Staff invitations. An owner or admin invites an address with a preassigned role,
+// and the emailed token is single use, expires, and is only ever stored hashed.
+// Accepting creates the account with that role in the inviter's practice; following
+// the link has already proven the address, so it starts out verified.
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{
+            AppError, AppResult, AuthProviderError, AuthorizationError, DatabaseError,
+            ValidationError,
+        },
+        interfaces::mailer::EmailMessage,
+        types::{
+            email::Email,
+            password::Password,
+            user::{User, UserRole},
+        },
+    },
+    services::{
+        audit::{self, AuditAction, AuditEvent},
+        user_sync,
+    },
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+pub struct NewInvitation {
+    pub id: Uuid,
+    pub expires_in: u64,
+}
+
+pub struct InvitationAcceptance {
+    pub token: String,
+    pub password: Password,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn invalid_invitation() -> AppError {
+    ValidationError::InvalidInput("Invitation is invalid, already used or expired".to_string())
+        .into()
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn create(
+    state: &AppState,
+    ctx: &RequestContext,
+    email: Email,
+    role: UserRole,
+) -> AppResult<NewInvitation> {
+    let settings = &state.settings.registration;
+    if !settings.invitations_enabled {
+        Err(AuthorizationError::InvitationsDisabled)?;
+    }
+    let auth = ctx.require_auth()?;
+    let invited_by = Uuid::parse_str(&auth.subject).map_err(AppError::internal)?;
+
+    if state
+        .auth_provider
+        .read()
+        .await
+        .get_user_id(email.clone())
+        .await?
+        .is_some()
+    {
+        Err(AuthProviderError::UserExists)?;
+    }
+
+    let id = Uuid::new_v4();
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+    let db = state.db.read().await.clone();
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| DatabaseError::context("begin transaction", e))?;
+    // A fresh invitation replaces any still pending for the address
+    sqlx::query("DELETE FROM invitations WHERE email = $1 AND accepted_at IS NULL")
+        .bind(email.as_ref().expose_secret())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::context("replace pending invitation", e))?;
+    sqlx::query(
+        r#"
+        INSERT INTO invitations
+            (id, token_hash, email, email_display, role, invited_by, practice_id, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, NOW() + make_interval(secs => $8))
+        "#,
+    )
+    .bind(id)
+    .bind(hash_token(&token))
+    .bind(email.as_ref().expose_secret())
+    .bind(email.display().expose_secret())
+    .bind(role.as_str())
+    .bind(invited_by)
+    .bind(auth.practice_id)
+    .bind(settings.invitation_ttl_secs as f64)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| DatabaseError::context("store invitation", e))?;
+
+    // Only commit once the link is on its way; an unsent invitation could never be accepted
+    state
+        .mailer
+        .send(EmailMessage {
+            to: email,
+            subject: "You have been invited to LGR EHR".to_string(),
+            body: format!(
+                "You have been invited to join LGR EHR as {role}. Follow this link to choose \
+                 a password and activate your account:\n\n{url}?token={token}\n\nThe link \
+                 can be used once and expires in {hours} hours.",
+                role = role.as_str(),
+                url = settings.invitation_url,
+                hours = settings.invitation_ttl_secs / 3600,
+            ),
+        })
+        .await?;
+    tx.commit()
+        .await
+        .map_err(|e| DatabaseError::context("commit invitation", e))?;
+
+    let event = AuditEvent {
+        user_id: Some(invited_by),
+        action: AuditAction::InvitationCreated,
+        resource_type: "invitation",
+        resource_id: Some(id.to_string()),
+        reason: None,
+    };
+    audit::record(&db, ctx, event).await?;
+
+    Ok(NewInvitation {
+        id,
+        expires_in: settings.invitation_ttl_secs,
+    })
+}
+
+// Creates the invited account and returns its id
+#[tracing::instrument(skip_all)]
+pub async fn accept(
+    state: &AppState,
+    ctx: &RequestContext,
+    acceptance: InvitationAcceptance,
+) -> AppResult<String> {
+    if !state.settings.registration.invitations_enabled {
+        Err(AuthorizationError::InvitationsDisabled)?;
+    }
+
+    let db = state.db.read().await.clone();
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| DatabaseError::context("begin transaction", e))?;
+    // The claim holds the row lock until commit, so a concurrent accept waits and then misses
+    let (id, email, role, practice_id): (Uuid, String, String, Option<Uuid>) = sqlx::query_as(
+        r#"
+        UPDATE invitations SET accepted_at = NOW()
+        WHERE token_hash = $1 AND accepted_at IS NULL AND expires_at > NOW()
+        RETURNING id, email_display, role, practice_id
+        "#,
+    )
+    .bind(hash_token(&acceptance.token))
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| DatabaseError::context("claim invitation", e))?
+    .ok_or_else(invalid_invitation)?;
+
+    let email = Email::parse(email, state.settings.email_case_insensitive_local_part)?;
+    let mut user = User::new(
+        email.as_ref().expose_secret().to_string(),
+        email,
+        acceptance.password,
+        acceptance.first_name,
+        acceptance.last_name,
+        Some(role.parse()?),
+    );
+    user.email_verified = true;
+    user.practice_id = practice_id;
+
+    // A failed signup rolls the claim back, leaving the invitation usable
+    let user_id = user_sync::create_user(state, user).await?;
+    let account = Uuid::parse_str(&user_id).map_err(AppError::internal)?;
+
+    sqlx::query("UPDATE invitations SET accepted_user_id = $2 WHERE id = $1")
+        .bind(id)
+        .bind(account)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::context("record accepted invitation", e))?;
+    if let Err(e) = tx.commit().await {
+        // The account exists either way; a retried link then finds the address taken
+        tracing::error!(%user_id, error = %e, "Failed to mark invitation accepted");
+    }
+
+    let event = AuditEvent {
+        user_id: Some(account),
+        action: AuditAction::InvitationAccepted,
+        resource_type: "invitation",
+        resource_id: Some(id.to_string()),
+        reason: None,
+    };
+    audit::record(&db, ctx, event).await?;
+
+    Ok(user_id)
+}
@@ -0,0 +1,393 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation, decode, decode_header,
+    jwk::{JwkSet, PublicKeyUse},
+};
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    error::app_error::{AppResult, AuthProviderError},
+    interfaces::token_validator::TokenValidator,
+    types::{auth_context::AuthContext, practice, token::TokenClaims},
+};
+
+// Asymmetric algorithms Keycloak can sign access tokens with
+const ALLOWED_ALGORITHMS: [Algorithm; 7] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+];
+
+// Stops tokens with made-up key ids from turning into a flood of JWKS requests
+const DEFAULT_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct JwksCache {
+    keys: HashMap<String, DecodingKey>,
+    fetched_at: Option<Instant>,
+}
+
+pub struct JwksTokenValidator {
+    client: reqwest::Client,
+    jwks_endpoint: String,
+    issuer: String,
+    audience: String,
+    min_refresh_interval: Duration,
+    cache: RwLock<JwksCache>,
+}
+
+impl JwksTokenValidator {
+    pub fn new(
+        client: reqwest::Client,
+        jwks_endpoint: String,
+        issuer: String,
+        audience: String,
+    ) -> Self {
+        Self {
+            client,
+            jwks_endpoint,
+            issuer,
+            audience,
+            min_refresh_interval: DEFAULT_MIN_REFRESH_INTERVAL,
+            cache: RwLock::new(JwksCache::default()),
+        }
+    }
+
+    pub fn with_min_refresh_interval(mut self, interval: Duration) -> Self {
+        self.min_refresh_interval = interval;
+        self
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn fetch_keys(&self) -> AppResult<HashMap<String, DecodingKey>> {
+        let response = self
+            .client
+            .get(&self.jwks_endpoint)
+            .send()
+            .await
+            .map_err(|e| {
+                AuthProviderError::Network(format!("Failed to send request to Keycloak: {e}"))
+            })?;
+
+        if !response.status().is_success() {
+            return Err(AuthProviderError::Upstream(format!(
+                "Failed to fetch signing keys from Keycloak: {}",
+                response.status()
+            )))?;
+        }
+
+        let jwks: JwkSet = response.json().await.map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to parse Keycloak signing keys: {e}"))
+        })?;
+
+        let keys = jwks
+            .keys
+            .iter()
+            .filter(|jwk| {
+                !matches!(
+                    jwk.common.public_key_use,
+                    Some(PublicKeyUse::Encryption | PublicKeyUse::Other(_))
+                )
+            })
+            .filter_map(|jwk| {
+                let kid = jwk.common.key_id.clone()?;
+                let key = DecodingKey::from_jwk(jwk).ok()?;
+                Some((kid, key))
+            })
+            .collect();
+
+        Ok(keys)
+    }
+
+    async fn decoding_key(&self, kid: &str) -> AppResult<DecodingKey> {
+        if let Some(key) = self.cache.read().await.keys.get(kid) {
+            return Ok(key.clone());
+        }
+
+        // Unknown key id: the realm may have rotated its signing keys
+        let mut cache = self.cache.write().await;
+        if let Some(key) = cache.keys.get(kid) {
+            return Ok(key.clone());
+        }
+
+        let recently_fetched = cache
+            .fetched_at
+            .is_some_and(|fetched_at| fetched_at.elapsed() < self.min_refresh_interval);
+        if !recently_fetched {
+            cache.keys = self.fetch_keys().await?;
+            cache.fetched_at = Some(Instant::now());
+        }
+
+        cache.keys.get(kid).cloned().ok_or_else(|| {
+            AuthProviderError::InvalidToken("Token signed with an unknown key".to_string()).into()
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenValidator for JwksTokenValidator {
+    #[tracing::instrument(skip_all)]
+    async fn validate_access_token(&self, token: &str) -> AppResult<AuthContext> {
+        let header = decode_header(token)
+            .map_err(|e| AuthProviderError::InvalidToken(format!("Malformed token: {e}")))?;
+
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(AuthProviderError::InvalidToken(format!(
+                "Unsupported signing algorithm: {:?}",
+                header.alg
+            )))?;
+        }
+
+        let kid = header.kid.ok_or(AuthProviderError::InvalidToken(
+            "Token is missing a key id".to_string(),
+        ))?;
+        let key = self.decoding_key(&kid).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<TokenClaims>(token, &key, &validation)
+            .map_err(|e| AuthProviderError::InvalidToken(e.to_string()))?
+            .claims;
+
+        Ok(AuthContext {
+            subject: claims.sub,
+            email: claims.email,
+            realm_roles: claims.realm_access.roles,
+            practice_id: practice::from_groups(&claims.groups),
+            scopes: None,
+            pending_mfa: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+    use jsonwebtoken::{EncodingKey, Header, encode};
+    use poem::{EndpointExt, Route, Server, get, handler, listener::TcpListener, web::Data};
+    use tokio::sync::Mutex;
+
+    use super::*;
+
+    const ISSUER: &str = "http://localhost:8080/realms/ehr";
+    const AUDIENCE: &str = "ehr-backend";
+
+    struct TestKey {
+        kid: &'static str,
+        pem: &'static [u8],
+        jwk: &'static str,
+    }
+
+    const KEY_1: TestKey = TestKey {
+        kid: "key-1",
+        pem: include_bytes!("../../tests/fixtures/jwt_signing_key_1.pem"),
+        jwk: include_str!("../../tests/fixtures/jwt_signing_key_1.jwk.json"),
+    };
+    const KEY_2: TestKey = TestKey {
+        kid: "key-2",
+        pem: include_bytes!("../../tests/fixtures/jwt_signing_key_2.pem"),
+        jwk: include_str!("../../tests/fixtures/jwt_signing_key_2.jwk.json"),
+    };
+
+    impl TestKey {
+        fn jwk(&self) -> serde_json::Value {
+            serde_json::from_str(self.jwk).unwrap()
+        }
+
+        fn sign(&self, claims: serde_json::Value) -> String {
+            let mut header = Header::new(Algorithm::RS256);
+            header.kid = Some(self.kid.to_string());
+            encode(
+                &header,
+                &claims,
+                &EncodingKey::from_rsa_pem(self.pem).unwrap(),
+            )
+            .unwrap()
+        }
+    }
+
+    fn claims() -> serde_json::Value {
+        serde_json::json!({
+            "sub": "1b4e28ba-2fa1-11d2-883f-0016d3cca427",
+            "iss": ISSUER,
+            "aud": [AUDIENCE, "account"],
+            "exp": jsonwebtoken::get_current_timestamp() + 300,
+            "email": "test@example.com",
+            "realm_access": { "roles": ["admin", "offline_access"] }
+        })
+    }
+
+    type SharedJwks = Arc<Mutex<serde_json::Value>>;
+
+    #[handler]
+    async fn serve_jwks(Data(jwks): Data<&SharedJwks>) -> poem::web::Json<serde_json::Value> {
+        poem::web::Json(jwks.lock().await.clone())
+    }
+
+    async fn spawn_jwks_server(keys: &[&TestKey]) -> (String, SharedJwks) {
+        let jwks = Arc::new(Mutex::new(serde_json::json!({
+            "keys": keys.iter().map(|k| k.jwk()).collect::<Vec<_>>()
+        })));
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let app = Route::new()
+            .at("/certs", get(serve_jwks))
+            .data(jwks.clone());
+        tokio::spawn(async move {
+            Server::new(TcpListener::bind(format!("127.0.0.1:{port}")))
+                .run(app)
+                .await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        (format!("http://127.0.0.1:{port}/certs"), jwks)
+    }
+
+    fn validator(jwks_endpoint: String) -> JwksTokenValidator {
+        JwksTokenValidator::new(
+            reqwest::Client::new(),
+            jwks_endpoint,
+            ISSUER.to_string(),
+            AUDIENCE.to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_valid_token() {
+        let (endpoint, _) = spawn_jwks_server(&[&KEY_1]).await;
+        let validator = validator(endpoint);
+
+        let auth = validator
+            .validate_access_token(&KEY_1.sign(claims()))
+            .await
+            .unwrap();
+
+        assert_eq!(auth.subject, "1b4e28ba-2fa1-11d2-883f-0016d3cca427");
+        assert_eq!(auth.email.as_deref(), Some("test@example.com"));
+        assert_eq!(auth.realm_roles, vec!["admin", "offline_access"]);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_claims() {
+        let (endpoint, _) = spawn_jwks_server(&[&KEY_1]).await;
+        let validator = validator(endpoint);
+
+        let mut wrong_issuer = claims();
+        wrong_issuer["iss"] = "http://localhost:8080/realms/other".into();
+        let mut wrong_audience = claims();
+        wrong_audience["aud"] = "account".into();
+        let mut expired = claims();
+        expired["exp"] = (jsonwebtoken::get_current_timestamp() - 3600).into();
+        let mut missing_exp = claims();
+        missing_exp.as_object_mut().unwrap().remove("exp");
+
+        for (i, claims) in [wrong_issuer, wrong_audience, expired, missing_exp]
+            .into_iter()
+            .enumerate()
+        {
+            let result = validator.validate_access_token(&KEY_1.sign(claims)).await;
+            assert!(result.is_err(), "Test case {} failed", i);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_signature() {
+        let (endpoint, _) = spawn_jwks_server(&[&KEY_1]).await;
+        let validator = validator(endpoint);
+
+        // Signed with key 2 but claiming to be key 1
+        let token = KEY_2.sign(claims());
+        let (_, rest) = token.split_once('.').unwrap();
+        let forged_header = URL_SAFE_NO_PAD.encode(br#"{"alg":"RS256","typ":"JWT","kid":"key-1"}"#);
+        let forged = format!("{forged_header}.{rest}");
+
+        assert!(validator.validate_access_token(&forged).await.is_err());
+        assert!(
+            validator
+                .validate_access_token("not-a-token")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_symmetric_algorithm() {
+        let (endpoint, _) = spawn_jwks_server(&[&KEY_1]).await;
+        let validator = validator(endpoint);
+
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(KEY_1.kid.to_string());
+        let token = encode(&header, &claims(), &EncodingKey::from_secret(b"secret")).unwrap();
+
+        assert!(validator.validate_access_token(&token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refreshes_keys_when_kid_changes() {
+        let (endpoint, jwks) = spawn_jwks_server(&[&KEY_1]).await;
+        let validator = validator(endpoint).with_min_refresh_interval(Duration::ZERO);
+
+        assert!(
+            validator
+                .validate_access_token(&KEY_1.sign(claims()))
+                .await
+                .is_ok()
+        );
+
+        // Keycloak rotates to a new key
+        *jwks.lock().await = serde_json::json!({ "keys": [KEY_2.jwk()] });
+
+        assert!(
+            validator
+                .validate_access_token(&KEY_2.sign(claims()))
+                .await
+                .is_ok()
+        );
+        assert!(
+            validator
+                .validate_access_token(&KEY_1.sign(claims()))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_kid_refresh_is_rate_limited() {
+        let (endpoint, jwks) = spawn_jwks_server(&[&KEY_1]).await;
+        let validator = validator(endpoint);
+
+        assert!(
+            validator
+                .validate_access_token(&KEY_1.sign(claims()))
+                .await
+                .is_ok()
+        );
+
+        *jwks.lock().await = serde_json::json!({ "keys": [KEY_1.jwk(), KEY_2.jwk()] });
+
+        // The cache was refreshed moments ago, so the new key isn't fetched yet
+        assert!(
+            validator
+                .validate_access_token(&KEY_2.sign(claims()))
+                .await
+                .is_err()
+        );
+    }
+}
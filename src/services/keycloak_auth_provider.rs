@@ -1,14 +1,23 @@
-use reqwest::StatusCode;
+use std::time::{Duration, Instant};
+
+use reqwest::{RequestBuilder, Response, StatusCode, header::LOCATION};
 use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use uuid::Uuid;
 
 use crate::{
     domain::{
-        error::app_error::{AppResult, AuthProviderError},
+        error::app_error::{AppResult, AuthProviderError, ValidationError},
         interfaces::auth_provider::AuthProvider,
         types::{
+            authorization::AuthorizationRequest,
             email::Email,
             password::Password,
-            user::{User, UserUpdate},
+            practice,
+            session::UserSession,
+            token::{AuthTokens, TokenClaims},
+            user::{User, UserRole, UserUpdate},
         },
     },
     utils::config::AppSettings,
@@ -16,7 +25,11 @@ use crate::{
 
 pub struct KeycloakEndpoints {
     pub admin_enpoint: String,
+    pub authorization_endpoint: String,
     pub token_endpoint: String,
+    pub introspection_endpoint: String,
+    pub revocation_endpoint: String,
+    pub jwks_endpoint: String,
     pub users_endpoint: String,
     pub client_id: String,
     pub client_secret: Option<SecretString>,
@@ -24,40 +37,167 @@ pub struct KeycloakEndpoints {
 
 impl KeycloakEndpoints {
     pub fn from_config(config: &AppSettings) -> Self {
-        let admin_enpoint = format!(
-            "{}/admin/realms/{}",
-            config.keycloak_base_url, config.keycloak_realm
-        );
-        let token_endpoint = format!(
-            "{}/realms/{}/protocol/openid-connect/token",
-            config.keycloak_base_url, config.keycloak_realm
+        let mut endpoints = Self::new(
+            &config.keycloak_base_url,
+            &config.keycloak_realm,
+            config.keycloak_client_id.clone(),
+            config.keycloak_client_secret.clone(),
         );
+        // Browsers reach Keycloak on the issuer URL, not the one the API uses
+        endpoints.authorization_endpoint =
+            format!("{}/protocol/openid-connect/auth", config.keycloak_issuer);
+        endpoints
+    }
+
+    pub fn new(
+        base_url: &str,
+        realm: &str,
+        client_id: String,
+        client_secret: Option<SecretString>,
+    ) -> Self {
+        let admin_enpoint = format!("{base_url}/admin/realms/{realm}");
+        let openid_endpoint = format!("{base_url}/realms/{realm}/protocol/openid-connect");
+        let authorization_endpoint = format!("{openid_endpoint}/auth");
+        let token_endpoint = format!("{openid_endpoint}/token");
+        let introspection_endpoint = format!("{token_endpoint}/introspect");
+        let revocation_endpoint = format!("{openid_endpoint}/revoke");
+        let jwks_endpoint = format!("{openid_endpoint}/certs");
         let users_endpoint = format!("{admin_enpoint}/users");
         Self {
             admin_enpoint,
+            authorization_endpoint,
             token_endpoint,
+            introspection_endpoint,
+            revocation_endpoint,
+            jwks_endpoint,
             users_endpoint,
-            client_id: config.keycloak_client_id.clone(),
-            client_secret: config.keycloak_client_secret.clone(),
+            client_id,
+            client_secret,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    id_token: Option<String>,
+    token_type: String,
+    expires_in: u64,
+    refresh_expires_in: u64,
+    session_state: Option<String>,
+}
+
+impl From<TokenResponse> for AuthTokens {
+    fn from(response: TokenResponse) -> Self {
+        AuthTokens {
+            access_token: SecretString::from(response.access_token),
+            refresh_token: SecretString::from(response.refresh_token),
+            id_token: response.id_token.map(SecretString::from),
+            token_type: response.token_type,
+            expires_in: response.expires_in,
+            refresh_expires_in: response.refresh_expires_in,
+            session_id: response.session_state,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct TokenErrorResponse {
+    error: String,
+    #[serde(default)]
+    error_description: String,
+}
+
+#[derive(Deserialize)]
+struct IntrospectionResponse {
+    active: bool,
+    sub: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ClientCredentialsResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserRepresentation {
+    id: String,
+    username: String,
+    email: Option<String>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    enabled: Option<bool>,
+}
+
+// Keycloak reports session times in epoch milliseconds
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserSessionRepresentation {
+    id: String,
+    ip_address: Option<String>,
+    start: u64,
+    last_access: u64,
+}
+
+impl From<UserSessionRepresentation> for UserSession {
+    fn from(session: UserSessionRepresentation) -> Self {
+        UserSession {
+            session_id: session.id,
+            ip_address: session.ip_address,
+            started_at: session.start / 1000,
+            last_access_at: session.last_access / 1000,
         }
     }
 }
 
+// Keycloak's role-mapping endpoints only need the role's id and name
+#[derive(Deserialize, Serialize)]
+struct RoleRepresentation {
+    id: String,
+    name: String,
+}
+
+// Practices are top-level groups; membership is all the API reads or writes
+#[derive(Deserialize)]
+struct GroupRepresentation {
+    id: String,
+    name: String,
+}
+
+// How long the link in a password reset email stays valid
+const PASSWORD_RESET_LIFESPAN_SECS: u64 = 15 * 60;
+
+// Refresh the admin token this long before Keycloak expires it
+const ADMIN_TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+struct CachedAdminToken {
+    access_token: String,
+    refresh_at: Instant,
+}
+
 pub struct KeycloakUserStore {
     pub client: reqwest::Client,
     pub endpoints: KeycloakEndpoints,
+    // Held across the client_credentials round-trip so concurrent callers share one refresh
+    admin_token: Mutex<Option<CachedAdminToken>>,
 }
 
 impl KeycloakUserStore {
     pub fn new(client: reqwest::Client, endpoints: KeycloakEndpoints) -> Self {
-        Self { client, endpoints }
+        Self {
+            client,
+            endpoints,
+            admin_token: Mutex::new(None),
+        }
     }
-}
 
-#[async_trait::async_trait]
-impl AuthProvider for KeycloakUserStore {
     #[tracing::instrument(skip_all)]
-    async fn retrieve_auth_token(&self) -> AppResult<String> {
+    async fn fetch_admin_token(&self) -> AppResult<CachedAdminToken> {
         let form = [
             ("grant_type", "client_credentials"),
             ("client_id", &self.endpoints.client_id),
@@ -90,84 +230,717 @@ impl AuthProvider for KeycloakUserStore {
             )))?;
         }
 
-        let token: serde_json::Value = response.json().await.map_err(|e| {
+        let token: ClientCredentialsResponse = response.json().await.map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to parse Keycloak response: {e}"))
+        })?;
+
+        let lifetime = Duration::from_secs(token.expires_in);
+        let margin = ADMIN_TOKEN_REFRESH_MARGIN.min(lifetime / 2);
+        Ok(CachedAdminToken {
+            access_token: token.access_token,
+            refresh_at: Instant::now() + lifetime - margin,
+        })
+    }
+
+    // Drops the cached admin token unless another request already replaced it
+    async fn invalidate_admin_token(&self, rejected_token: &str) {
+        let mut cached = self.admin_token.lock().await;
+        if cached
+            .as_ref()
+            .is_some_and(|token| token.access_token == rejected_token)
+        {
+            *cached = None;
+        }
+    }
+
+    // Sends an admin API request, retrying once with a fresh token if Keycloak rejects the cached one
+    async fn send_admin<F>(&self, request: F) -> AppResult<Response>
+    where
+        F: Fn(&str) -> RequestBuilder,
+    {
+        let token = self.retrieve_auth_token().await?;
+        let response = request(&token).send().await.map_err(|e| {
+            AuthProviderError::Network(format!("Failed to send request to Keycloak: {e}"))
+        })?;
+
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        tracing::debug!("Keycloak rejected the cached admin token, retrying with a new one");
+        self.invalidate_admin_token(&token).await;
+        let token = self.retrieve_auth_token().await?;
+        let response = request(&token).send().await.map_err(|e| {
+            AuthProviderError::Network(format!("Failed to send request to Keycloak: {e}"))
+        })?;
+
+        Ok(response)
+    }
+
+    // Client authentication parameters for the OIDC token, introspection and revocation endpoints
+    fn client_auth_form(&self) -> Vec<(&str, &str)> {
+        let mut form = vec![("client_id", self.endpoints.client_id.as_str())];
+        if let Some(secret) = &self.endpoints.client_secret {
+            form.push(("client_secret", secret.expose_secret()));
+        }
+        form
+    }
+
+    // Resolves the owner of a refresh token, or None if Keycloak no longer considers it active
+    #[tracing::instrument(skip_all)]
+    async fn introspect_refresh_token(
+        &self,
+        refresh_token: &SecretString,
+    ) -> AppResult<Option<String>> {
+        let mut form = self.client_auth_form();
+        form.push(("token", refresh_token.expose_secret()));
+        form.push(("token_type_hint", "refresh_token"));
+
+        let response = self
+            .client
+            .post(&self.endpoints.introspection_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| {
+                AuthProviderError::Network(format!("Failed to send request to Keycloak: {e}"))
+            })?;
+
+        if !response.status().is_success() {
+            return Err(AuthProviderError::Upstream(format!(
+                "Failed to introspect token with Keycloak: {}",
+                response.status()
+            )))?;
+        }
+
+        let introspection: IntrospectionResponse = response.json().await.map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to parse Keycloak response: {e}"))
+        })?;
+
+        Ok(introspection.sub.filter(|_| introspection.active))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn realm_role_mappings(&self, user_id: &str) -> AppResult<Vec<RoleRepresentation>> {
+        let url = format!(
+            "{}/{}/role-mappings/realm",
+            &self.endpoints.users_endpoint, user_id
+        );
+        let response = self
+            .send_admin(|token| self.client.get(&url).bearer_auth(token))
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => {}
+            StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+            status => Err(AuthProviderError::Upstream(format!(
+                "Failed to get realm roles from Keycloak: {status}"
+            )))?,
+        }
+
+        Ok(response.json().await.map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to parse Keycloak response: {e}"))
+        })?)
+    }
+
+    // Maps the role onto the user, then drops any other EHR role they held; no role
+    // drops them all
+    #[tracing::instrument(skip_all)]
+    async fn set_realm_role(&self, user_id: &str, role: Option<UserRole>) -> AppResult<()> {
+        let url = format!(
+            "{}/{}/role-mappings/realm",
+            &self.endpoints.users_endpoint, user_id
+        );
+        let current = self.realm_role_mappings(user_id).await?;
+
+        if let Some(role) = role
+            && !current.iter().any(|mapped| mapped.name == role.as_str())
+        {
+            let role_url = format!("{}/roles/{}", &self.endpoints.admin_enpoint, role.as_str());
+            let response = self
+                .send_admin(|token| self.client.get(&role_url).bearer_auth(token))
+                .await?;
+
+            if !response.status().is_success() {
+                return Err(AuthProviderError::Upstream(format!(
+                    "Failed to get realm role '{}' from Keycloak: {}",
+                    role.as_str(),
+                    response.status()
+                )))?;
+            }
+
+            let representation: RoleRepresentation = response.json().await.map_err(|e| {
+                AuthProviderError::Upstream(format!("Failed to parse Keycloak response: {e}"))
+            })?;
+            let roles = [representation];
+            let response = self
+                .send_admin(|token| self.client.post(&url).bearer_auth(token).json(&roles))
+                .await?;
+
+            match response.status() {
+                StatusCode::NO_CONTENT => {}
+                StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+                status => Err(AuthProviderError::Upstream(format!(
+                    "Failed to add realm role in Keycloak: {status}"
+                )))?,
+            }
+        }
+
+        let stale: Vec<RoleRepresentation> = current
+            .into_iter()
+            .filter(|mapped| {
+                UserRole::from_realm_role(&mapped.name).is_some_and(|r| Some(r) != role)
+            })
+            .collect();
+        if stale.is_empty() {
+            return Ok(());
+        }
+
+        let response = self
+            .send_admin(|token| self.client.delete(&url).bearer_auth(token).json(&stale))
+            .await?;
+
+        match response.status() {
+            StatusCode::NO_CONTENT => Ok(()),
+            StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+            status => Err(AuthProviderError::Upstream(format!(
+                "Failed to remove realm roles in Keycloak: {status}"
+            )))?,
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn user_groups(&self, user_id: &str) -> AppResult<Vec<GroupRepresentation>> {
+        let url = format!("{}/{}/groups", &self.endpoints.users_endpoint, user_id);
+        let response = self
+            .send_admin(|token| self.client.get(&url).bearer_auth(token))
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => {}
+            StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+            status => Err(AuthProviderError::Upstream(format!(
+                "Failed to get groups from Keycloak: {status}"
+            )))?,
+        }
+
+        Ok(response.json().await.map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to parse Keycloak response: {e}"))
+        })?)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn practice_group(&self, practice_id: Uuid) -> AppResult<GroupRepresentation> {
+        let url = format!("{}/groups", &self.endpoints.admin_enpoint);
+        let name = practice::group_name(practice_id);
+        let response = self
+            .send_admin(|token| {
+                self.client
+                    .get(&url)
+                    .bearer_auth(token)
+                    .query(&[("search", name.as_str()), ("exact", "true")])
+            })
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AuthProviderError::Upstream(format!(
+                "Failed to search groups in Keycloak: {}",
+                response.status()
+            )))?;
+        }
+
+        let groups: Vec<GroupRepresentation> = response.json().await.map_err(|e| {
             AuthProviderError::Upstream(format!("Failed to parse Keycloak response: {e}"))
         })?;
-        let access_token = token.get("access_token").and_then(|t| t.as_str()).ok_or(
-            AuthProviderError::Upstream("Missing access token in Keycloak response".to_string()),
-        )?;
-        Ok(access_token.to_string())
+        Ok(groups
+            .into_iter()
+            .find(|group| group.name == name)
+            .ok_or(AuthProviderError::PracticeNotFound)?)
+    }
+
+    // Joins the user to the practice's group, then leaves any other practice they belonged
+    // to; no practice leaves them all
+    #[tracing::instrument(skip_all)]
+    async fn set_practice(&self, user_id: &str, practice_id: Option<Uuid>) -> AppResult<()> {
+        let group = match practice_id {
+            Some(practice_id) => Some(self.practice_group(practice_id).await?),
+            None => None,
+        };
+        let current = self.user_groups(user_id).await?;
+
+        if let Some(group) = &group
+            && !current.iter().any(|member| member.id == group.id)
+        {
+            let url = format!(
+                "{}/{}/groups/{}",
+                &self.endpoints.users_endpoint, user_id, group.id
+            );
+            let response = self
+                .send_admin(|token| self.client.put(&url).bearer_auth(token))
+                .await?;
+
+            match response.status() {
+                StatusCode::NO_CONTENT => {}
+                StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+                status => Err(AuthProviderError::Upstream(format!(
+                    "Failed to add user to group in Keycloak: {status}"
+                )))?,
+            }
+        }
+
+        for stale in current.iter().filter(|member| {
+            group.as_ref().is_none_or(|group| member.id != group.id)
+                && practice::from_groups(&[&member.name]).is_some()
+        }) {
+            let url = format!(
+                "{}/{}/groups/{}",
+                &self.endpoints.users_endpoint, user_id, stale.id
+            );
+            let response = self
+                .send_admin(|token| self.client.delete(&url).bearer_auth(token))
+                .await?;
+
+            match response.status() {
+                StatusCode::NO_CONTENT | StatusCode::NOT_FOUND => {}
+                status => Err(AuthProviderError::Upstream(format!(
+                    "Failed to remove user from group in Keycloak: {status}"
+                )))?,
+            }
+        }
+
+        Ok(())
     }
 
     #[tracing::instrument(skip_all)]
-    async fn signup_user(&self, user: User) -> AppResult<()> {
+    async fn send_verify_email(&self, user_id: &str) -> AppResult<()> {
+        let url = format!(
+            "{}/{}/send-verify-email",
+            &self.endpoints.users_endpoint, user_id
+        );
+        let response = self
+            .send_admin(|token| self.client.put(&url).bearer_auth(token))
+            .await?;
+
+        match response.status() {
+            StatusCode::NO_CONTENT => Ok(()),
+            StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+            status => Err(AuthProviderError::Upstream(format!(
+                "Failed to send verification email through Keycloak: {status}"
+            )))?,
+        }
+    }
+
+    // Don't hand out a session for an address nobody has proven they own
+    async fn reject_unverified(&self, tokens: AuthTokens) -> AppResult<AuthTokens> {
+        let claims = TokenClaims::decode_unverified(tokens.access_token.expose_secret())?;
+        if claims.email_verified != Some(false) {
+            return Ok(tokens);
+        }
+
+        if let Err(e) = self.revoke_token(&tokens.refresh_token).await {
+            tracing::error!(error = %e, "Failed to revoke tokens of unverified user");
+        }
+        Err(AuthProviderError::EmailNotVerified)?
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn revoke_token(&self, refresh_token: &SecretString) -> AppResult<()> {
+        let mut form = self.client_auth_form();
+        form.push(("token", refresh_token.expose_secret()));
+        form.push(("token_type_hint", "refresh_token"));
+
         let response = self
             .client
-            .post(&self.endpoints.users_endpoint)
-            .bearer_auth(&self.retrieve_auth_token().await?)
-            .json(&user.signup_json(true, true))
+            .post(&self.endpoints.revocation_endpoint)
+            .form(&form)
             .send()
             .await
             .map_err(|e| {
-                AuthProviderError::Upstream(format!("Failed to send request to Keycloak: {e}"))
+                AuthProviderError::Network(format!("Failed to send request to Keycloak: {e}"))
             })?;
 
+        if !response.status().is_success() {
+            return Err(AuthProviderError::Upstream(format!(
+                "Failed to revoke token with Keycloak: {}",
+                response.status()
+            )))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthProvider for KeycloakUserStore {
+    #[tracing::instrument(skip_all)]
+    async fn retrieve_auth_token(&self) -> AppResult<String> {
+        let mut cached = self.admin_token.lock().await;
+
+        if let Some(token) = cached.as_ref()
+            && Instant::now() < token.refresh_at
+        {
+            return Ok(token.access_token.clone());
+        }
+
+        let token = self.fetch_admin_token().await?;
+        let access_token = token.access_token.clone();
+        *cached = Some(token);
+
+        Ok(access_token)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn signup_user(&self, user: User) -> AppResult<String> {
+        let body = user.signup_json(user.enabled, user.email_verified);
+        let response = self
+            .send_admin(|token| {
+                self.client
+                    .post(&self.endpoints.users_endpoint)
+                    .bearer_auth(token)
+                    .json(&body)
+            })
+            .await?;
+
         match response.status() {
-            StatusCode::CREATED => Ok(()),
+            StatusCode::CREATED => {}
             StatusCode::CONFLICT => Err(AuthProviderError::UserExists)?,
             status => Err(AuthProviderError::Network(format!(
                 "Failed to create user in Keycloak: {status}"
             )))?,
         }
+
+        // Keycloak points the Location header at the new user: .../users/{id}
+        let user_id = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| location.rsplit('/').next())
+            .filter(|id| !id.is_empty())
+            .ok_or(AuthProviderError::Upstream(
+                "Keycloak did not return the new user's location".to_string(),
+            ))?
+            .to_string();
+
+        if let Some(role) = user.role
+            && let Err(e) = self.set_realm_role(&user_id, Some(role)).await
+        {
+            // Don't leave behind an account without the role it was created for
+            if let Err(cleanup) = self.delete_user(user_id.clone()).await {
+                tracing::error!(error = %cleanup, "Failed to remove user after role assignment failed");
+            }
+            return Err(e);
+        }
+
+        if let Some(practice_id) = user.practice_id
+            && let Err(e) = self.set_practice(&user_id, Some(practice_id)).await
+        {
+            // Nor one that belongs to no practice
+            if let Err(cleanup) = self.delete_user(user_id.clone()).await {
+                tracing::error!(error = %cleanup, "Failed to remove user after practice assignment failed");
+            }
+            return Err(e);
+        }
+
+        // The account exists either way; the user can ask for the email again if this fails
+        if !user.email_verified
+            && let Err(e) = self.send_verify_email(&user_id).await
+        {
+            tracing::error!(error = %e, "Failed to send verification email");
+        }
+
+        Ok(user_id)
     }
 
     #[tracing::instrument(skip_all)]
-    async fn login_user(&self, _email: Email, _password: Password) -> AppResult<User> {
-        // Implementation to log in a user via Keycloak
-        unimplemented!()
+    async fn login_user(&self, email: Email, password: Password) -> AppResult<(User, AuthTokens)> {
+        let mut form = self.client_auth_form();
+        form.push(("grant_type", "password"));
+        form.push(("username", email.as_ref().expose_secret()));
+        form.push(("password", password.as_ref().expose_secret()));
+        form.push(("scope", "openid"));
+
+        let response = self
+            .client
+            .post(&self.endpoints.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| {
+                AuthProviderError::Network(format!("Failed to send request to Keycloak: {e}"))
+            })?;
+
+        match response.status() {
+            status if status.is_success() => {}
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED => {
+                let error: TokenErrorResponse = response.json().await.unwrap_or_default();
+                return match (error.error.as_str(), error.error_description.as_str()) {
+                    ("invalid_grant", "Account disabled") => {
+                        Err(AuthProviderError::AccountDisabled)?
+                    }
+                    // Reported while the VERIFY_EMAIL required action is still pending
+                    ("invalid_grant", "Account is not fully set up") => {
+                        Err(AuthProviderError::EmailNotVerified)?
+                    }
+                    ("invalid_grant", _) => Err(AuthProviderError::InvalidCredentials)?,
+                    (code, description) => Err(AuthProviderError::Upstream(format!(
+                        "Keycloak rejected login: {code} {description}"
+                    )))?,
+                };
+            }
+            status => {
+                return Err(AuthProviderError::Upstream(format!(
+                    "Failed to log in user with Keycloak: {status}"
+                )))?;
+            }
+        }
+
+        let tokens: TokenResponse = response.json().await.map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to parse Keycloak response: {e}"))
+        })?;
+        let tokens = self.reject_unverified(tokens.into()).await?;
+        let claims = TokenClaims::decode_unverified(tokens.access_token.expose_secret())?;
+
+        let mut user = User::new(
+            claims
+                .preferred_username
+                .unwrap_or_else(|| email.as_ref().expose_secret().to_string()),
+            email,
+            password,
+            claims.given_name.unwrap_or_default(),
+            claims.family_name.unwrap_or_default(),
+            UserRole::from_realm_roles(&claims.realm_access.roles),
+        );
+        user.user_id = Some(claims.sub);
+        user.email_verified = true;
+
+        Ok((user, tokens))
     }
 
     #[tracing::instrument(skip_all)]
-    async fn logout_user(&self, _user_id: String) -> AppResult<()> {
-        // Implementation to log out a user via Keycloak
-        unimplemented!()
+    async fn revoke_refresh_token(&self, refresh_token: SecretString) -> AppResult<()> {
+        self.revoke_token(&refresh_token).await
     }
 
     #[tracing::instrument(skip_all)]
-    async fn delete_user(&self, user_id: String) -> AppResult<()> {
-        let url = format!("{}/{}", &self.endpoints.users_endpoint, user_id);
+    async fn logout_user(&self, refresh_token: SecretString) -> AppResult<()> {
+        // An inactive token means the session is already gone, so there is nothing left to do
+        let Some(user_id) = self.introspect_refresh_token(&refresh_token).await? else {
+            return Ok(());
+        };
+
+        self.revoke_token(&refresh_token).await?;
+
+        let url = format!("{}/{}/logout", &self.endpoints.users_endpoint, user_id);
+        let response = self
+            .send_admin(|token| self.client.post(&url).bearer_auth(token))
+            .await?;
+
+        match response.status() {
+            StatusCode::NO_CONTENT | StatusCode::NOT_FOUND => Ok(()),
+            status => Err(AuthProviderError::Upstream(format!(
+                "Failed to log out user in Keycloak: {status}"
+            )))?,
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn refresh_user_tokens(&self, refresh_token: SecretString) -> AppResult<AuthTokens> {
+        let mut form = self.client_auth_form();
+        form.push(("grant_type", "refresh_token"));
+        form.push(("refresh_token", refresh_token.expose_secret()));
+
         let response = self
             .client
-            .delete(&url)
-            .bearer_auth(&self.retrieve_auth_token().await?)
+            .post(&self.endpoints.token_endpoint)
+            .form(&form)
             .send()
             .await
             .map_err(|e| {
-                AuthProviderError::Upstream(format!("Failed to send request to Keycloak: {e}"))
+                AuthProviderError::Network(format!("Failed to send request to Keycloak: {e}"))
             })?;
 
         match response.status() {
-            StatusCode::NO_CONTENT => Ok(()),
-            StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
-            status => Err(AuthProviderError::Network(format!(
-                "Failed to delete user in Keycloak: {status}"
-            )))?,
+            status if status.is_success() => {}
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED => {
+                let error: TokenErrorResponse = response.json().await.unwrap_or_default();
+                return match error.error.as_str() {
+                    "invalid_grant" => Err(AuthProviderError::InvalidToken(
+                        "Refresh token is invalid or expired".to_string(),
+                    ))?,
+                    _ => Err(AuthProviderError::Upstream(format!(
+                        "Keycloak rejected the refresh request: {}",
+                        error.error
+                    )))?,
+                };
+            }
+            status => {
+                return Err(AuthProviderError::Upstream(format!(
+                    "Failed to refresh tokens with Keycloak: {status}"
+                )))?;
+            }
         }
+
+        let tokens: TokenResponse = response.json().await.map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to parse Keycloak response: {e}"))
+        })?;
+
+        Ok(tokens.into())
+    }
+
+    fn authorization_url(&self, request: &AuthorizationRequest) -> AppResult<String> {
+        let url = reqwest::Url::parse_with_params(
+            &self.endpoints.authorization_endpoint,
+            [
+                ("response_type", "code"),
+                ("client_id", self.endpoints.client_id.as_str()),
+                ("redirect_uri", request.redirect_uri.as_str()),
+                ("scope", "openid"),
+                ("state", request.state.as_str()),
+                ("nonce", request.nonce.as_str()),
+                ("code_challenge", request.code_challenge().as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| {
+            AuthProviderError::Upstream(format!("Invalid Keycloak authorization endpoint: {e}"))
+        })?;
+
+        Ok(url.into())
     }
 
     #[tracing::instrument(skip_all)]
-    async fn get_user_id(&self, email: Email) -> AppResult<Option<String>> {
+    async fn exchange_authorization_code(
+        &self,
+        code: String,
+        code_verifier: SecretString,
+        redirect_uri: String,
+    ) -> AppResult<AuthTokens> {
+        let mut form = self.client_auth_form();
+        form.push(("grant_type", "authorization_code"));
+        form.push(("code", &code));
+        form.push(("code_verifier", code_verifier.expose_secret()));
+        form.push(("redirect_uri", &redirect_uri));
+
         let response = self
             .client
-            .get(&self.endpoints.users_endpoint)
-            .bearer_auth(&self.retrieve_auth_token().await?)
-            .query(&[("email", email.as_ref().expose_secret())])
+            .post(&self.endpoints.token_endpoint)
+            .form(&form)
             .send()
             .await
             .map_err(|e| {
                 AuthProviderError::Network(format!("Failed to send request to Keycloak: {e}"))
             })?;
 
+        match response.status() {
+            status if status.is_success() => {}
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED => {
+                let error: TokenErrorResponse = response.json().await.unwrap_or_default();
+                return match error.error.as_str() {
+                    "invalid_grant" => Err(AuthProviderError::InvalidToken(
+                        "Authorization code is invalid or expired".to_string(),
+                    ))?,
+                    _ => Err(AuthProviderError::Upstream(format!(
+                        "Keycloak rejected the authorization code: {}",
+                        error.error
+                    )))?,
+                };
+            }
+            status => {
+                return Err(AuthProviderError::Upstream(format!(
+                    "Failed to exchange authorization code with Keycloak: {status}"
+                )))?;
+            }
+        }
+
+        let tokens: TokenResponse = response.json().await.map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to parse Keycloak response: {e}"))
+        })?;
+
+        self.reject_unverified(tokens.into()).await
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn end_session(&self, session_id: String) -> AppResult<()> {
+        let url = format!("{}/sessions/{}", &self.endpoints.admin_enpoint, session_id);
+        let response = self
+            .send_admin(|token| self.client.delete(&url).bearer_auth(token))
+            .await?;
+
+        match response.status() {
+            StatusCode::NO_CONTENT | StatusCode::NOT_FOUND => Ok(()),
+            status => Err(AuthProviderError::Upstream(format!(
+                "Failed to end session in Keycloak: {status}"
+            )))?,
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_user_sessions(&self, user_id: String) -> AppResult<Vec<UserSession>> {
+        let url = format!("{}/{}/sessions", &self.endpoints.users_endpoint, user_id);
+        let response = self
+            .send_admin(|token| self.client.get(&url).bearer_auth(token))
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => {}
+            StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+            status => Err(AuthProviderError::Upstream(format!(
+                "Failed to get user sessions from Keycloak: {status}"
+            )))?,
+        }
+
+        let sessions: Vec<UserSessionRepresentation> = response.json().await.map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to parse Keycloak response: {e}"))
+        })?;
+
+        Ok(sessions.into_iter().map(UserSession::from).collect())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn end_all_sessions(&self, user_id: String) -> AppResult<()> {
+        let url = format!("{}/{}/logout", &self.endpoints.users_endpoint, user_id);
+        let response = self
+            .send_admin(|token| self.client.post(&url).bearer_auth(token))
+            .await?;
+
+        match response.status() {
+            StatusCode::NO_CONTENT => Ok(()),
+            StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+            status => Err(AuthProviderError::Upstream(format!(
+                "Failed to log out user sessions in Keycloak: {status}"
+            )))?,
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete_user(&self, user_id: String) -> AppResult<()> {
+        let url = format!("{}/{}", &self.endpoints.users_endpoint, user_id);
+        let response = self
+            .send_admin(|token| self.client.delete(&url).bearer_auth(token))
+            .await?;
+
+        match response.status() {
+            StatusCode::NO_CONTENT => Ok(()),
+            StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+            status => Err(AuthProviderError::Network(format!(
+                "Failed to delete user in Keycloak: {status}"
+            )))?,
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_user_id(&self, email: Email) -> AppResult<Option<String>> {
+        let email = email.as_ref().expose_secret();
+        // Without exact, Keycloak matches any address containing the search term
+        let response = self
+            .send_admin(|token| {
+                self.client
+                    .get(&self.endpoints.users_endpoint)
+                    .bearer_auth(token)
+                    .query(&[("email", email), ("exact", "true")])
+            })
+            .await?;
+
         if !response.status().is_success() {
             return Err(AuthProviderError::Upstream(format!(
                 "Failed to get user from Keycloak: {}",
@@ -179,18 +952,407 @@ impl AuthProvider for KeycloakUserStore {
             AuthProviderError::Network(format!("Failed to parse Keycloak response: {e}"))
         })?;
 
-        if let Some(user) = users.first() {
-            if let Some(id) = user.get("id").and_then(|id| id.as_str()) {
-                return Ok(Some(id.to_string()));
+        // Keycloak stores addresses lowercased
+        let id = users
+            .iter()
+            .find(|user| {
+                user.get("email")
+                    .and_then(|e| e.as_str())
+                    .is_some_and(|e| e.to_lowercase() == email.to_lowercase())
+            })
+            .and_then(|user| user.get("id").and_then(|id| id.as_str()))
+            .map(str::to_string);
+
+        Ok(id)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_user(&self, user_id: String) -> AppResult<User> {
+        let url = format!("{}/{}", &self.endpoints.users_endpoint, user_id);
+        let response = self
+            .send_admin(|token| self.client.get(&url).bearer_auth(token))
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => {}
+            StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+            status => Err(AuthProviderError::Upstream(format!(
+                "Failed to get user from Keycloak: {status}"
+            )))?,
+        }
+
+        let representation: UserRepresentation = response.json().await.map_err(|e| {
+            AuthProviderError::Upstream(format!("Failed to parse Keycloak response: {e}"))
+        })?;
+        let email = Email::new(representation.email.unwrap_or_default()).map_err(|_| {
+            AuthProviderError::Upstream("Keycloak user has no valid email".to_string())
+        })?;
+        let roles: Vec<String> = self
+            .realm_role_mappings(&representation.id)
+            .await?
+            .into_iter()
+            .map(|role| role.name)
+            .collect();
+        let groups: Vec<String> = self
+            .user_groups(&representation.id)
+            .await?
+            .into_iter()
+            .map(|group| group.name)
+            .collect();
+
+        Ok(User {
+            user_id: Some(representation.id),
+            username: representation.username,
+            email,
+            password: None,
+            first_name: representation.first_name.unwrap_or_default(),
+            last_name: representation.last_name.unwrap_or_default(),
+            role: UserRole::from_realm_roles(&roles),
+            email_verified: representation.email_verified,
+            practice_id: practice::from_groups(&groups),
+            enabled: representation.enabled.unwrap_or(true),
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn update_user(&self, user_update: UserUpdate) -> AppResult<()> {
+        let user_id = user_update
+            .user_id
+            .as_ref()
+            .ok_or(ValidationError::InvalidInput(
+                "User ID is required for updates".to_string(),
+            ))?;
+        let url = format!("{}/{}", &self.endpoints.users_endpoint, user_id);
+
+        if let Some(profile) = user_update.profile_json() {
+            let response = self
+                .send_admin(|token| self.client.put(&url).bearer_auth(token).json(&profile))
+                .await?;
+
+            match response.status() {
+                StatusCode::NO_CONTENT => {}
+                StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+                StatusCode::CONFLICT => Err(AuthProviderError::UserExists)?,
+                status => Err(AuthProviderError::Upstream(format!(
+                    "Failed to update user in Keycloak: {status}"
+                )))?,
             }
         }
 
-        Ok(None)
+        if let Some(password) = &user_update.password {
+            let credential = serde_json::json!({
+                "type": "password",
+                "value": password.as_ref().expose_secret(),
+                "temporary": false
+            });
+            let reset_url = format!("{url}/reset-password");
+            let response = self
+                .send_admin(|token| {
+                    self.client
+                        .put(&reset_url)
+                        .bearer_auth(token)
+                        .json(&credential)
+                })
+                .await?;
+
+            match response.status() {
+                StatusCode::NO_CONTENT => {}
+                StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+                // Rejected by the realm's own password policy
+                StatusCode::BAD_REQUEST => Err(ValidationError::InvalidPassword(Vec::new()))?,
+                status => Err(AuthProviderError::Upstream(format!(
+                    "Failed to reset password in Keycloak: {status}"
+                )))?,
+            }
+        }
+
+        if let Some(role) = user_update.role {
+            self.set_realm_role(user_id, role).await?;
+        }
+
+        if let Some(practice_id) = user_update.practice_id {
+            self.set_practice(user_id, practice_id).await?;
+        }
+
+        Ok(())
     }
 
     #[tracing::instrument(skip_all)]
-    async fn update_user(&self, _user_update: UserUpdate) -> AppResult<()> {
-        // Implementation to update a user's details in Keycloak
-        unimplemented!()
+    async fn send_password_reset(&self, email: Email) -> AppResult<()> {
+        let user_id = self
+            .get_user_id(email)
+            .await?
+            .ok_or(AuthProviderError::UserNotFound)?;
+        let url = format!(
+            "{}/{}/execute-actions-email",
+            &self.endpoints.users_endpoint, user_id
+        );
+        let actions = ["UPDATE_PASSWORD"];
+
+        let response = self
+            .send_admin(|token| {
+                self.client
+                    .put(&url)
+                    .bearer_auth(token)
+                    .query(&[("lifespan", PASSWORD_RESET_LIFESPAN_SECS)])
+                    .json(&actions)
+            })
+            .await?;
+
+        match response.status() {
+            StatusCode::NO_CONTENT => Ok(()),
+            StatusCode::NOT_FOUND => Err(AuthProviderError::UserNotFound)?,
+            status => Err(AuthProviderError::Upstream(format!(
+                "Failed to send password reset email through Keycloak: {status}"
+            )))?,
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn send_verification_email(&self, email: Email) -> AppResult<()> {
+        let user_id = self
+            .get_user_id(email)
+            .await?
+            .ok_or(AuthProviderError::UserNotFound)?;
+
+        // Nothing to verify, and Keycloak would mail a link that no longer leads anywhere
+        if self.get_user(user_id.clone()).await?.email_verified {
+            return Ok(());
+        }
+
+        self.send_verify_email(&user_id).await
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn create_practice(&self, practice_id: Uuid) -> AppResult<()> {
+        let url = format!("{}/groups", &self.endpoints.admin_enpoint);
+        let group = serde_json::json!({ "name": practice::group_name(practice_id) });
+        let response = self
+            .send_admin(|token| self.client.post(&url).bearer_auth(token).json(&group))
+            .await?;
+
+        match response.status() {
+            StatusCode::CREATED | StatusCode::CONFLICT => Ok(()),
+            status => Err(AuthProviderError::Upstream(format!(
+                "Failed to create group in Keycloak: {status}"
+            )))?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        sync::{
+            Arc,
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+        },
+    };
+
+    use poem::{
+        EndpointExt, Request, Route, Server, delete, get, handler,
+        http::StatusCode as PoemStatus,
+        listener::TcpListener,
+        post,
+        web::{Data, Query},
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct StubKeycloak {
+        token_requests: AtomicUsize,
+        expires_in: AtomicU64,
+        rejected_tokens: std::sync::Mutex<HashSet<String>>,
+        // (id, email) of the realm's users, searched by find_users
+        users: std::sync::Mutex<Vec<(String, String)>>,
+        last_search: std::sync::Mutex<Option<String>>,
+    }
+
+    #[handler]
+    async fn issue_token(
+        Data(stub): Data<&Arc<StubKeycloak>>,
+    ) -> poem::web::Json<serde_json::Value> {
+        // Slow enough for concurrent callers to pile up behind the refresh
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let n = stub.token_requests.fetch_add(1, Ordering::SeqCst) + 1;
+        poem::web::Json(serde_json::json!({
+            "access_token": format!("token-{n}"),
+            "expires_in": stub.expires_in.load(Ordering::SeqCst),
+        }))
+    }
+
+    #[handler]
+    async fn remove_user(req: &Request, Data(stub): Data<&Arc<StubKeycloak>>) -> PoemStatus {
+        let token = req
+            .header("authorization")
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        if stub.rejected_tokens.lock().unwrap().contains(token) {
+            PoemStatus::UNAUTHORIZED
+        } else {
+            PoemStatus::NO_CONTENT
+        }
+    }
+
+    // Searches like Keycloak: by substring unless the exact flag is set
+    #[handler]
+    async fn find_users(
+        req: &Request,
+        Query(params): Query<std::collections::HashMap<String, String>>,
+        Data(stub): Data<&Arc<StubKeycloak>>,
+    ) -> poem::web::Json<serde_json::Value> {
+        *stub.last_search.lock().unwrap() = req.uri().query().map(str::to_string);
+        let search = params.get("email").cloned().unwrap_or_default();
+        let exact = params.get("exact").is_some_and(|e| e == "true");
+        let users: Vec<_> = stub
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, email)| {
+                if exact {
+                    *email == search
+                } else {
+                    email.contains(&search)
+                }
+            })
+            .map(|(id, email)| serde_json::json!({ "id": id, "email": email }))
+            .collect();
+        poem::web::Json(serde_json::json!(users))
+    }
+
+    async fn spawn_stub(expires_in: u64) -> (KeycloakUserStore, Arc<StubKeycloak>) {
+        let stub = Arc::new(StubKeycloak::default());
+        stub.expires_in.store(expires_in, Ordering::SeqCst);
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let app = Route::new()
+            .at(
+                "/realms/ehr/protocol/openid-connect/token",
+                post(issue_token),
+            )
+            .at("/admin/realms/ehr/users", get(find_users))
+            .at("/admin/realms/ehr/users/:id", delete(remove_user))
+            .data(stub.clone());
+        tokio::spawn(async move {
+            Server::new(TcpListener::bind(format!("127.0.0.1:{port}")))
+                .run(app)
+                .await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let endpoints = KeycloakEndpoints::new(
+            &format!("http://127.0.0.1:{port}"),
+            "ehr",
+            "ehr-backend".to_string(),
+            Some(SecretString::from("secret")),
+        );
+        (
+            KeycloakUserStore::new(reqwest::Client::new(), endpoints),
+            stub,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_admin_token_is_cached() {
+        let (store, stub) = spawn_stub(300).await;
+
+        let first = store.retrieve_auth_token().await.unwrap();
+        let second = store.retrieve_auth_token().await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(stub.token_requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_callers_share_one_refresh() {
+        let (store, stub) = spawn_stub(300).await;
+        let store = Arc::new(store);
+
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let store = store.clone();
+                tokio::spawn(async move { store.retrieve_auth_token().await.unwrap() })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), "token-1");
+        }
+
+        assert_eq!(stub.token_requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_admin_token_refreshed_ahead_of_expiry() {
+        // Expires within the refresh margin, so it is never reused
+        let (store, stub) = spawn_stub(1).await;
+
+        store.retrieve_auth_token().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        store.retrieve_auth_token().await.unwrap();
+
+        assert_eq!(stub.token_requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_admin_request_retried_once_after_401() {
+        let (store, stub) = spawn_stub(300).await;
+
+        store.retrieve_auth_token().await.unwrap();
+        stub.rejected_tokens
+            .lock()
+            .unwrap()
+            .insert("token-1".to_string());
+
+        store.delete_user("some-user".to_string()).await.unwrap();
+
+        assert_eq!(stub.token_requests.load(Ordering::SeqCst), 2);
+        assert_eq!(store.retrieve_auth_token().await.unwrap(), "token-2");
+    }
+
+    #[tokio::test]
+    async fn test_admin_request_not_retried_twice() {
+        let (store, stub) = spawn_stub(300).await;
+        stub.rejected_tokens
+            .lock()
+            .unwrap()
+            .extend(["token-1".to_string(), "token-2".to_string()]);
+
+        assert!(store.delete_user("some-user".to_string()).await.is_err());
+        assert_eq!(stub.token_requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_user_id_lookup_matches_whole_address() {
+        let (store, stub) = spawn_stub(300).await;
+        let email = |address: &str| Email::parse(address.to_string(), true).unwrap();
+        stub.users
+            .lock()
+            .unwrap()
+            .push(("jimbob".to_string(), "jimbob@clinic.com".to_string()));
+
+        assert_eq!(
+            store.get_user_id(email("bob@clinic.com")).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            stub.last_search.lock().unwrap().as_deref(),
+            Some("email=bob%40clinic.com&exact=true")
+        );
+
+        stub.users
+            .lock()
+            .unwrap()
+            .push(("bob".to_string(), "bob@clinic.com".to_string()));
+        assert_eq!(
+            store.get_user_id(email("Bob@Clinic.com")).await.unwrap(),
+            Some("bob".to_string())
+        );
     }
 }
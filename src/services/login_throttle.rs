@@ -0,0 +1,291 @@
+// Brute-force protection for logins. Failed attempts are counted per account
+// and per source address; reaching the limit locks that key out for a while,
+// and every repeated lockout doubles the wait.
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{
+            AppError, AppResult, AuthProviderError, DatabaseError, ValidationError,
+        },
+        types::{email::Email, password::Password, user::User},
+    },
+    services::audit::{self, AuditAction, AuditEvent},
+    state::AppState,
+    utils::{config::LoginThrottleSettings, tracing::RequestContext},
+};
+
+// A key with no failures for this long starts over, lockout history included
+const FAILURE_MEMORY_SECS: f64 = 24.0 * 60.0 * 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Account,
+    Ip,
+}
+
+impl Scope {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Account => "account",
+            Scope::Ip => "ip",
+        }
+    }
+}
+
+// Accounts are keyed by canonical email, as the providers match them
+fn account_key(email: &Email) -> String {
+    email.as_ref().expose_secret().to_string()
+}
+
+fn lockout_secs(settings: &LoginThrottleSettings, lockouts: u32) -> u64 {
+    let factor = 2u64.saturating_pow(lockouts.saturating_sub(1));
+    settings
+        .lockout_secs
+        .saturating_mul(factor)
+        .min(settings.max_lockout_secs)
+}
+
+fn locked_error(scope: Scope, secs: u64) -> AppError {
+    match scope {
+        Scope::Account => AuthProviderError::AccountLocked(secs).into(),
+        Scope::Ip => AuthProviderError::TooManyAttempts(secs).into(),
+    }
+}
+
+// Refuses the attempt while the account or the caller's address is locked out
+#[tracing::instrument(skip_all)]
+pub async fn check(state: &AppState, ctx: &RequestContext, email: &Email) -> AppResult<()> {
+    let locks: Vec<(String, i64)> = sqlx::query_as(
+        r#"
+        SELECT scope, CEIL(EXTRACT(EPOCH FROM locked_until - NOW()))::BIGINT
+        FROM login_attempts
+        WHERE locked_until > NOW()
+          AND ((scope = 'account' AND key = $1) OR (scope = 'ip' AND key = $2))
+        "#,
+    )
+    .bind(account_key(email))
+    .bind(ctx.ip.as_deref())
+    .fetch_all(&*state.db.read().await)
+    .await
+    .map_err(|e| DatabaseError::context("look up login lockouts", e))?;
+
+    for scope in [Scope::Account, Scope::Ip] {
+        if let Some((_, secs)) = locks.iter().find(|(s, _)| s == scope.as_str()) {
+            return Err(locked_error(scope, (*secs).max(1) as u64));
+        }
+    }
+    Ok(())
+}
+
+// Counts a failed attempt against the account and the caller's address. Returns the
+// lockout error if this attempt reached a limit.
+#[tracing::instrument(skip_all)]
+pub async fn record_failure(
+    state: &AppState,
+    ctx: &RequestContext,
+    email: &Email,
+) -> AppResult<Option<AppError>> {
+    let settings = &state.settings.login_throttle;
+    let db = state.db.read().await.clone();
+
+    let mut keys = vec![(
+        Scope::Account,
+        account_key(email),
+        settings.max_account_failures,
+    )];
+    if let Some(ip) = &ctx.ip {
+        keys.push((Scope::Ip, ip.clone(), settings.max_ip_failures));
+    }
+
+    let mut locked = None;
+    for (scope, key, max_failures) in keys {
+        let Some(secs) = count_failure(&db, settings, scope, &key, max_failures).await? else {
+            continue;
+        };
+        tracing::warn!(
+            scope = scope.as_str(),
+            secs,
+            "Login locked out after repeated failures"
+        );
+
+        let user_id = match scope {
+            Scope::Account => mirrored_user_id(&db, &key).await?,
+            Scope::Ip => None,
+        };
+        let event = AuditEvent {
+            user_id,
+            action: AuditAction::LoginLockout,
+            resource_type: scope.as_str(),
+            resource_id: Some(key),
+            reason: None,
+        };
+        audit::record(&db, ctx, event).await?;
+
+        locked.get_or_insert(locked_error(scope, secs));
+    }
+    Ok(locked)
+}
+
+async fn count_failure(
+    db: &PgPool,
+    settings: &LoginThrottleSettings,
+    scope: Scope,
+    key: &str,
+    max_failures: u32,
+) -> AppResult<Option<u64>> {
+    let (failures, lockouts): (i32, i32) = sqlx::query_as(
+        r#"
+        INSERT INTO login_attempts (scope, key, failures) VALUES ($1, $2, 1)
+        ON CONFLICT (scope, key) DO UPDATE SET
+            failures = CASE
+                WHEN login_attempts.last_failure_at < NOW() - make_interval(secs => $3) THEN 1
+                ELSE login_attempts.failures + 1
+            END,
+            lockouts = CASE
+                WHEN login_attempts.last_failure_at < NOW() - make_interval(secs => $3) THEN 0
+                ELSE login_attempts.lockouts
+            END,
+            last_failure_at = NOW()
+        RETURNING failures, lockouts
+        "#,
+    )
+    .bind(scope.as_str())
+    .bind(key)
+    .bind(FAILURE_MEMORY_SECS)
+    .fetch_one(db)
+    .await
+    .map_err(|e| DatabaseError::context("record failed login", e))?;
+
+    if (failures as u32) < max_failures {
+        return Ok(None);
+    }
+
+    let lockouts = lockouts as u32 + 1;
+    let secs = lockout_secs(settings, lockouts);
+    sqlx::query(
+        r#"
+        UPDATE login_attempts
+        SET failures = 0, lockouts = $3, locked_until = NOW() + make_interval(secs => $4)
+        WHERE scope = $1 AND key = $2
+        "#,
+    )
+    .bind(scope.as_str())
+    .bind(key)
+    .bind(lockouts as i32)
+    .bind(secs as f64)
+    .execute(db)
+    .await
+    .map_err(|e| DatabaseError::context("lock out login", e))?;
+
+    Ok(Some(secs))
+}
+
+async fn mirrored_user_id(db: &PgPool, email: &str) -> AppResult<Option<Uuid>> {
+    sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+        .bind(email)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| DatabaseError::context("look up user", e))
+}
+
+// A successful login clears the account's failures and lockout history
+#[tracing::instrument(skip_all)]
+pub async fn record_success(state: &AppState, email: &Email) -> AppResult<()> {
+    sqlx::query("DELETE FROM login_attempts WHERE scope = 'account' AND key = $1")
+        .bind(account_key(email))
+        .execute(&*state.db.read().await)
+        .await
+        .map_err(|e| DatabaseError::context("clear failed logins", e))?;
+    Ok(())
+}
+
+// Re-checks a signed-in user's password before a sensitive change. Wrong guesses count
+// towards the same lockout as failed logins.
+#[tracing::instrument(skip_all)]
+pub async fn verify_current_password(
+    state: &AppState,
+    ctx: &RequestContext,
+    user: &User,
+    password: &str,
+) -> AppResult<()> {
+    let incorrect =
+        || ValidationError::InvalidInput("Current password is incorrect".to_string()).into();
+    check(state, ctx, &user.email).await?;
+
+    // An empty password can never match a stored credential
+    let result = match Password::new(password.to_string()) {
+        Ok(password) => {
+            let provider = state.auth_provider.read().await;
+            provider
+                .login_user(user.email.clone(), password)
+                .await
+                .map(|(_, tokens)| tokens)
+        }
+        Err(_) => Err(AuthProviderError::InvalidCredentials.into()),
+    };
+
+    match result {
+        Ok(tokens) => {
+            // Proving the password means logging in with it; drop the session that creates
+            if let Some(session_id) = tokens.session_id
+                && let Err(e) = state
+                    .auth_provider
+                    .read()
+                    .await
+                    .end_session(session_id)
+                    .await
+            {
+                tracing::warn!(error = %e, "Failed to end password verification session");
+            }
+            record_success(state, &user.email).await
+        }
+        Err(AppError::AuthProvider(AuthProviderError::InvalidCredentials)) => {
+            let locked = record_failure(state, ctx, &user.email).await?;
+            Err(locked.unwrap_or_else(incorrect))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// Lifts an account lockout on an admin's behalf
+#[tracing::instrument(skip_all)]
+pub async fn unlock(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+    email: &Email,
+) -> AppResult<()> {
+    let db = state.db.read().await.clone();
+    record_success(state, email).await?;
+
+    let event = AuditEvent {
+        user_id: Uuid::parse_str(user_id).ok(),
+        action: AuditAction::LoginUnlock,
+        resource_type: Scope::Account.as_str(),
+        resource_id: Some(user_id.to_string()),
+        reason: None,
+    };
+    audit::record(&db, ctx, event).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lockout_doubles_up_to_the_cap() {
+        let settings = LoginThrottleSettings {
+            max_account_failures: 5,
+            max_ip_failures: 20,
+            lockout_secs: 60,
+            max_lockout_secs: 600,
+        };
+
+        let secs: Vec<u64> = (1..=6).map(|n| lockout_secs(&settings, n)).collect();
+        assert_eq!(secs, vec![60, 120, 240, 480, 600, 600]);
+        assert_eq!(lockout_secs(&settings, 200), 600);
+    }
+}
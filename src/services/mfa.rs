@@ -0,0 +1,597 @@
+// TOTP second factors and one-time recovery codes. A factor is stored as soon
+// as enrollment starts but only counts once the user confirms it with a code,
+// which proves the authenticator app holds the same secret. Secrets are kept
+// encrypted with the MFA_SECRET_KEY setting, so a copy of the database alone
+// cannot generate codes.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, AuthProviderError, DatabaseError, MfaError},
+        types::auth_context::{AuthContext, PendingMfa},
+    },
+    services::audit::{self, AuditAction, AuditEvent},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+// Shown next to the account name in authenticator apps
+const ISSUER: &str = "LGR EHR";
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP_SECS: u64 = 30;
+const RECOVERY_CODE_COUNT: usize = 10;
+// Marks a bearer credential as an enrollment token rather than a JWT
+pub const ENROLLMENT_TOKEN_PREFIX: &str = "lgre_";
+pub const ENROLLMENT_TOKEN_TTL_SECS: u64 = 15 * 60;
+
+pub struct TotpEnrollment {
+    pub factor_id: Uuid,
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct MfaFactor {
+    pub id: Uuid,
+    pub kind: String,
+    pub label: String,
+    pub confirmed: bool,
+}
+
+impl MfaFactor {
+    pub fn response_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "factor_id": self.id.to_string(),
+            "kind": self.kind,
+            "label": self.label,
+            "confirmed": self.confirmed,
+        })
+    }
+}
+
+fn parse_user_id(user_id: &str) -> AppResult<Uuid> {
+    Uuid::parse_str(user_id).map_err(AppError::internal)
+}
+
+fn totp(secret: &str, account_name: &str) -> AppResult<TOTP> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| AppError::internal(anyhow::anyhow!("Invalid TOTP secret: {e:?}")))?;
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        0,
+        TOTP_STEP_SECS,
+        secret,
+        Some(ISSUER.to_string()),
+        account_name.to_string(),
+    )
+    .map_err(AppError::internal)
+}
+
+fn cipher(state: &AppState) -> AppResult<Aes256Gcm> {
+    let key = STANDARD
+        .decode(state.settings.mfa_secret_key.expose_secret())
+        .map_err(AppError::internal)?;
+    if key.len() != 32 {
+        return Err(AppError::internal(anyhow::anyhow!(
+            "MFA secret key must be 32 bytes"
+        )));
+    }
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+// Encrypts a factor's secret under a fresh nonce, bound to the factor id so a
+// ciphertext copied onto another row does not decrypt
+fn seal_secret(state: &AppState, factor_id: Uuid, secret: &str) -> AppResult<(String, Vec<u8>)> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let payload = Payload {
+        msg: secret.as_bytes(),
+        aad: factor_id.as_bytes(),
+    };
+    let ciphertext = cipher(state)?
+        .encrypt(&nonce, payload)
+        .map_err(|e| AppError::internal(anyhow::anyhow!("Failed to encrypt TOTP secret: {e}")))?;
+    Ok((STANDARD.encode(ciphertext), nonce.to_vec()))
+}
+
+fn open_secret(
+    state: &AppState,
+    factor_id: Uuid,
+    sealed: &str,
+    nonce: Option<&[u8]>,
+) -> AppResult<String> {
+    let nonce = nonce
+        .filter(|nonce| nonce.len() == 12)
+        .ok_or_else(|| AppError::internal(anyhow::anyhow!("TOTP secret is not encrypted")))?;
+    let ciphertext = STANDARD.decode(sealed).map_err(AppError::internal)?;
+    let payload = Payload {
+        msg: &ciphertext,
+        aad: factor_id.as_bytes(),
+    };
+    let secret = cipher(state)?
+        .decrypt(Nonce::from_slice(nonce), payload)
+        .map_err(|e| AppError::internal(anyhow::anyhow!("Failed to decrypt TOTP secret: {e}")))?;
+    String::from_utf8(secret).map_err(AppError::internal)
+}
+
+// Encrypts secrets stored before they were kept encrypted; run at startup
+#[tracing::instrument(skip_all)]
+pub async fn seal_plaintext_secrets(state: &AppState) -> AppResult<u64> {
+    let db = state.db.read().await.clone();
+    let factors: Vec<(Uuid, String)> =
+        sqlx::query_as("SELECT id, secret FROM mfa_factors WHERE secret_nonce IS NULL")
+            .fetch_all(&db)
+            .await
+            .map_err(|e| DatabaseError::context("look up MFA factors", e))?;
+
+    let mut sealed = 0;
+    for (factor_id, secret) in factors {
+        let (ciphertext, nonce) = seal_secret(state, factor_id, &secret)?;
+        sealed += sqlx::query(
+            "UPDATE mfa_factors SET secret = $2, secret_nonce = $3 WHERE id = $1 AND secret_nonce IS NULL",
+        )
+        .bind(factor_id)
+        .bind(ciphertext)
+        .bind(nonce)
+        .execute(&db)
+        .await
+        .map_err(|e| DatabaseError::context("encrypt MFA factor", e))?
+        .rows_affected();
+    }
+    Ok(sealed)
+}
+
+// Time step the code was generated in, allowing one step of clock drift either way
+fn matching_step(totp: &TOTP, code: &str, now: u64) -> Option<u64> {
+    let current = now / TOTP_STEP_SECS;
+    [current, current.saturating_sub(1), current + 1]
+        .into_iter()
+        .find(|step| totp.check(code, step * TOTP_STEP_SECS))
+}
+
+fn unix_now() -> AppResult<u64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .map_err(AppError::internal)
+}
+
+// Recovery codes are compared without dashes, spaces or case
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    format!(
+        "{:x}",
+        Sha256::digest(normalize_recovery_code(code).as_bytes())
+    )
+}
+
+fn hash_enrollment_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn new_recovery_code() -> String {
+    let random = Uuid::new_v4().simple().to_string();
+    format!("{}-{}", &random[..5], &random[5..10])
+}
+
+// The change has already been made by the time it is audited, so a failure is only logged
+async fn record(db: &PgPool, ctx: &RequestContext, event: AuditEvent) {
+    if let Err(e) = audit::record(db, ctx, event).await {
+        tracing::error!(error = %e, "Failed to record MFA audit event");
+    }
+}
+
+fn factor_event(user_id: Uuid, action: AuditAction, factor_id: Uuid) -> AuditEvent {
+    AuditEvent {
+        user_id: Some(user_id),
+        action,
+        resource_type: "mfa_factor",
+        resource_id: Some(factor_id.to_string()),
+        reason: None,
+    }
+}
+
+fn recovery_code_event(user_id: Uuid, action: AuditAction) -> AuditEvent {
+    AuditEvent {
+        user_id: Some(user_id),
+        action,
+        resource_type: "mfa_recovery_code",
+        resource_id: None,
+        reason: None,
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn enroll_totp(
+    state: &AppState,
+    user_id: &str,
+    account_name: &str,
+    label: &str,
+) -> AppResult<TotpEnrollment> {
+    let user_id = parse_user_id(user_id)?;
+    let secret = Secret::generate_secret().to_encoded().to_string();
+    let totp = totp(&secret, account_name)?;
+    let factor_id = Uuid::new_v4();
+    let (sealed, nonce) = seal_secret(state, factor_id, &secret)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO mfa_factors (id, user_id, kind, label, secret, secret_nonce)
+        VALUES ($1, $2, 'totp', $3, $4, $5)
+        "#,
+    )
+    .bind(factor_id)
+    .bind(user_id)
+    .bind(label)
+    .bind(sealed)
+    .bind(nonce)
+    .execute(&*state.db.read().await)
+    .await
+    .map_err(|e| DatabaseError::context("store MFA factor", e))?;
+
+    Ok(TotpEnrollment {
+        factor_id,
+        provisioning_uri: totp.get_url(),
+        secret,
+    })
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn confirm_totp(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+    factor_id: Uuid,
+    code: &str,
+) -> AppResult<()> {
+    let user_id = parse_user_id(user_id)?;
+    let db = state.db.read().await.clone();
+
+    let factor: Option<(String, Option<Vec<u8>>, bool)> = sqlx::query_as(
+        r#"
+        SELECT secret, secret_nonce, confirmed_at IS NOT NULL
+        FROM mfa_factors WHERE id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(factor_id)
+    .bind(user_id)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| DatabaseError::context("look up MFA factor", e))?;
+
+    let (sealed, nonce, confirmed) = factor.ok_or(MfaError::FactorNotFound)?;
+    if confirmed {
+        Err(MfaError::AlreadyConfirmed)?
+    }
+    let secret = open_secret(state, factor_id, &sealed, nonce.as_deref())?;
+    let step = matching_step(&totp(&secret, "")?, code.trim(), unix_now()?)
+        .ok_or(MfaError::InvalidCode)?;
+
+    let confirmed = sqlx::query(
+        r#"
+        UPDATE mfa_factors SET confirmed_at = NOW(), last_used_step = $3
+        WHERE id = $1 AND user_id = $2 AND confirmed_at IS NULL
+        "#,
+    )
+    .bind(factor_id)
+    .bind(user_id)
+    .bind(step as i64)
+    .execute(&db)
+    .await
+    .map_err(|e| DatabaseError::context("confirm MFA factor", e))?;
+    if confirmed.rows_affected() == 0 {
+        Err(MfaError::AlreadyConfirmed)?
+    }
+
+    // Enrolled now, so signing in with a code replaces any enrollment token
+    sqlx::query("DELETE FROM mfa_enrollment_tokens WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&db)
+        .await
+        .map_err(|e| DatabaseError::context("remove enrollment tokens", e))?;
+
+    record(
+        &db,
+        ctx,
+        factor_event(user_id, AuditAction::MfaFactorEnrolled, factor_id),
+    )
+    .await;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn list_factors(state: &AppState, user_id: &str) -> AppResult<Vec<MfaFactor>> {
+    sqlx::query_as(
+        r#"
+        SELECT id, kind, label, confirmed_at IS NOT NULL AS confirmed
+        FROM mfa_factors WHERE user_id = $1 ORDER BY created_at
+        "#,
+    )
+    .bind(parse_user_id(user_id)?)
+    .fetch_all(&*state.db.read().await)
+    .await
+    .map_err(|e| DatabaseError::context("list MFA factors", e))
+}
+
+// A confirmed factor only goes with a current code, so a stolen session alone can't
+// switch MFA off
+#[tracing::instrument(skip_all)]
+pub async fn remove_factor(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+    factor_id: Uuid,
+    code: Option<&str>,
+) -> AppResult<()> {
+    let user_id = parse_user_id(user_id)?;
+    let db = state.db.read().await.clone();
+
+    let confirmed: bool = sqlx::query_scalar(
+        "SELECT confirmed_at IS NOT NULL FROM mfa_factors WHERE id = $1 AND user_id = $2",
+    )
+    .bind(factor_id)
+    .bind(user_id)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| DatabaseError::context("look up MFA factor", e))?
+    .ok_or(MfaError::FactorNotFound)?;
+    if confirmed {
+        let code = code.ok_or(MfaError::Required)?;
+        verify_code(state, ctx, &user_id.to_string(), code).await?;
+    }
+
+    let removed = sqlx::query("DELETE FROM mfa_factors WHERE id = $1 AND user_id = $2")
+        .bind(factor_id)
+        .bind(user_id)
+        .execute(&db)
+        .await
+        .map_err(|e| DatabaseError::context("remove MFA factor", e))?;
+    if removed.rows_affected() == 0 {
+        Err(MfaError::FactorNotFound)?
+    }
+
+    record(
+        &db,
+        ctx,
+        factor_event(user_id, AuditAction::MfaFactorRemoved, factor_id),
+    )
+    .await;
+    Ok(())
+}
+
+// Replaces any earlier recovery codes; the plain codes are only ever returned here
+#[tracing::instrument(skip_all)]
+pub async fn generate_recovery_codes(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+) -> AppResult<Vec<String>> {
+    if !has_confirmed_factor(state, user_id).await? {
+        Err(MfaError::NotEnrolled)?
+    }
+    let user_id = parse_user_id(user_id)?;
+    let db = state.db.read().await.clone();
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| new_recovery_code())
+        .collect();
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| DatabaseError::context("begin transaction", e))?;
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::context("remove recovery codes", e))?;
+    for code in &codes {
+        sqlx::query("INSERT INTO mfa_recovery_codes (code_hash, user_id) VALUES ($1, $2)")
+            .bind(hash_recovery_code(code))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::context("store recovery code", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| DatabaseError::context("commit recovery codes", e))?;
+
+    record(
+        &db,
+        ctx,
+        recovery_code_event(user_id, AuditAction::MfaRecoveryCodesGenerated),
+    )
+    .await;
+    Ok(codes)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn has_confirmed_factor(state: &AppState, user_id: &str) -> AppResult<bool> {
+    sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM mfa_factors WHERE user_id = $1 AND confirmed_at IS NOT NULL)",
+    )
+    .bind(parse_user_id(user_id)?)
+    .fetch_one(&*state.db.read().await)
+    .await
+    .map_err(|e| DatabaseError::context("look up MFA factors", e))
+}
+
+// Accepts a code from any confirmed authenticator, or failing that an unused recovery code
+#[tracing::instrument(skip_all)]
+pub async fn verify_code(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+    code: &str,
+) -> AppResult<()> {
+    let user_id = parse_user_id(user_id)?;
+    let db = state.db.read().await.clone();
+    let code = code.trim();
+
+    let factors: Vec<(Uuid, String, Option<Vec<u8>>)> = sqlx::query_as(
+        r#"
+        SELECT id, secret, secret_nonce FROM mfa_factors
+        WHERE user_id = $1 AND confirmed_at IS NOT NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| DatabaseError::context("look up MFA factors", e))?;
+
+    let now = unix_now()?;
+    for (factor_id, sealed, nonce) in factors {
+        let secret = open_secret(state, factor_id, &sealed, nonce.as_deref())?;
+        let Some(step) = matching_step(&totp(&secret, "")?, code, now) else {
+            continue;
+        };
+        // Only a later step than the last accepted one counts, so a code can't be replayed
+        let accepted = sqlx::query(
+            "UPDATE mfa_factors SET last_used_step = $2 WHERE id = $1 AND last_used_step < $2",
+        )
+        .bind(factor_id)
+        .bind(step as i64)
+        .execute(&db)
+        .await
+        .map_err(|e| DatabaseError::context("record MFA code use", e))?;
+        if accepted.rows_affected() > 0 {
+            return Ok(());
+        }
+    }
+
+    let redeemed = sqlx::query(
+        r#"
+        UPDATE mfa_recovery_codes SET used_at = NOW()
+        WHERE code_hash = $1 AND user_id = $2 AND used_at IS NULL
+        "#,
+    )
+    .bind(hash_recovery_code(code))
+    .bind(user_id)
+    .execute(&db)
+    .await
+    .map_err(|e| DatabaseError::context("redeem recovery code", e))?;
+    if redeemed.rows_affected() == 0 {
+        Err(MfaError::InvalidCode)?
+    }
+
+    record(
+        &db,
+        ctx,
+        recovery_code_event(user_id, AuditAction::MfaRecoveryCodeUsed),
+    )
+    .await;
+    Ok(())
+}
+
+// Issued in place of tokens when the role requires MFA and nothing is enrolled yet
+#[tracing::instrument(skip_all)]
+pub async fn issue_enrollment_token(
+    state: &AppState,
+    user_id: &str,
+    email: &str,
+) -> AppResult<SecretString> {
+    let user_id = parse_user_id(user_id)?;
+    let token = format!(
+        "{ENROLLMENT_TOKEN_PREFIX}{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+    let db = state.db.read().await.clone();
+
+    // Expired tokens are never presented again, so sweep them here
+    sqlx::query("DELETE FROM mfa_enrollment_tokens WHERE expires_at < NOW()")
+        .execute(&db)
+        .await
+        .map_err(|e| DatabaseError::context("remove expired enrollment tokens", e))?;
+    sqlx::query(
+        r#"
+        INSERT INTO mfa_enrollment_tokens (token_hash, user_id, email, expires_at)
+        VALUES ($1, $2, $3, NOW() + make_interval(secs => $4))
+        "#,
+    )
+    .bind(hash_enrollment_token(&token))
+    .bind(user_id)
+    .bind(email)
+    .bind(ENROLLMENT_TOKEN_TTL_SECS as f64)
+    .execute(&db)
+    .await
+    .map_err(|e| DatabaseError::context("store enrollment token", e))?;
+
+    Ok(SecretString::from(token))
+}
+
+// Resolves an enrollment token to a caller who may only enroll an authenticator
+#[tracing::instrument(skip_all)]
+pub async fn authenticate_enrollment(
+    state: &AppState,
+    token: &SecretString,
+) -> AppResult<AuthContext> {
+    let (user_id, email): (Uuid, String) = sqlx::query_as(
+        "SELECT user_id, email FROM mfa_enrollment_tokens WHERE token_hash = $1 AND expires_at > NOW()",
+    )
+    .bind(hash_enrollment_token(token.expose_secret()))
+    .fetch_optional(&*state.db.read().await)
+    .await
+    .map_err(|e| DatabaseError::context("look up enrollment token", e))?
+    .ok_or(AuthProviderError::InvalidToken(
+        "Unknown or expired enrollment token".to_string(),
+    ))?;
+
+    Ok(AuthContext {
+        subject: user_id.to_string(),
+        email: Some(email),
+        realm_roles: Vec::new(),
+        practice_id: None,
+        scopes: Some(Vec::new()),
+        pending_mfa: Some(PendingMfa::Enroll),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_step_allows_one_step_of_drift() {
+        let secret = Secret::generate_secret().to_encoded().to_string();
+        let totp = totp(&secret, "test@example.com").unwrap();
+        let now = 1_760_000_000;
+
+        let previous = totp.generate(now - TOTP_STEP_SECS);
+        assert_eq!(
+            matching_step(&totp, &previous, now),
+            Some(now / TOTP_STEP_SECS - 1)
+        );
+
+        let stale = totp.generate(now - 3 * TOTP_STEP_SECS);
+        assert_eq!(matching_step(&totp, &stale, now), None);
+    }
+
+    #[test]
+    fn test_recovery_code_hash_ignores_formatting() {
+        let code = new_recovery_code();
+        assert_eq!(code.len(), 11);
+        assert_eq!(
+            hash_recovery_code(&code),
+            hash_recovery_code(&format!(" {} ", code.to_uppercase().replace('-', "")))
+        );
+    }
+}
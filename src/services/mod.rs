@@ -1 +1,19 @@
+pub mod account_status;
+pub mod api_keys;
+pub mod audit;
+pub mod authorization_code;
+pub mod in_memory_auth_provider;
+pub mod invitations;
+pub mod jwks_token_validator;
 pub mod keycloak_auth_provider;
+pub mod login_throttle;
+pub mod mfa;
+pub mod offboarding;
+pub mod password_policy;
+pub mod practices;
+pub mod refresh_tokens;
+pub mod smtp_mailer;
+pub mod user_directory;
+pub mod user_sessions;
+pub mod user_sync;
+pub mod web_sessions;
@@ -0,0 +1,378 @@
+// Offboarding bundles what happens by hand when someone leaves: the account is disabled,
+// sessions and API keys are revoked, and their work is handed over. It runs as a job
+// whose steps each record a status, so a failed run can be resumed where it stopped.
+// Tasks, appointments and patient panels are not held by this service yet; those steps
+// are recorded as skipped and the job ends as needs_attention rather than completed, so
+// the handover to the reassignment target is known to be outstanding.
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{
+            AppError, AppResult, AuthProviderError, DatabaseError, ValidationError,
+        },
+        types::offboarding::{JobStatus, OffboardingJob, OffboardingStep, StepState, StepStatus},
+    },
+    services::{
+        account_status, api_keys,
+        audit::{self, AuditAction, AuditEvent},
+        practices, user_sessions,
+    },
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+type JobRow = (Uuid, Uuid, String, Option<Uuid>, String, i64, Option<i64>);
+
+type StepRow = (String, String, Option<String>, i32, i64);
+
+// Starts offboarding the user, or resumes their unfinished job; a resumed job keeps the
+// reason and reassignment target it was started with
+#[tracing::instrument(skip_all)]
+pub async fn offboard(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+    reason: String,
+    reassign_to: Option<String>,
+) -> AppResult<OffboardingJob> {
+    let auth = ctx.require_auth()?;
+    account_status::authorize_change(state, auth, user_id, false).await?;
+    let id = Uuid::parse_str(user_id).map_err(|_| AuthProviderError::UserNotFound)?;
+
+    let db = state.db.read().await.clone();
+    let unfinished: Option<Uuid> = sqlx::query_scalar(
+        "SELECT id FROM offboarding_jobs WHERE user_id = $1 AND completed_at IS NULL",
+    )
+    .bind(id)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| DatabaseError::context("find offboarding job", e))?;
+
+    let job_id = match unfinished {
+        Some(job_id) => job_id,
+        None => {
+            let reassign_to = match reassign_to {
+                Some(target) => Some(reassignment_target(state, ctx, user_id, &target).await?),
+                None => None,
+            };
+            start(state, ctx, id, reason, reassign_to).await?
+        }
+    };
+
+    run(state, ctx, job_id).await?;
+    load(state, job_id).await
+}
+
+// The latest offboarding job of the user, finished or not
+#[tracing::instrument(skip_all)]
+pub async fn latest(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+) -> AppResult<OffboardingJob> {
+    practices::require_same_practice(state, ctx.require_auth()?, user_id).await?;
+    let id = Uuid::parse_str(user_id).map_err(|_| AuthProviderError::OffboardingNotFound)?;
+
+    let job_id: Uuid = sqlx::query_scalar(
+        "SELECT id FROM offboarding_jobs WHERE user_id = $1 ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(id)
+    .fetch_optional(&*state.db.read().await)
+    .await
+    .map_err(|e| DatabaseError::context("find offboarding job", e))?
+    .ok_or(AuthProviderError::OffboardingNotFound)?;
+
+    load(state, job_id).await
+}
+
+// Work is handed to another active member of the same practice
+async fn reassignment_target(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+    target: &str,
+) -> AppResult<Uuid> {
+    if target == user_id {
+        Err(ValidationError::InvalidInput(
+            "Work cannot be reassigned to the user being offboarded".to_string(),
+        ))?;
+    }
+    practices::require_same_practice(state, ctx.require_auth()?, target).await?;
+    let recipient = state
+        .auth_provider
+        .read()
+        .await
+        .get_user(target.to_string())
+        .await?;
+    if !recipient.enabled {
+        Err(ValidationError::InvalidInput(
+            "Work cannot be reassigned to a disabled account".to_string(),
+        ))?;
+    }
+    Uuid::parse_str(target).map_err(|_| AuthProviderError::UserNotFound.into())
+}
+
+async fn start(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: Uuid,
+    reason: String,
+    reassign_to: Option<Uuid>,
+) -> AppResult<Uuid> {
+    let requested_by = Uuid::parse_str(&ctx.require_auth()?.subject).ok();
+    let job_id = Uuid::new_v4();
+
+    let mut tx = state
+        .db
+        .read()
+        .await
+        .begin()
+        .await
+        .map_err(|e| DatabaseError::context("start transaction", e))?;
+
+    // A concurrent request may have started a job in the meantime; that one is resumed
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO offboarding_jobs (id, user_id, requested_by, reason, reassign_to, status)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id) WHERE completed_at IS NULL DO NOTHING
+        "#,
+    )
+    .bind(job_id)
+    .bind(user_id)
+    .bind(requested_by)
+    .bind(&reason)
+    .bind(reassign_to)
+    .bind(JobStatus::InProgress.as_str())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| DatabaseError::context("create offboarding job", e))?
+    .rows_affected()
+        > 0;
+    if !inserted {
+        return sqlx::query_scalar(
+            "SELECT id FROM offboarding_jobs WHERE user_id = $1 AND completed_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::context("find offboarding job", e));
+    }
+
+    for (position, step) in OffboardingStep::ALL.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO offboarding_steps (job_id, position, step, status) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(job_id)
+        .bind(position as i32)
+        .bind(step.as_str())
+        .bind(StepStatus::Pending.as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::context("create offboarding steps", e))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| DatabaseError::context("commit transaction", e))?;
+    Ok(job_id)
+}
+
+// Runs every step not yet done, in order, stopping at the first that fails
+async fn run(state: &AppState, ctx: &RequestContext, job_id: Uuid) -> AppResult<()> {
+    let db = state.db.read().await.clone();
+    let (user_id, reason): (Uuid, String) =
+        sqlx::query_as("SELECT user_id, reason FROM offboarding_jobs WHERE id = $1")
+            .bind(job_id)
+            .fetch_one(&db)
+            .await
+            .map_err(|e| DatabaseError::context("read offboarding job", e))?;
+    let user_id = user_id.to_string();
+
+    let remaining: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT step FROM offboarding_steps
+        WHERE job_id = $1 AND status IN ($2, $3)
+        ORDER BY position
+        "#,
+    )
+    .bind(job_id)
+    .bind(StepStatus::Pending.as_str())
+    .bind(StepStatus::Failed.as_str())
+    .fetch_all(&db)
+    .await
+    .map_err(|e| DatabaseError::context("read offboarding steps", e))?;
+
+    for name in remaining {
+        let step = OffboardingStep::from_name(&name).ok_or_else(|| {
+            AppError::internal(anyhow::anyhow!("Unknown offboarding step: {name}"))
+        })?;
+        let (status, detail) = match run_step(state, ctx, &user_id, &reason, step).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                tracing::warn!(step = step.as_str(), error = %e, "Offboarding step failed");
+                (StepStatus::Failed, e.to_string())
+            }
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE offboarding_steps
+            SET status = $3, detail = $4, attempts = attempts + 1, updated_at = NOW()
+            WHERE job_id = $1 AND step = $2
+            "#,
+        )
+        .bind(job_id)
+        .bind(step.as_str())
+        .bind(status.as_str())
+        .bind(&detail)
+        .execute(&db)
+        .await
+        .map_err(|e| DatabaseError::context("update offboarding step", e))?;
+
+        if status == StepStatus::Failed {
+            return set_job_status(state, job_id, JobStatus::Failed).await;
+        }
+    }
+
+    // Skips from an earlier run of a resumed job count too
+    let skipped: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM offboarding_steps WHERE job_id = $1 AND status = $2)",
+    )
+    .bind(job_id)
+    .bind(StepStatus::Skipped.as_str())
+    .fetch_one(&db)
+    .await
+    .map_err(|e| DatabaseError::context("read offboarding steps", e))?;
+
+    let (status, action) = if skipped {
+        (
+            JobStatus::NeedsAttention,
+            AuditAction::UserOffboardingNeedsAttention,
+        )
+    } else {
+        (JobStatus::Completed, AuditAction::UserOffboarded)
+    };
+    set_job_status(state, job_id, status).await?;
+
+    let event = AuditEvent {
+        user_id: Uuid::parse_str(&ctx.require_auth()?.subject).ok(),
+        action,
+        resource_type: "user",
+        resource_id: Some(user_id),
+        reason: Some(reason),
+    };
+    audit::record(&db, ctx, event).await
+}
+
+async fn run_step(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+    reason: &str,
+    step: OffboardingStep,
+) -> AppResult<(StepStatus, String)> {
+    match step {
+        OffboardingStep::DisableAccount => {
+            let current = state
+                .auth_provider
+                .read()
+                .await
+                .get_user(user_id.to_string())
+                .await?;
+            account_status::change_status(state, ctx, &current, false, reason.to_string()).await?;
+            Ok((StepStatus::Completed, "Account disabled".to_string()))
+        }
+        OffboardingStep::RevokeSessions => {
+            user_sessions::revoke_all(state, ctx, user_id).await?;
+            Ok((StepStatus::Completed, "All sessions ended".to_string()))
+        }
+        OffboardingStep::RevokeApiKeys => {
+            let revoked = api_keys::revoke_created_by(state, ctx, user_id).await?;
+            Ok((
+                StepStatus::Completed,
+                format!("{revoked} API key(s) revoked"),
+            ))
+        }
+        OffboardingStep::ReassignTasks => Ok((
+            StepStatus::Skipped,
+            "No tasks are held by this service".to_string(),
+        )),
+        OffboardingStep::ReassignAppointments => Ok((
+            StepStatus::Skipped,
+            "No appointments are held by this service".to_string(),
+        )),
+        OffboardingStep::TransferPatients => Ok((
+            StepStatus::Skipped,
+            "No patient panels are held by this service".to_string(),
+        )),
+    }
+}
+
+async fn set_job_status(state: &AppState, job_id: Uuid, status: JobStatus) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE offboarding_jobs
+        SET status = $2,
+            completed_at = CASE WHEN $3 THEN NOW() END
+        WHERE id = $1
+        "#,
+    )
+    .bind(job_id)
+    .bind(status.as_str())
+    .bind(status.is_finished())
+    .execute(&*state.db.read().await)
+    .await
+    .map_err(|e| DatabaseError::context("update offboarding job", e))?;
+    Ok(())
+}
+
+async fn load(state: &AppState, job_id: Uuid) -> AppResult<OffboardingJob> {
+    let db = state.db.read().await.clone();
+    let (id, user_id, reason, reassign_to, status, created_at, completed_at): JobRow =
+        sqlx::query_as(
+            r#"
+            SELECT id, user_id, reason, reassign_to, status,
+                EXTRACT(EPOCH FROM created_at)::BIGINT,
+                EXTRACT(EPOCH FROM completed_at)::BIGINT
+            FROM offboarding_jobs WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .fetch_one(&db)
+        .await
+        .map_err(|e| DatabaseError::context("read offboarding job", e))?;
+
+    let steps: Vec<StepRow> = sqlx::query_as(
+        r#"
+        SELECT step, status, detail, attempts, EXTRACT(EPOCH FROM updated_at)::BIGINT
+        FROM offboarding_steps WHERE job_id = $1
+        ORDER BY position
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| DatabaseError::context("read offboarding steps", e))?;
+
+    Ok(OffboardingJob {
+        id,
+        user_id,
+        reason,
+        reassign_to,
+        status,
+        created_at,
+        completed_at,
+        steps: steps
+            .into_iter()
+            .map(|(step, status, detail, attempts, updated_at)| StepState {
+                step,
+                status,
+                detail,
+                attempts,
+                updated_at,
+            })
+            .collect(),
+    })
+}
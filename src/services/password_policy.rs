@@ -0,0 +1,124 @@
+// Enforces the configured password policy. Reuse is judged against argon2 hashes of
+// each user's most recent passwords, kept in password_history and trimmed to the
+// policy's history size whenever a new password is stored.
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier, password_hash::SaltString};
+use secrecy::ExposeSecret;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, DatabaseError, ValidationError},
+        types::{password::Password, password_policy::PasswordRule},
+    },
+    state::AppState,
+};
+
+async fn recently_used(state: &AppState, user_id: Uuid, password: &Password) -> AppResult<bool> {
+    let hashes: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT password_hash FROM password_history
+        WHERE user_id = $1
+        ORDER BY id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(i64::from(state.settings.password_policy.history_size))
+    .fetch_all(&state.db.read().await.clone())
+    .await
+    .map_err(|e| DatabaseError::context("read password history", e))?;
+    if hashes.is_empty() {
+        return Ok(false);
+    }
+
+    // Argon2 is deliberately slow; keep it off the async workers
+    let password = password.clone();
+    tokio::task::spawn_blocking(move || {
+        hashes.iter().any(|hash| {
+            PasswordHash::new(hash).is_ok_and(|hash| {
+                Argon2::default()
+                    .verify_password(password.as_ref().expose_secret().as_bytes(), &hash)
+                    .is_ok()
+            })
+        })
+    })
+    .await
+    .map_err(AppError::internal)
+}
+
+// Checks every policy rule and fails with all the ones broken. `user_id` is None for
+// accounts that do not exist yet and so have no history.
+#[tracing::instrument(skip_all)]
+pub async fn enforce(
+    state: &AppState,
+    password: &Password,
+    user_id: Option<Uuid>,
+    personal_info: &[&str],
+) -> AppResult<()> {
+    let policy = &state.settings.password_policy;
+    let mut failed = policy.check(password, personal_info);
+
+    if let Some(user_id) = user_id
+        && policy.history_size > 0
+        && recently_used(state, user_id, password).await?
+    {
+        failed.push(PasswordRule::RecentlyUsed(policy.history_size));
+    }
+
+    if !failed.is_empty() {
+        Err(ValidationError::InvalidPassword(failed))?;
+    }
+    Ok(())
+}
+
+// Adds a password the user now has to their history and forgets the ones beyond the limit
+#[tracing::instrument(skip_all)]
+pub async fn remember(
+    state: &AppState,
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    password: &Password,
+) -> AppResult<()> {
+    let history_size = state.settings.password_policy.history_size;
+    if history_size == 0 {
+        return Ok(());
+    }
+
+    let password = password.clone();
+    let hash = tokio::task::spawn_blocking(move || {
+        let salt = SaltString::encode_b64(Uuid::new_v4().as_bytes())?;
+        Argon2::default()
+            .hash_password(password.as_ref().expose_secret().as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+    })
+    .await
+    .map_err(AppError::internal)?
+    .map_err(|e| AppError::internal(anyhow::anyhow!("Failed to hash password: {e}")))?;
+
+    sqlx::query("INSERT INTO password_history (user_id, password_hash) VALUES ($1, $2)")
+        .bind(user_id)
+        .bind(hash)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| DatabaseError::context("store password history", e))?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM password_history
+        WHERE user_id = $1 AND id NOT IN (
+            SELECT id FROM password_history
+            WHERE user_id = $1
+            ORDER BY id DESC
+            LIMIT $2
+        )
+        "#,
+    )
+    .bind(user_id)
+    .bind(i64::from(history_size))
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| DatabaseError::context("trim password history", e))?;
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+// Practices (clinics) partition the users. A caller's practice comes from their
+// access token and the target's from the users mirror, and a user of another
+// practice is reported as not found rather than forbidden, so ids cannot be probed.
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, AuthProviderError, DatabaseError},
+        types::{auth_context::AuthContext, practice::Practice, user::UserUpdate},
+    },
+    services::{
+        audit::{self, AuditAction, AuditEvent},
+        user_sync,
+    },
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+fn database_error(action: &str, e: sqlx::Error) -> AppError {
+    if let sqlx::Error::Database(db) = &e
+        && db.is_unique_violation()
+    {
+        return AuthProviderError::PracticeExists.into();
+    }
+    DatabaseError::context(action, e)
+}
+
+fn actor(auth: &AuthContext) -> Option<Uuid> {
+    Uuid::parse_str(&auth.subject).ok()
+}
+
+// Fails with UserNotFound unless the user shares the caller's practice; callers may
+// always reach their own account
+#[tracing::instrument(skip_all)]
+pub async fn require_same_practice(
+    state: &AppState,
+    auth: &AuthContext,
+    user_id: &str,
+) -> AppResult<()> {
+    if auth.subject == user_id {
+        return Ok(());
+    }
+    let id = Uuid::parse_str(user_id).map_err(|_| AuthProviderError::UserNotFound)?;
+
+    let db = state.db.read().await.clone();
+    // Accounts that were never mirrored belong to no practice
+    let practice_id: Option<Uuid> =
+        sqlx::query_scalar("SELECT practice_id FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&db)
+            .await
+            .map_err(|e| database_error("read user practice", e))?
+            .flatten();
+
+    if practice_id != auth.practice_id {
+        Err(AuthProviderError::UserNotFound)?;
+    }
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn create(state: &AppState, ctx: &RequestContext, name: String) -> AppResult<Practice> {
+    let auth = ctx.require_auth()?;
+    let practice = Practice {
+        id: Uuid::new_v4(),
+        name,
+    };
+
+    let db = state.db.read().await.clone();
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| database_error("begin transaction", e))?;
+    sqlx::query("INSERT INTO practices (id, name) VALUES ($1, $2)")
+        .bind(practice.id)
+        .bind(&practice.name)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| database_error("store practice", e))?;
+
+    // Only commit once the group exists, so every stored practice can take members
+    state
+        .auth_provider
+        .read()
+        .await
+        .create_practice(practice.id)
+        .await?;
+    tx.commit()
+        .await
+        .map_err(|e| database_error("commit practice", e))?;
+
+    let event = AuditEvent {
+        user_id: actor(auth),
+        action: AuditAction::PracticeCreated,
+        resource_type: "practice",
+        resource_id: Some(practice.id.to_string()),
+        reason: None,
+    };
+    audit::record(&db, ctx, event).await?;
+
+    Ok(practice)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn list(state: &AppState) -> AppResult<Vec<Practice>> {
+    let db = state.db.read().await.clone();
+    let rows: Vec<(Uuid, String)> = sqlx::query_as("SELECT id, name FROM practices ORDER BY name")
+        .fetch_all(&db)
+        .await
+        .map_err(|e| database_error("list practices", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, name)| Practice { id, name })
+        .collect())
+}
+
+// Moves a user of the caller's practice into another practice
+#[tracing::instrument(skip_all)]
+pub async fn assign(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+    practice_id: Uuid,
+) -> AppResult<()> {
+    let auth = ctx.require_auth()?;
+    require_same_practice(state, auth, user_id).await?;
+
+    let db = state.db.read().await.clone();
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM practices WHERE id = $1)")
+        .bind(practice_id)
+        .fetch_one(&db)
+        .await
+        .map_err(|e| database_error("read practice", e))?;
+    if !exists {
+        Err(AuthProviderError::PracticeNotFound)?;
+    }
+
+    let current = state
+        .auth_provider
+        .read()
+        .await
+        .get_user(user_id.to_string())
+        .await?;
+    let update = UserUpdate {
+        user_id: Some(user_id.to_string()),
+        practice_id: Some(Some(practice_id)),
+        ..Default::default()
+    };
+    user_sync::update_user(state, &current, update).await?;
+
+    let event = AuditEvent {
+        user_id: actor(auth),
+        action: AuditAction::UserPracticeChanged,
+        resource_type: "user",
+        resource_id: Some(user_id.to_string()),
+        reason: None,
+    };
+    audit::record(&db, ctx, event).await?;
+
+    Ok(())
+}
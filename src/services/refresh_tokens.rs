@@ -0,0 +1,199 @@
+// Tracks refresh token rotation so a replayed token can be told apart from a
+// fresh one. Each login starts a family; a token that was already rotated
+// showing up again means it leaked, so the family and its session are ended.
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, AuthProviderError, DatabaseError},
+        types::token::AuthTokens,
+    },
+    services::audit::{self, AuditAction, AuditEvent},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+fn hash_token(token: &SecretString) -> String {
+    format!("{:x}", Sha256::digest(token.expose_secret().as_bytes()))
+}
+
+async fn insert_token(
+    conn: &mut PgConnection,
+    family_id: Uuid,
+    token: &SecretString,
+) -> AppResult<()> {
+    sqlx::query("INSERT INTO refresh_tokens (token_hash, family_id) VALUES ($1, $2)")
+        .bind(hash_token(token))
+        .bind(family_id)
+        .execute(conn)
+        .await
+        .map_err(|e| DatabaseError::context("store refresh token", e))?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn start_family(state: &AppState, user_id: &str, tokens: &AuthTokens) -> AppResult<()> {
+    let user_id = Uuid::parse_str(user_id).map_err(AppError::internal)?;
+    let family_id = Uuid::new_v4();
+
+    let mut tx = state
+        .db
+        .read()
+        .await
+        .begin()
+        .await
+        .map_err(|e| DatabaseError::context("begin transaction", e))?;
+    sqlx::query("INSERT INTO refresh_token_families (id, user_id, session_id) VALUES ($1, $2, $3)")
+        .bind(family_id)
+        .bind(user_id)
+        .bind(tokens.session_id.as_deref())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::context("store refresh token family", e))?;
+    insert_token(&mut tx, family_id, &tokens.refresh_token).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| DatabaseError::context("commit refresh token family", e))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn rotate(
+    state: &AppState,
+    ctx: &RequestContext,
+    refresh_token: SecretString,
+) -> AppResult<AuthTokens> {
+    let token_hash = hash_token(&refresh_token);
+    let db = state.db.read().await.clone();
+
+    // Claim the token first so two requests presenting it can't both rotate it
+    let claimed: Option<(Uuid,)> = sqlx::query_as(
+        r#"
+        UPDATE refresh_tokens t
+        SET rotated_at = NOW()
+        FROM refresh_token_families f
+        WHERE t.token_hash = $1
+          AND t.rotated_at IS NULL
+          AND f.id = t.family_id
+          AND f.revoked_at IS NULL
+        RETURNING t.family_id
+        "#,
+    )
+    .bind(&token_hash)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| DatabaseError::context("claim refresh token", e))?;
+
+    let Some((family_id,)) = claimed else {
+        return Err(reject_reuse(state, ctx, &token_hash).await);
+    };
+
+    let result = state
+        .auth_provider
+        .read()
+        .await
+        .refresh_user_tokens(refresh_token)
+        .await;
+
+    match result {
+        Ok(tokens) => {
+            let mut conn = db
+                .acquire()
+                .await
+                .map_err(|e| DatabaseError::context("acquire a connection", e))?;
+            insert_token(&mut conn, family_id, &tokens.refresh_token).await?;
+            Ok(tokens)
+        }
+        // The provider no longer accepts the token, so it stays spent
+        Err(e @ AppError::AuthProvider(AuthProviderError::InvalidToken(_))) => Err(e),
+        Err(e) => {
+            // Transient failure: release the claim so the client can retry
+            sqlx::query("UPDATE refresh_tokens SET rotated_at = NULL WHERE token_hash = $1")
+                .bind(&token_hash)
+                .execute(&db)
+                .await
+                .map_err(|e| DatabaseError::context("release refresh token", e))?;
+            Err(e)
+        }
+    }
+}
+
+// Handles a token that could not be claimed: unknown, already rotated, or from a revoked family
+async fn reject_reuse(state: &AppState, ctx: &RequestContext, token_hash: &str) -> AppError {
+    let db = state.db.read().await.clone();
+    let family: Result<Option<(Uuid, Uuid, Option<String>)>, _> = sqlx::query_as(
+        r#"
+        SELECT f.id, f.user_id, f.session_id
+        FROM refresh_tokens t
+        JOIN refresh_token_families f ON f.id = t.family_id
+        WHERE t.token_hash = $1
+        "#,
+    )
+    .bind(token_hash)
+    .fetch_optional(&db)
+    .await;
+
+    let (family_id, user_id, session_id) = match family {
+        Ok(Some(family)) => family,
+        Ok(None) => return AuthProviderError::InvalidToken("Unknown refresh token".into()).into(),
+        Err(e) => return DatabaseError::context("look up refresh token", e),
+    };
+
+    tracing::warn!(%family_id, %user_id, "Refresh token reuse detected");
+
+    let revoked = sqlx::query(
+        "UPDATE refresh_token_families SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL",
+    )
+    .bind(family_id)
+    .execute(&db)
+    .await
+    .map(|result| result.rows_affected() > 0);
+
+    match revoked {
+        Ok(true) => {
+            if let Some(session_id) = session_id
+                && let Err(e) = state
+                    .auth_provider
+                    .read()
+                    .await
+                    .end_session(session_id)
+                    .await
+            {
+                tracing::error!(%family_id, error = %e, "Failed to end session after refresh token reuse");
+            }
+        }
+        Ok(false) => {}
+        Err(e) => return DatabaseError::context("revoke refresh token family", e),
+    }
+
+    let event = AuditEvent {
+        user_id: Some(user_id),
+        action: AuditAction::RefreshTokenReuse,
+        resource_type: "refresh_token_family",
+        resource_id: Some(family_id.to_string()),
+        reason: None,
+    };
+    if let Err(e) = audit::record(&db, ctx, event).await {
+        return e;
+    }
+
+    AuthProviderError::InvalidToken("Refresh token has already been used".into()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_token_is_stable_sha256_hex() {
+        let token = SecretString::from("refresh-token");
+        let hash = hash_token(&token);
+
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, hash_token(&SecretString::from("refresh-token")));
+        assert_ne!(hash, hash_token(&SecretString::from("other-token")));
+    }
+}
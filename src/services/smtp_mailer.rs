@@ -0,0 +1,56 @@
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::Mailbox};
+use secrecy::ExposeSecret;
+
+use crate::{
+    domain::{
+        error::app_error::{AppResult, MailerError},
+        interfaces::mailer::{EmailMessage, Mailer},
+    },
+    utils::config::AppSettings,
+};
+
+// Plain SMTP relay, e.g. Mailpit in development. TLS and auth are left to the relay.
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn from_config(config: &AppSettings) -> AppResult<Self> {
+        let from = config
+            .smtp_from
+            .parse()
+            .map_err(|e| MailerError::Delivery(format!("Invalid SMTP_FROM address: {e}")))?;
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+            .port(config.smtp_port)
+            .build();
+
+        Ok(Self { transport, from })
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for SmtpMailer {
+    #[tracing::instrument(skip_all)]
+    async fn send(&self, message: EmailMessage) -> AppResult<()> {
+        let to = message
+            .to
+            .as_ref()
+            .expose_secret()
+            .parse()
+            .map_err(|e| MailerError::Delivery(format!("Invalid recipient address: {e}")))?;
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(message.subject)
+            .body(message.body)
+            .map_err(|e| MailerError::Delivery(format!("Failed to build email: {e}")))?;
+
+        self.transport
+            .send(email)
+            .await
+            .map_err(|e| MailerError::Delivery(format!("Failed to send email: {e}")))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+// Lists the users of the caller's practice from the local users mirror, which
+// user_sync keeps in step with the auth provider.
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{AppResult, DatabaseError},
+        types::{
+            auth_context::AuthContext,
+            directory::{DirectoryEntry, SortField, UserQuery},
+        },
+    },
+    state::AppState,
+};
+
+pub struct DirectoryPage {
+    pub users: Vec<DirectoryEntry>,
+    // Matching users across all pages
+    pub total: i64,
+}
+
+type DirectoryRow = (
+    Uuid,
+    String,
+    String,
+    String,
+    Option<String>,
+    bool,
+    i64,
+    Option<i64>,
+);
+
+// Filters shared by the count and the page; $1 is the practice, $2..$4 the optional filters
+const FILTERS: &str = r#"
+    practice_id IS NOT DISTINCT FROM $1
+    AND ($2::TEXT IS NULL
+        OR first_name || ' ' || last_name ILIKE $2
+        OR email ILIKE $2
+        OR email_display ILIKE $2)
+    AND ($3::TEXT IS NULL OR role = $3)
+    AND ($4::BOOLEAN IS NULL OR enabled = $4)
+"#;
+
+// Matches the text anywhere, with LIKE wildcards in it taken literally
+fn like_pattern(search: &str) -> String {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+fn order_by(query: &UserQuery) -> String {
+    let direction = if query.sort.descending { "DESC" } else { "ASC" };
+    let columns: &[&str] = match query.sort.field {
+        SortField::Name => &["LOWER(last_name)", "LOWER(first_name)"],
+        SortField::Email => &["email"],
+        SortField::CreatedAt => &["created_at"],
+        SortField::LastLoginAt => &["last_login_at"],
+    };
+    let mut order: Vec<String> = columns
+        .iter()
+        .map(|column| format!("{column} {direction} NULLS LAST"))
+        .collect();
+    // Keeps pages stable when the sort column ties
+    order.push("id".to_string());
+    order.join(", ")
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn search(
+    state: &AppState,
+    auth: &AuthContext,
+    query: &UserQuery,
+) -> AppResult<DirectoryPage> {
+    let db = state.db.read().await.clone();
+    let search = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|search| !search.is_empty())
+        .map(like_pattern);
+    let role = query.role.map(|role| role.as_str());
+
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM users WHERE {FILTERS}"))
+        .bind(auth.practice_id)
+        .bind(&search)
+        .bind(role)
+        .bind(query.enabled)
+        .fetch_one(&db)
+        .await
+        .map_err(|e| DatabaseError::context("count users", e))?;
+
+    let rows: Vec<DirectoryRow> = sqlx::query_as(&format!(
+        r#"
+        SELECT id, first_name, last_name, email_display, role, enabled,
+            EXTRACT(EPOCH FROM created_at)::BIGINT,
+            EXTRACT(EPOCH FROM last_login_at)::BIGINT
+        FROM users
+        WHERE {FILTERS}
+        ORDER BY {order}
+        LIMIT $5 OFFSET $6
+        "#,
+        order = order_by(query),
+    ))
+    .bind(auth.practice_id)
+    .bind(&search)
+    .bind(role)
+    .bind(query.enabled)
+    .bind(i64::from(query.per_page))
+    .bind(query.offset() as i64)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| DatabaseError::context("list users", e))?;
+
+    let users = rows
+        .into_iter()
+        .map(
+            |(user_id, first_name, last_name, email, role, enabled, created_at, last_login_at)| {
+                DirectoryEntry {
+                    user_id,
+                    first_name,
+                    last_name,
+                    email,
+                    role,
+                    enabled,
+                    created_at,
+                    last_login_at,
+                }
+            },
+        )
+        .collect();
+
+    Ok(DirectoryPage { users, total })
+}
@@ -0,0 +1,109 @@
+// Lets owners see who is signed in and end sessions on a lost device or after a
+// termination. Sessions live at the auth provider; web sessions riding on an
+// ended provider session are dropped along with it.
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{AppResult, AuthProviderError, DatabaseError},
+        types::session::UserSession,
+    },
+    services::audit::{self, AuditAction, AuditEvent},
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+// Audit events name the administrator; the revoked sessions are the resource
+fn revocation_event(
+    ctx: &RequestContext,
+    action: AuditAction,
+    resource_type: &'static str,
+    resource_id: String,
+) -> AuditEvent {
+    AuditEvent {
+        user_id: ctx
+            .auth
+            .as_ref()
+            .and_then(|auth| Uuid::parse_str(&auth.subject).ok()),
+        action,
+        resource_type,
+        resource_id: Some(resource_id),
+        reason: None,
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn list(state: &AppState, user_id: &str) -> AppResult<Vec<UserSession>> {
+    state
+        .auth_provider
+        .read()
+        .await
+        .list_user_sessions(user_id.to_string())
+        .await
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn revoke(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+    session_id: &str,
+) -> AppResult<()> {
+    // Ending a session by id alone would let the path name a session of another user
+    if !list(state, user_id)
+        .await?
+        .iter()
+        .any(|session| session.session_id == session_id)
+    {
+        Err(AuthProviderError::SessionNotFound)?;
+    }
+
+    state
+        .auth_provider
+        .read()
+        .await
+        .end_session(session_id.to_string())
+        .await?;
+
+    let db = state.db.read().await.clone();
+    sqlx::query("DELETE FROM web_sessions WHERE provider_session_id = $1")
+        .bind(session_id)
+        .execute(&db)
+        .await
+        .map_err(|e| DatabaseError::context("delete web sessions", e))?;
+
+    let event = revocation_event(
+        ctx,
+        AuditAction::SessionRevoked,
+        "user_session",
+        format!("{user_id}/{session_id}"),
+    );
+    audit::record(&db, ctx, event).await
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn revoke_all(state: &AppState, ctx: &RequestContext, user_id: &str) -> AppResult<()> {
+    state
+        .auth_provider
+        .read()
+        .await
+        .end_all_sessions(user_id.to_string())
+        .await?;
+
+    let db = state.db.read().await.clone();
+    if let Ok(id) = Uuid::parse_str(user_id) {
+        sqlx::query("DELETE FROM web_sessions WHERE user_id = $1")
+            .bind(id)
+            .execute(&db)
+            .await
+            .map_err(|e| DatabaseError::context("delete web sessions", e))?;
+    }
+
+    let event = revocation_event(
+        ctx,
+        AuditAction::AllSessionsRevoked,
+        "user",
+        user_id.to_string(),
+    );
+    audit::record(&db, ctx, event).await
+}
@@ -0,0 +1,270 @@
+// Keeps the local users table in step with the auth provider. Every change is
+// written to Postgres inside a transaction that only commits once the provider
+// call succeeded, and provider changes are compensated if the DB write fails.
+use secrecy::ExposeSecret;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{AppError, AppResult, AuthProviderError, DatabaseError},
+        types::{
+            email::Email,
+            password_policy::personal_info,
+            user::{User, UserRole, UserUpdate},
+        },
+    },
+    services::password_policy,
+    state::AppState,
+};
+
+fn parse_user_id(user_id: &str) -> AppResult<Uuid> {
+    // Keycloak ids are UUIDs; anything else cannot name a user
+    Uuid::parse_str(user_id).map_err(|_| AppError::from(AuthProviderError::UserNotFound))
+}
+
+fn database_error(action: &str, e: sqlx::Error) -> AppError {
+    if let sqlx::Error::Database(db) = &e
+        && db.is_unique_violation()
+    {
+        return AuthProviderError::UserExists.into();
+    }
+    DatabaseError::context(action, e)
+}
+
+// The provider's view of a user, as copied into the users table
+struct MirrorRow<'a> {
+    email: &'a Email,
+    first_name: &'a str,
+    last_name: &'a str,
+    role: Option<UserRole>,
+    practice_id: Option<Uuid>,
+    enabled: bool,
+}
+
+async fn upsert_user(conn: &mut PgConnection, user_id: Uuid, row: MirrorRow<'_>) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO users
+            (id, email, email_display, first_name, last_name, role, practice_id, enabled)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (id) DO UPDATE
+        SET email = EXCLUDED.email, email_display = EXCLUDED.email_display,
+            first_name = EXCLUDED.first_name, last_name = EXCLUDED.last_name,
+            role = EXCLUDED.role, practice_id = EXCLUDED.practice_id,
+            enabled = EXCLUDED.enabled, updated_at = NOW()
+        "#,
+    )
+    .bind(user_id)
+    .bind(row.email.as_ref().expose_secret())
+    .bind(row.email.display().expose_secret())
+    .bind(row.first_name)
+    .bind(row.last_name)
+    .bind(row.role.map(|r| r.as_str()))
+    .bind(row.practice_id)
+    .bind(row.enabled)
+    .execute(conn)
+    .await
+    .map_err(|e| database_error("write user row", e))?;
+
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn create_user(state: &AppState, user: User) -> AppResult<String> {
+    let email = user.email.clone();
+    let (first_name, last_name) = (user.first_name.clone(), user.last_name.clone());
+    let role = user.role;
+    let practice_id = user.practice_id;
+    let enabled = user.enabled;
+    let password = user.password.clone();
+    if let Some(password) = &password {
+        let info = personal_info(
+            email.as_ref().expose_secret(),
+            &user.first_name,
+            &user.last_name,
+        );
+        password_policy::enforce(state, password, None, &info).await?;
+    }
+    let user_id = state.auth_provider.read().await.signup_user(user).await?;
+
+    let result = async {
+        let mut conn = state
+            .db
+            .read()
+            .await
+            .acquire()
+            .await
+            .map_err(|e| database_error("acquire a connection", e))?;
+        let id = parse_user_id(&user_id)?;
+        let row = MirrorRow {
+            email: &email,
+            first_name: &first_name,
+            last_name: &last_name,
+            role,
+            practice_id,
+            enabled,
+        };
+        upsert_user(&mut conn, id, row).await?;
+        match &password {
+            Some(password) => password_policy::remember(state, &mut conn, id, password).await,
+            None => Ok(()),
+        }
+    }
+    .await;
+
+    if let Err(e) = result {
+        // The account must not outlive its failed mirror row
+        if let Err(cleanup) = state
+            .auth_provider
+            .read()
+            .await
+            .delete_user(user_id.clone())
+            .await
+        {
+            tracing::error!(%user_id, error = %cleanup, "Failed to remove user after mirroring failed");
+        }
+        return Err(e);
+    }
+
+    Ok(user_id)
+}
+
+// `current` is the user as the provider reports it before the update
+#[tracing::instrument(skip_all)]
+pub async fn update_user(state: &AppState, current: &User, update: UserUpdate) -> AppResult<()> {
+    let user_id = current
+        .user_id
+        .clone()
+        .ok_or(AuthProviderError::UserNotFound)?;
+    let email = update
+        .email
+        .clone()
+        .unwrap_or_else(|| current.email.clone());
+    let role = update.role.unwrap_or(current.role);
+    let practice_id = update.practice_id.unwrap_or(current.practice_id);
+    let first_name = update.first_name.as_deref().unwrap_or(&current.first_name);
+    let last_name = update.last_name.as_deref().unwrap_or(&current.last_name);
+    let id = parse_user_id(&user_id)?;
+
+    if let Some(password) = &update.password {
+        let info = personal_info(email.as_ref().expose_secret(), first_name, last_name);
+        password_policy::enforce(state, password, Some(id), &info).await?;
+    }
+
+    let mut tx = state
+        .db
+        .read()
+        .await
+        .begin()
+        .await
+        .map_err(|e| database_error("begin transaction", e))?;
+    let row = MirrorRow {
+        email: &email,
+        first_name,
+        last_name,
+        role,
+        practice_id,
+        enabled: update.enabled.unwrap_or(current.enabled),
+    };
+    upsert_user(&mut tx, id, row).await?;
+    if let Some(password) = &update.password {
+        password_policy::remember(state, &mut tx, id, password).await?;
+    }
+
+    let provider = state.auth_provider.read().await;
+    // Restores every field the update touches; the previous password is not known, so
+    // a changed one stays in place
+    let revert = UserUpdate {
+        user_id: Some(user_id.clone()),
+        email: update.email.as_ref().map(|_| current.email.clone()),
+        password: None,
+        first_name: update
+            .first_name
+            .as_ref()
+            .map(|_| current.first_name.clone()),
+        last_name: update.last_name.as_ref().map(|_| current.last_name.clone()),
+        role: update.role.map(|_| current.role),
+        email_verified: update.email_verified.map(|_| current.email_verified),
+        practice_id: update.practice_id.map(|_| current.practice_id),
+        enabled: update.enabled.map(|_| current.enabled),
+    };
+    provider.update_user(update).await?;
+
+    if let Err(e) = tx.commit().await {
+        if let Err(cleanup) = provider.update_user(revert).await {
+            tracing::error!(%user_id, error = %cleanup, "Failed to revert user after mirroring failed");
+        }
+        return Err(database_error("commit user row", e));
+    }
+
+    Ok(())
+}
+
+// Stamps the user's row for the directory's last login column
+#[tracing::instrument(skip_all)]
+pub async fn record_login(state: &AppState, user_id: &str) -> AppResult<()> {
+    let id = parse_user_id(user_id)?;
+    sqlx::query("UPDATE users SET last_login_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&*state.db.read().await)
+        .await
+        .map_err(|e| database_error("record login", e))?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn delete_user(state: &AppState, user_id: String) -> AppResult<()> {
+    let id = parse_user_id(&user_id)?;
+
+    let mut tx = state
+        .db
+        .read()
+        .await
+        .begin()
+        .await
+        .map_err(|e| database_error("begin transaction", e))?;
+    // Deleting first surfaces foreign key violations before the account is gone
+    let mirrored = sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| database_error("delete user row", e))?
+        .rows_affected()
+        > 0;
+    // Every credential and session held for the account goes with it; refresh tokens
+    // follow their family
+    for table in [
+        "mfa_factors",
+        "mfa_recovery_codes",
+        "mfa_enrollment_tokens",
+        "refresh_token_families",
+        "web_sessions",
+        "password_history",
+    ] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE user_id = $1"))
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| database_error(&format!("delete {table} rows"), e))?;
+    }
+
+    match state
+        .auth_provider
+        .read()
+        .await
+        .delete_user(user_id.clone())
+        .await
+    {
+        Ok(()) => {}
+        // Already gone upstream: still drop the stale row
+        Err(AppError::AuthProvider(AuthProviderError::UserNotFound)) if mirrored => {}
+        Err(e) => return Err(e),
+    }
+
+    tx.commit().await.map_err(|e| {
+        // Deleted accounts cannot be restored, so this row has to be cleaned up by hand
+        tracing::error!(%user_id, error = %e, "User deleted in auth provider but row remains");
+        database_error("commit user deletion", e)
+    })
+}
@@ -0,0 +1,355 @@
+// Server-side sessions for the web frontend. The browser only holds an opaque id
+// in an HttpOnly cookie; the provider tokens behind it never reach the browser.
+// Sessions end after an idle and an absolute timeout, and state-changing requests
+// must echo the session's CSRF token in a header (double-submit). A session may be
+// opened pending MFA, in which case it only reaches the MFA routes until a code is given.
+use poem::web::cookie::{Cookie, CookieJar, SameSite};
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::app_error::{
+            AppError, AppResult, AuthProviderError, AuthorizationError, DatabaseError,
+        },
+        types::{
+            auth_context::{AuthContext, PendingMfa},
+            token::{AuthTokens, TokenClaims},
+        },
+    },
+    services::mfa,
+    state::AppState,
+    utils::tracing::RequestContext,
+};
+
+pub const SESSION_COOKIE: &str = "ehr_session";
+// Readable by the frontend so it can copy the value into CSRF_HEADER
+pub const CSRF_COOKIE: &str = "ehr_csrf";
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+pub struct NewWebSession {
+    pub id: SecretString,
+    pub csrf_token: String,
+}
+
+fn random_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+fn hash_session_id(session_id: &SecretString) -> String {
+    format!(
+        "{:x}",
+        Sha256::digest(session_id.expose_secret().as_bytes())
+    )
+}
+
+// Compares digests so the time taken doesn't depend on how much of the token matched
+fn csrf_matches(expected: &str, presented: Option<&str>) -> bool {
+    presented.is_some_and(|presented| {
+        Sha256::digest(expected.as_bytes()) == Sha256::digest(presented.as_bytes())
+    })
+}
+
+// Stores the tokens and returns the values for the session cookies
+#[tracing::instrument(skip_all)]
+pub async fn create(
+    state: &AppState,
+    ctx: &RequestContext,
+    user_id: &str,
+    tokens: &AuthTokens,
+    mfa_pending: bool,
+) -> AppResult<NewWebSession> {
+    let user_id = Uuid::parse_str(user_id).map_err(AppError::internal)?;
+    let session = NewWebSession {
+        id: SecretString::from(random_token()),
+        csrf_token: random_token(),
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO web_sessions
+            (id_hash, user_id, provider_session_id, access_token, refresh_token, csrf_token,
+             ip, user_agent, mfa_pending)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+    )
+    .bind(hash_session_id(&session.id))
+    .bind(user_id)
+    .bind(tokens.session_id.as_deref())
+    .bind(tokens.access_token.expose_secret())
+    .bind(tokens.refresh_token.expose_secret())
+    .bind(&session.csrf_token)
+    .bind(ctx.ip.as_deref())
+    .bind(ctx.user_agent.as_deref())
+    .bind(mfa_pending)
+    .execute(&state.db.read().await.clone())
+    .await
+    .map_err(|e| DatabaseError::context("store web session", e))?;
+
+    Ok(session)
+}
+
+// Lax still sends the session cookie when the user follows a link into the app
+pub fn set_cookies(cookie_jar: &CookieJar, session: &NewWebSession) {
+    let mut cookie = Cookie::new_with_str(SESSION_COOKIE, session.id.expose_secret());
+    cookie.set_http_only(true);
+    cookie.set_secure(true);
+    cookie.set_same_site(SameSite::Lax);
+    cookie.set_path("/");
+    cookie_jar.add(cookie);
+
+    let mut cookie = Cookie::new_with_str(CSRF_COOKIE, &session.csrf_token);
+    cookie.set_secure(true);
+    cookie.set_same_site(SameSite::Strict);
+    cookie.set_path("/");
+    cookie_jar.add(cookie);
+}
+
+pub fn clear_cookies(cookie_jar: &CookieJar) {
+    for name in [SESSION_COOKIE, CSRF_COOKIE] {
+        if cookie_jar.get(name).is_some() {
+            cookie_jar.remove(name);
+        }
+    }
+}
+
+// Resolves the caller behind a session cookie, refreshing the stored tokens when the
+// access token has expired. Returns None once the session is unknown or timed out.
+#[tracing::instrument(skip_all)]
+pub async fn authenticate(
+    state: &AppState,
+    session_id: &SecretString,
+    csrf_token: Option<&str>,
+    state_changing: bool,
+) -> AppResult<Option<AuthContext>> {
+    let id_hash = hash_session_id(session_id);
+    let db = state.db.read().await.clone();
+    let settings = state.settings.session;
+
+    let session: Option<(Uuid, String, String, bool, bool)> = sqlx::query_as(
+        r#"
+        SELECT user_id, access_token, csrf_token, mfa_pending,
+            created_at > NOW() - make_interval(secs => $2)
+                AND last_seen_at > NOW() - make_interval(secs => $3)
+        FROM web_sessions
+        WHERE id_hash = $1
+        "#,
+    )
+    .bind(&id_hash)
+    .bind(settings.absolute_timeout_secs as f64)
+    .bind(settings.idle_timeout_secs as f64)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| DatabaseError::context("look up web session", e))?;
+
+    let Some((user_id, access_token, expected_csrf, mfa_pending, live)) = session else {
+        return Ok(None);
+    };
+    if !live {
+        end(state, session_id).await?;
+        return Ok(None);
+    }
+    if state_changing && !csrf_matches(&expected_csrf, csrf_token) {
+        Err(AuthorizationError::InvalidCsrfToken)?;
+    }
+
+    let auth = if mfa_pending {
+        pending_context(state, user_id, &access_token).await?
+    } else {
+        match state
+            .token_validator
+            .validate_access_token(&access_token)
+            .await
+        {
+            Ok(auth) => auth,
+            Err(_) => match refresh(state, &id_hash, &access_token).await? {
+                Some(auth) => auth,
+                None => {
+                    end(state, session_id).await?;
+                    return Ok(None);
+                }
+            },
+        }
+    };
+
+    sqlx::query("UPDATE web_sessions SET last_seen_at = NOW() WHERE id_hash = $1")
+        .bind(&id_hash)
+        .execute(&db)
+        .await
+        .map_err(|e| DatabaseError::context("touch web session", e))?;
+
+    Ok(Some(auth))
+}
+
+// Stands in for the caller until the second factor is given; the stored tokens stay unused
+async fn pending_context(
+    state: &AppState,
+    user_id: Uuid,
+    access_token: &str,
+) -> AppResult<AuthContext> {
+    let subject = user_id.to_string();
+    let step = if mfa::has_confirmed_factor(state, &subject).await? {
+        PendingMfa::Verify
+    } else {
+        PendingMfa::Enroll
+    };
+    // Stored as received from the provider's token endpoint when the session was opened
+    let email = TokenClaims::decode_unverified(access_token)
+        .ok()
+        .and_then(|claims| claims.email);
+
+    Ok(AuthContext {
+        subject,
+        email,
+        realm_roles: Vec::new(),
+        practice_id: None,
+        scopes: Some(Vec::new()),
+        pending_mfa: Some(step),
+    })
+}
+
+// Lets a pending session through once the second factor is given. The session gets a
+// new id and CSRF token, so one seen before sign-in finished can't be reused.
+#[tracing::instrument(skip_all)]
+pub async fn complete_mfa(state: &AppState, session_id: &SecretString) -> AppResult<NewWebSession> {
+    let session = NewWebSession {
+        id: SecretString::from(random_token()),
+        csrf_token: random_token(),
+    };
+
+    let updated = sqlx::query(
+        r#"
+        UPDATE web_sessions SET id_hash = $2, csrf_token = $3, mfa_pending = FALSE
+        WHERE id_hash = $1 AND mfa_pending
+        "#,
+    )
+    .bind(hash_session_id(session_id))
+    .bind(hash_session_id(&session.id))
+    .bind(&session.csrf_token)
+    .execute(&state.db.read().await.clone())
+    .await
+    .map_err(|e| DatabaseError::context("complete web session sign-in", e))?;
+    if updated.rows_affected() == 0 {
+        Err(AuthProviderError::Unauthenticated)?;
+    }
+
+    Ok(session)
+}
+
+// Swaps the stored tokens for fresh ones; None if the provider won't refresh them
+async fn refresh(
+    state: &AppState,
+    id_hash: &str,
+    stale_access_token: &str,
+) -> AppResult<Option<AuthContext>> {
+    let mut tx = state
+        .db
+        .read()
+        .await
+        .begin()
+        .await
+        .map_err(|e| DatabaseError::context("begin transaction", e))?;
+    // Holding the row lock keeps concurrent requests from spending the refresh token twice
+    let tokens: Option<(String, String)> = sqlx::query_as(
+        "SELECT access_token, refresh_token FROM web_sessions WHERE id_hash = $1 FOR UPDATE",
+    )
+    .bind(id_hash)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| DatabaseError::context("lock web session", e))?;
+
+    let Some((access_token, refresh_token)) = tokens else {
+        return Ok(None);
+    };
+    if access_token != stale_access_token {
+        // Another request refreshed the session while this one waited
+        return Ok(state
+            .token_validator
+            .validate_access_token(&access_token)
+            .await
+            .ok());
+    }
+
+    let tokens = match state
+        .auth_provider
+        .read()
+        .await
+        .refresh_user_tokens(SecretString::from(refresh_token))
+        .await
+    {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            tracing::info!(error = %e, "Web session could not be refreshed");
+            return Ok(None);
+        }
+    };
+    let auth = match state
+        .token_validator
+        .validate_access_token(tokens.access_token.expose_secret())
+        .await
+    {
+        Ok(auth) => auth,
+        Err(_) => return Ok(None),
+    };
+
+    sqlx::query("UPDATE web_sessions SET access_token = $2, refresh_token = $3 WHERE id_hash = $1")
+        .bind(id_hash)
+        .bind(tokens.access_token.expose_secret())
+        .bind(tokens.refresh_token.expose_secret())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::context("store refreshed tokens", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| DatabaseError::context("commit refreshed tokens", e))?;
+
+    Ok(Some(auth))
+}
+
+// Drops the session and ends it at the provider, leaving the user's sessions on other
+// devices alone; unknown sessions are ignored
+#[tracing::instrument(skip_all)]
+pub async fn end(state: &AppState, session_id: &SecretString) -> AppResult<()> {
+    let removed: Option<(Option<String>, String)> = sqlx::query_as(
+        "DELETE FROM web_sessions WHERE id_hash = $1 RETURNING provider_session_id, refresh_token",
+    )
+    .bind(hash_session_id(session_id))
+    .fetch_optional(&state.db.read().await.clone())
+    .await
+    .map_err(|e| DatabaseError::context("delete web session", e))?;
+    let Some((provider_session_id, refresh_token)) = removed else {
+        return Ok(());
+    };
+
+    // The row is gone either way, so the cookie no longer works even if these fail
+    let provider = state.auth_provider.read().await;
+    if let Err(e) = provider
+        .revoke_refresh_token(SecretString::from(refresh_token))
+        .await
+    {
+        tracing::warn!(error = %e, "Failed to revoke refresh token of web session");
+    }
+    if let Some(provider_session_id) = provider_session_id
+        && let Err(e) = provider.end_session(provider_session_id).await
+    {
+        tracing::warn!(error = %e, "Failed to end provider session of web session");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csrf_token_must_match_exactly() {
+        let token = random_token();
+
+        assert!(csrf_matches(&token, Some(&token)));
+        assert!(!csrf_matches(&token, Some(&token[1..])));
+        assert!(!csrf_matches(&token, Some("")));
+        assert!(!csrf_matches(&token, None));
+    }
+}
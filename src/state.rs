@@ -3,19 +3,36 @@ use std::sync::Arc;
 use sqlx::PgPool;
 use tokio::sync::RwLock;
 
-use crate::domain::interfaces::auth_provider::AuthProvider;
+use crate::{
+    domain::interfaces::{
+        auth_provider::AuthProvider, mailer::Mailer, token_validator::TokenValidator,
+    },
+    utils::config::AppSettings,
+};
 
 #[derive(Clone)]
 pub struct AppState {
     pub auth_provider: Arc<RwLock<dyn AuthProvider + Send + Sync>>,
     pub db: Arc<RwLock<PgPool>>,
+    pub token_validator: Arc<dyn TokenValidator + Send + Sync>,
+    pub mailer: Arc<dyn Mailer + Send + Sync>,
+    pub settings: Arc<AppSettings>,
 }
 
 impl AppState {
     pub fn new(
         auth_provider: Arc<RwLock<dyn AuthProvider + Send + Sync>>,
         db: Arc<RwLock<PgPool>>,
+        token_validator: Arc<dyn TokenValidator + Send + Sync>,
+        mailer: Arc<dyn Mailer + Send + Sync>,
+        settings: Arc<AppSettings>,
     ) -> Self {
-        Self { auth_provider, db }
+        Self {
+            auth_provider,
+            db,
+            token_validator,
+            mailer,
+            settings,
+        }
     }
 }
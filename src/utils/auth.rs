@@ -0,0 +1,160 @@
+use poem::{
+    Endpoint, IntoResponse, Middleware, Request, Response,
+    http::{Method, header},
+};
+use secrecy::SecretString;
+
+use crate::{
+    domain::{
+        error::{
+            app_error::{AppError, AuthProviderError, AuthorizationError},
+            http_response::AppHttpResponse,
+        },
+        types::{api_key::API_KEY_PREFIX, auth_context::AuthContext, permission::Permission},
+    },
+    services::{
+        api_keys,
+        mfa::{self, ENROLLMENT_TOKEN_PREFIX},
+        web_sessions::{self, CSRF_HEADER, SESSION_COOKIE},
+    },
+    state::AppState,
+    utils::tracing::request_id,
+};
+
+// Validates bearer tokens (JWTs, API keys or MFA enrollment tokens), or failing that the web session cookie,
+// and exposes the caller's identity to RequestContext. Requests with neither pass through
+// unauthenticated; routes that need a caller reject them via RequestContext::require_auth.
+// An expired session cookie is cleared and treated as absent.
+pub struct BearerAuth;
+
+impl<E: Endpoint> Middleware<E> for BearerAuth {
+    type Output = BearerAuthEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        BearerAuthEndpoint { ep }
+    }
+}
+
+pub struct BearerAuthEndpoint<E> {
+    ep: E,
+}
+
+impl<E: Endpoint> Endpoint for BearerAuthEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> poem::Result<Self::Output> {
+        let state = req
+            .data::<AppState>()
+            .cloned()
+            .expect("AppState must be added to the route");
+
+        if let Some(token) = bearer_token(&req) {
+            let validated = if token.starts_with(API_KEY_PREFIX) {
+                api_keys::authenticate(&state, &SecretString::from(token)).await
+            } else if token.starts_with(ENROLLMENT_TOKEN_PREFIX) {
+                mfa::authenticate_enrollment(&state, &SecretString::from(token)).await
+            } else {
+                state.token_validator.validate_access_token(&token).await
+            };
+            match validated {
+                Ok(auth) => {
+                    req.extensions_mut().insert(auth);
+                }
+                Err(e) => return Ok(reject(e, &req)),
+            }
+        } else if let Some(cookie) = req.cookie().get(SESSION_COOKIE) {
+            let session_id = SecretString::from(cookie.value_str());
+            // Browsers attach the cookie to cross-site requests too, so writes need the header
+            let state_changing =
+                !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+
+            match web_sessions::authenticate(
+                &state,
+                &session_id,
+                req.header(CSRF_HEADER),
+                state_changing,
+            )
+            .await
+            {
+                Ok(Some(auth)) => {
+                    req.extensions_mut().insert(auth);
+                }
+                Ok(None) => web_sessions::clear_cookies(req.cookie()),
+                Err(e) => return Ok(reject(e, &req)),
+            }
+        }
+
+        self.ep.call(req).await.map(IntoResponse::into_response)
+    }
+}
+
+// Rejects callers whose roles don't grant the permission, per ROLE_POLICIES
+pub struct RequirePermission(pub Permission);
+
+impl<E: Endpoint> Middleware<E> for RequirePermission {
+    type Output = RequirePermissionEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RequirePermissionEndpoint {
+            ep,
+            permission: self.0,
+        }
+    }
+}
+
+pub struct RequirePermissionEndpoint<E> {
+    ep: E,
+    permission: Permission,
+}
+
+impl<E: Endpoint> Endpoint for RequirePermissionEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> poem::Result<Self::Output> {
+        match req.extensions().get::<AuthContext>() {
+            None => return Ok(reject(AuthProviderError::Unauthenticated.into(), &req)),
+            Some(auth) => {
+                if let Err(error) = auth.require_mfa_complete() {
+                    return Ok(reject(error, &req));
+                }
+                if !auth.has_permission(self.permission) {
+                    let error = AuthorizationError::MissingPermission(self.permission);
+                    return Ok(reject(error.into(), &req));
+                }
+            }
+        }
+
+        self.ep.call(req).await.map(IntoResponse::into_response)
+    }
+}
+
+// Guards for EHRApi operations, used as `#[oai(..., transform = "require_manage_users")]`
+macro_rules! permission_guards {
+    ($($guard:ident => $permission:expr),* $(,)?) => {
+        $(
+            pub fn $guard<E: Endpoint>(ep: E) -> RequirePermissionEndpoint<E> {
+                RequirePermission($permission).transform(ep)
+            }
+        )*
+    };
+}
+
+permission_guards! {
+    require_read_users => Permission::ReadUsers,
+    require_manage_users => Permission::ManageUsers,
+    require_manage_sessions => Permission::ManageSessions,
+    require_manage_practices => Permission::ManagePractices,
+    require_manage_api_keys => Permission::ManageApiKeys,
+    require_delete_users => Permission::DeleteUsers,
+}
+
+fn bearer_token(req: &Request) -> Option<String> {
+    req.header(header::AUTHORIZATION)
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+}
+
+fn reject(error: AppError, req: &Request) -> Response {
+    tracing::warn!(error = %error, "Rejected unauthorized request");
+    AppHttpResponse::from_app_error(error, &request_id(req)).into_response()
+}
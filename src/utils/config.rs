@@ -1,15 +1,198 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
 use secrecy::SecretString;
 
+use crate::domain::types::password_policy::{CharacterClass, PasswordPolicy};
+
+// Identity backend picked at EHRApp::build time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthProviderKind {
+    Keycloak,
+    // Offline HashMap store for local development and tests
+    InMemory,
+}
+
+impl AuthProviderKind {
+    // Reads AUTH_PROVIDER ("keycloak" or "in_memory")
+    fn from_env(default: Self) -> Self {
+        match std::env::var("AUTH_PROVIDER").as_deref() {
+            Err(_) => default,
+            Ok("keycloak") => AuthProviderKind::Keycloak,
+            Ok("in_memory") => AuthProviderKind::InMemory,
+            Ok(other) => panic!("AUTH_PROVIDER must be 'keycloak' or 'in_memory', got '{other}'"),
+        }
+    }
+
+    // Keycloak settings are only required when Keycloak is the provider
+    fn keycloak_var(&self, name: &str) -> String {
+        match std::env::var(name) {
+            Ok(value) => value,
+            Err(_) if *self == AuthProviderKind::InMemory => String::new(),
+            Err(_) => panic!("{name} must be set in .env or environment"),
+        }
+    }
+}
+
+// Limits on failed logins before an account or source address is locked out
+#[derive(Clone, Copy, Debug)]
+pub struct LoginThrottleSettings {
+    pub max_account_failures: u32,
+    pub max_ip_failures: u32,
+    // First lockout lasts this long and each repeat doubles it, up to max_lockout_secs
+    pub lockout_secs: u64,
+    pub max_lockout_secs: u64,
+}
+
+impl LoginThrottleSettings {
+    fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            max_account_failures: var("LOGIN_MAX_FAILURES", 5),
+            max_ip_failures: var("LOGIN_IP_MAX_FAILURES", 20),
+            lockout_secs: var("LOGIN_LOCKOUT_SECS", 60),
+            max_lockout_secs: var("LOGIN_LOCKOUT_MAX_SECS", 3600),
+        }
+    }
+}
+
+// Lifetimes of browser sessions held in the session cookie
+#[derive(Clone, Copy, Debug)]
+pub struct SessionSettings {
+    // Ends a session nobody has used for this long
+    pub idle_timeout_secs: u64,
+    // Ends a session this long after sign-in, however active it is
+    pub absolute_timeout_secs: u64,
+}
+
+impl SessionSettings {
+    fn from_env() -> Self {
+        fn var(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            idle_timeout_secs: var("SESSION_IDLE_TIMEOUT_SECS", 30 * 60),
+            absolute_timeout_secs: var("SESSION_ABSOLUTE_TIMEOUT_SECS", 12 * 60 * 60),
+        }
+    }
+}
+
+// How new accounts get onto the system. Accounts are only ever created through the API,
+// so keycloak/ehr-realm.json keeps the hosted login page's Register link switched off.
+#[derive(Clone, Debug)]
+pub struct RegistrationSettings {
+    // Whether anyone may create an account through /auth/signup without a token
+    pub open_signup: bool,
+    pub invitations_enabled: bool,
+    pub invitation_ttl_secs: u64,
+    // Page that accepts invitations; the token is appended as ?token=
+    pub invitation_url: String,
+}
+
+impl RegistrationSettings {
+    // Open signup turns off once invitations are the way in, whatever OPEN_SIGNUP says
+    fn from_env(default_invitation_url: String) -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let invitations_enabled = var("INVITATIONS_ENABLED", false);
+        Self {
+            open_signup: var("OPEN_SIGNUP", true) && !invitations_enabled,
+            invitations_enabled,
+            invitation_ttl_secs: var("INVITATION_TTL_SECS", 7 * 24 * 60 * 60),
+            invitation_url: var("INVITATION_URL", default_invitation_url),
+        }
+    }
+}
+
+// Rules new passwords must meet; anything unset keeps PasswordPolicy's default.
+// keycloak/ehr-realm.json carries the defaults as the realm's passwordPolicy, so a
+// deployment that changes these must also set PasswordPolicy::keycloak_policy() there.
+fn password_policy_from_env() -> PasswordPolicy {
+    fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+
+    let default = PasswordPolicy::default();
+    // Comma-separated, e.g. "lowercase,uppercase,digit,symbol"; empty requires none
+    let required_classes = match std::env::var("PASSWORD_REQUIRED_CLASSES") {
+        Err(_) => default.required_classes,
+        Ok(classes) => classes
+            .split(',')
+            .map(str::trim)
+            .filter(|class| !class.is_empty())
+            .map(|class| {
+                class.parse::<CharacterClass>().unwrap_or_else(|_| {
+                    panic!("PASSWORD_REQUIRED_CLASSES has unknown class '{class}'")
+                })
+            })
+            .collect(),
+    };
+
+    PasswordPolicy {
+        min_length: var("PASSWORD_MIN_LENGTH", default.min_length),
+        max_length: var("PASSWORD_MAX_LENGTH", default.max_length),
+        required_classes,
+        forbid_personal_info: var(
+            "PASSWORD_FORBID_PERSONAL_INFO",
+            default.forbid_personal_info,
+        ),
+        history_size: var("PASSWORD_HISTORY", default.history_size),
+        reject_breached: var("PASSWORD_REJECT_BREACHED", default.reject_breached),
+    }
+}
+
+// Key that encrypts TOTP secrets at rest: 32 random bytes, base64-encoded, e.g. from
+// `openssl rand -base64 32`. Losing it invalidates every enrolled authenticator.
+fn mfa_secret_key_from_env() -> SecretString {
+    let key =
+        std::env::var("MFA_SECRET_KEY").expect("MFA_SECRET_KEY must be set in .env or environment");
+    match STANDARD.decode(key.trim()) {
+        Ok(bytes) if bytes.len() == 32 => SecretString::from(key.trim().to_string()),
+        _ => panic!("MFA_SECRET_KEY must be 32 bytes, base64-encoded"),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AppSettings {
     pub app_host: String,
     pub app_port: u16,
     pub database_url: SecretString,
     pub log_level: String,
+    pub auth_provider: AuthProviderKind,
     pub keycloak_base_url: String,
     pub keycloak_realm: String,
     pub keycloak_client_id: String,
     pub keycloak_client_secret: Option<SecretString>,
+    pub keycloak_issuer: String,
+    pub keycloak_audience: String,
+    // Callback Keycloak sends the browser back to after sign-in; must be a registered redirect URI
+    pub oidc_redirect_uri: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_from: String,
+    // Whether Foo@example.com and foo@example.com are the same account; Keycloak always folds case
+    pub email_case_insensitive_local_part: bool,
+    pub login_throttle: LoginThrottleSettings,
+    pub session: SessionSettings,
+    pub password_policy: PasswordPolicy,
+    pub registration: RegistrationSettings,
+    pub mfa_secret_key: SecretString,
     pub tls_cert_path: String,
     pub tls_key_path: String,
 }
@@ -45,15 +228,52 @@ impl AppSettings {
         let log_level = std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".into());
 
         // Keycloak settings
-        let keycloak_base_url = std::env::var("KEYCLOAK_BASE_URL_PROD")
-            .expect("KEYCLOAK_BASE_URL must be set in .env or environment");
-        let keycloak_realm = std::env::var("KEYCLOAK_REALM")
-            .expect("KEYCLOAK_REALM must be set in .env or environment");
-        let keycloak_client_id = std::env::var("KEYCLOAK_CLIENT_ID")
-            .expect("KEYCLOAK_CLIENT_ID must be set in .env or environment");
+        let auth_provider = AuthProviderKind::from_env(AuthProviderKind::Keycloak);
+        let keycloak_base_url = auth_provider.keycloak_var("KEYCLOAK_BASE_URL_PROD");
+        let keycloak_realm = auth_provider.keycloak_var("KEYCLOAK_REALM");
+        let keycloak_client_id = auth_provider.keycloak_var("KEYCLOAK_CLIENT_ID");
         let keycloak_client_secret = std::env::var("KEYCLOAK_CLIENT_SECRET")
             .ok()
             .map(SecretString::from);
+        // Issuer as it appears in tokens, which can differ from the URL the API reaches Keycloak on
+        let keycloak_issuer = std::env::var("KEYCLOAK_ISSUER")
+            .unwrap_or_else(|_| format!("{keycloak_base_url}/realms/{keycloak_realm}"));
+        let keycloak_audience =
+            std::env::var("KEYCLOAK_AUDIENCE").unwrap_or_else(|_| keycloak_client_id.clone());
+        let oidc_redirect_uri = std::env::var("OIDC_REDIRECT_URI")
+            .unwrap_or_else(|_| format!("https://{app_host}:{app_port}/api/auth/callback"));
+
+        // SMTP settings
+        let smtp_host = std::env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".into());
+        let smtp_port = std::env::var("SMTP_PORT")
+            .ok()
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(1025);
+        let smtp_from =
+            std::env::var("SMTP_FROM").unwrap_or_else(|_| "no-reply@lgr-ehr.local".into());
+
+        // Email settings
+        let email_case_insensitive_local_part = std::env::var("EMAIL_CASE_INSENSITIVE_LOCAL_PART")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(true);
+
+        // Login throttling settings
+        let login_throttle = LoginThrottleSettings::from_env();
+
+        // Browser session settings
+        let session = SessionSettings::from_env();
+
+        // Password policy settings
+        let password_policy = password_policy_from_env();
+
+        // Registration settings
+        let registration = RegistrationSettings::from_env(format!(
+            "https://{app_host}:{app_port}/invitations/accept"
+        ));
+
+        // MFA settings
+        let mfa_secret_key = mfa_secret_key_from_env();
 
         // TLS settings
         let tls_cert_path =
@@ -66,10 +286,23 @@ impl AppSettings {
             app_port,
             database_url: SecretString::from(database_url),
             log_level,
+            auth_provider,
             keycloak_base_url,
             keycloak_realm,
             keycloak_client_id,
             keycloak_client_secret,
+            keycloak_issuer,
+            keycloak_audience,
+            oidc_redirect_uri,
+            smtp_host,
+            smtp_port,
+            smtp_from,
+            email_case_insensitive_local_part,
+            login_throttle,
+            session,
+            password_policy,
+            registration,
+            mfa_secret_key,
             tls_cert_path,
             tls_key_path,
         }
@@ -85,16 +318,55 @@ impl AppSettings {
             .unwrap()
             .port();
 
-        // Keycloak settings
-        let keycloak_base_url = std::env::var("KEYCLOAK_BASE_URL_DEV")
-            .expect("KEYCLOAK_BASE_URL must be set in .env or environment");
-        let keycloak_realm = std::env::var("KEYCLOAK_REALM")
-            .expect("KEYCLOAK_REALM must be set in .env or environment");
-        let keycloak_client_id = std::env::var("KEYCLOAK_CLIENT_ID")
-            .expect("KEYCLOAK_CLIENT_ID must be set in .env or environment");
+        // Keycloak settings; tests run against the in-memory provider unless AUTH_PROVIDER=keycloak
+        let auth_provider = AuthProviderKind::from_env(AuthProviderKind::InMemory);
+        let keycloak_base_url = auth_provider.keycloak_var("KEYCLOAK_BASE_URL_DEV");
+        let keycloak_realm = auth_provider.keycloak_var("KEYCLOAK_REALM");
+        let keycloak_client_id = auth_provider.keycloak_var("KEYCLOAK_CLIENT_ID");
         let keycloak_client_secret = std::env::var("KEYCLOAK_CLIENT_SECRET")
             .ok()
             .map(SecretString::from);
+        let keycloak_issuer = std::env::var("KEYCLOAK_ISSUER")
+            .unwrap_or_else(|_| format!("{keycloak_base_url}/realms/{keycloak_realm}"));
+        let keycloak_audience =
+            std::env::var("KEYCLOAK_AUDIENCE").unwrap_or_else(|_| keycloak_client_id.clone());
+        let oidc_redirect_uri = format!("https://127.0.0.1:{port}/api/auth/callback");
+
+        // SMTP settings
+        let smtp_host = std::env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".into());
+        let smtp_port = std::env::var("SMTP_PORT")
+            .ok()
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(1025);
+        let smtp_from =
+            std::env::var("SMTP_FROM").unwrap_or_else(|_| "no-reply@lgr-ehr.local".into());
+
+        // Email settings
+        let email_case_insensitive_local_part = std::env::var("EMAIL_CASE_INSENSITIVE_LOCAL_PART")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(true);
+
+        // Login throttling settings
+        let login_throttle = LoginThrottleSettings::from_env();
+
+        // Browser session settings
+        let session = SessionSettings::from_env();
+
+        // Password policy settings
+        let password_policy = password_policy_from_env();
+
+        // Registration settings
+        let registration =
+            RegistrationSettings::from_env(format!("https://127.0.0.1:{port}/invitations/accept"));
+
+        // A fresh key per test app
+        let key = [
+            *uuid::Uuid::new_v4().as_bytes(),
+            *uuid::Uuid::new_v4().as_bytes(),
+        ]
+        .concat();
+        let mfa_secret_key = SecretString::from(STANDARD.encode(key));
 
         // TLS settings
         let tls_cert_path =
@@ -107,10 +379,23 @@ impl AppSettings {
             app_port: port,
             database_url,
             log_level: "info".into(),
+            auth_provider,
             keycloak_base_url,
             keycloak_realm,
             keycloak_client_id,
             keycloak_client_secret,
+            keycloak_issuer,
+            keycloak_audience,
+            oidc_redirect_uri,
+            smtp_host,
+            smtp_port,
+            smtp_from,
+            email_case_insensitive_local_part,
+            login_throttle,
+            session,
+            password_policy,
+            registration,
+            mfa_secret_key,
             tls_cert_path,
             tls_key_path,
         }
@@ -145,3 +430,13 @@ impl AppSettings {
         Self::build_database_url(db_name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_realm_file_disables_self_registration() {
+        let realm: serde_json::Value =
+            serde_json::from_str(include_str!("../../keycloak/ehr-realm.json")).unwrap();
+        assert_eq!(realm["registrationAllowed"], false);
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Once;
+
 use poem::{FromRequest, Request, RequestBody};
 use tracing_appender::{
     non_blocking,
@@ -6,6 +8,11 @@ use tracing_appender::{
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, registry, util::SubscriberInitExt};
 
+use crate::domain::{
+    error::app_error::{AppResult, AuthProviderError},
+    types::auth_context::{AuthContext, PendingMfa},
+};
+
 pub fn init_tracing(log_level: &str) {
     color_eyre::install().unwrap();
 
@@ -38,38 +45,79 @@ pub fn init_tracing(log_level: &str) {
     std::mem::forget(_guard2);
 }
 
+// Safe to call from every test; only the first call installs the subscriber
 pub fn init_tracing_for_tests() {
-    color_eyre::install().unwrap();
+    static INIT: Once = Once::new();
 
-    let (non_blocking_stdout, _guard2) = non_blocking(std::io::stdout());
+    INIT.call_once(|| {
+        color_eyre::install().unwrap();
 
-    registry()
-        .with(
-            fmt::layer()
-                .with_writer(non_blocking_stdout)
-                .with_ansi(true)
-                .with_target(false),
-        )
-        .with(EnvFilter::new("debug,sqlx=warn,hyper=warn,reqwest=warn"))
-        .with(ErrorLayer::default())
-        .init();
+        let (non_blocking_stdout, _guard2) = non_blocking(std::io::stdout());
 
-    std::mem::forget(_guard2);
+        registry()
+            .with(
+                fmt::layer()
+                    .with_writer(non_blocking_stdout)
+                    .with_ansi(true)
+                    .with_target(false),
+            )
+            .with(EnvFilter::new("debug,sqlx=warn,hyper=warn,reqwest=warn"))
+            .with(ErrorLayer::default())
+            .init();
+
+        std::mem::forget(_guard2);
+    });
 }
 
 #[derive(Clone)]
 pub struct RequestContext {
     pub request_id: String,
+    pub auth: Option<AuthContext>,
+    // Peer address as seen by the server; recorded in audit events
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl RequestContext {
+    pub fn require_auth(&self) -> AppResult<&AuthContext> {
+        let auth = self
+            .auth
+            .as_ref()
+            .ok_or(AuthProviderError::Unauthenticated)?;
+        auth.require_mfa_complete()?;
+        Ok(auth)
+    }
+
+    // Like require_auth, but also lets in a caller who is still at the given MFA step
+    pub fn require_auth_at(&self, step: PendingMfa) -> AppResult<&AuthContext> {
+        let auth = self
+            .auth
+            .as_ref()
+            .ok_or(AuthProviderError::Unauthenticated)?;
+        if auth.pending_mfa != Some(step) {
+            auth.require_mfa_complete()?;
+        }
+        Ok(auth)
+    }
+}
+
+pub fn request_id(req: &Request) -> String {
+    req.header("x-request-id")
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
 }
 
 impl<'a> FromRequest<'a> for RequestContext {
     async fn from_request(req: &'a Request, _: &mut RequestBody) -> poem::Result<Self> {
-        let request_id = req
-            .header("x-request-id")
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
-
-        Ok(RequestContext { request_id })
+        Ok(RequestContext {
+            request_id: request_id(req),
+            auth: req.extensions().get::<AuthContext>().cloned(),
+            ip: req
+                .remote_addr()
+                .as_socket_addr()
+                .map(|addr| addr.ip().to_string()),
+            user_agent: req.header("user-agent").map(|s| s.to_string()),
+        })
     }
 }
 
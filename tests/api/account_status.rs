@@ -0,0 +1,110 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+async fn audited_reasons(app: &TestApp, action: &str) -> Vec<Option<String>> {
+    sqlx::query_scalar("SELECT reason FROM audit_logs WHERE action = $1 ORDER BY id")
+        .bind(action)
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn disabled_user_should_be_signed_out_and_unable_to_log_in() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login(&email).await;
+    let (_, admin_token) = app
+        .signup_and_login_as(&generate_valid_email(), "admin")
+        .await;
+
+    let response = app
+        .post_disable_user(&user_id, "  Left the practice ", &admin_token)
+        .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["enabled"], false);
+
+    assert_eq!(app.get_user(&user_id, &token).await.status(), 401);
+    let response = app
+        .post_login(serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status(), 403);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "AccountDisabled");
+
+    let response = app.get_user(&user_id, &admin_token).await;
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["enabled"], false);
+    assert_eq!(
+        audited_reasons(&app, "account_disabled").await,
+        vec![Some("Left the practice".to_string())]
+    );
+
+    let response = app
+        .post_enable_user(&user_id, "Rehired", &admin_token)
+        .await;
+    assert_eq!(response.status(), 200);
+    app.login(&email, "Password123!").await;
+    assert_eq!(
+        audited_reasons(&app, "account_enabled").await,
+        vec![Some("Rehired".to_string())]
+    );
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn disable_user_should_require_a_reason() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (user_id, _) = app.signup_and_login(&generate_valid_email()).await;
+    let (_, admin_token) = app
+        .signup_and_login_as(&generate_valid_email(), "admin")
+        .await;
+
+    let response = app.post_disable_user(&user_id, "   ", &admin_token).await;
+    assert_eq!(response.status(), 400);
+    let response = app
+        .post_disable_user(&user_id, &"x".repeat(501), &admin_token)
+        .await;
+    assert_eq!(response.status(), 400);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn disable_user_should_reject_self_and_higher_roles() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (owner_id, _) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let (admin_id, admin_token) = app
+        .signup_and_login_as(&generate_valid_email(), "admin")
+        .await;
+    let (_, clinician_token) = app
+        .signup_and_login_as(&generate_valid_email(), "clinician")
+        .await;
+
+    let response = app
+        .post_disable_user(&admin_id, "Testing", &admin_token)
+        .await;
+    assert_eq!(response.status(), 400);
+
+    let response = app
+        .post_disable_user(&owner_id, "Testing", &admin_token)
+        .await;
+    assert_eq!(response.status(), 403);
+
+    let response = app
+        .post_disable_user(&admin_id, "Testing", &clinician_token)
+        .await;
+    assert_eq!(response.status(), 403);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "MissingPermission");
+
+    app.cleanup().await;
+}
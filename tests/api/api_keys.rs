@@ -0,0 +1,139 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+// Creates a key as the owner, returning its id and the key itself
+async fn create_key(app: &TestApp, scopes: &[&str], owner_token: &str) -> (String, String) {
+    let response = app
+        .post_api_key(
+            serde_json::json!({ "name": "Lab interface", "scopes": scopes }),
+            owner_token,
+        )
+        .await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    (
+        body["api_key_id"].as_str().unwrap().to_string(),
+        body["key"].as_str().unwrap().to_string(),
+    )
+}
+
+#[tokio::test]
+async fn api_keys_should_authenticate_within_their_scopes() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (user_id, _) = app.signup_and_login(&generate_valid_email()).await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+
+    let (key_id, key) = create_key(&app, &["users:read", "patients:read"], &owner_token).await;
+
+    assert_eq!(app.get_user(&user_id, &key).await.status(), 200);
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "first_name": "Renamed" }),
+            &key,
+        )
+        .await;
+    assert_eq!(response.status(), 403);
+
+    // Only the hash is stored, and listing never reveals the key again
+    let stored: String = sqlx::query_scalar("SELECT key_hash FROM api_keys WHERE id = $1::uuid")
+        .bind(&key_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_ne!(stored, key);
+
+    let response = app.get_api_keys(&owner_token).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let listed = &body["api_keys"][0];
+    assert_eq!(listed["api_key_id"], key_id.as_str());
+    assert_eq!(
+        listed["scopes"],
+        serde_json::json!(["patients:read", "users:read"])
+    );
+    assert!(key.starts_with(listed["prefix"].as_str().unwrap()));
+    assert!(listed.get("key").is_none());
+    assert!(listed["last_used_at"].is_i64());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn revoked_and_expired_keys_should_be_rejected() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (user_id, _) = app.signup_and_login(&generate_valid_email()).await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let (revoked_id, revoked) = create_key(&app, &["users:read"], &owner_token).await;
+    let (expired_id, expired) = create_key(&app, &["users:read"], &owner_token).await;
+
+    let response = app.delete_api_key(&revoked_id, &owner_token).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(app.get_user(&user_id, &revoked).await.status(), 401);
+
+    sqlx::query("UPDATE api_keys SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1::uuid")
+        .bind(&expired_id)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(app.get_user(&user_id, &expired).await.status(), 401);
+
+    let unknown = format!("lgrk_{}", uuid::Uuid::new_v4().simple());
+    assert_eq!(app.get_user(&user_id, &unknown).await.status(), 401);
+
+    let missing = uuid::Uuid::new_v4().to_string();
+    assert_eq!(
+        app.delete_api_key(&missing, &owner_token).await.status(),
+        404
+    );
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn creating_api_keys_should_be_owner_only_and_validated() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let admin_token = app.admin_token().await;
+
+    let response = app
+        .post_api_key(
+            serde_json::json!({ "name": "Billing", "scopes": ["claims:write"] }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 403);
+    assert_eq!(app.get_api_keys(&admin_token).await.status(), 403);
+
+    for body in [
+        serde_json::json!({ "name": "Billing", "scopes": [] }),
+        serde_json::json!({ "name": "Billing", "scopes": ["claims"] }),
+        serde_json::json!({ "name": "Billing", "scopes": ["api_keys:manage"] }),
+        serde_json::json!({ "name": " ", "scopes": ["claims:write"] }),
+        serde_json::json!({ "name": "Billing", "scopes": ["claims:write"], "expires_in": 0 }),
+        serde_json::json!({
+            "name": "Billing",
+            "scopes": ["claims:write"],
+            "expires_in": 2 * 365 * 24 * 60 * 60
+        }),
+    ] {
+        let response = app.post_api_key(body.clone(), &owner_token).await;
+        assert_eq!(response.status(), 400, "{body}");
+    }
+
+    // A key cannot reach the key endpoints, whatever its scopes
+    let (_, key) = create_key(&app, &["users:manage"], &owner_token).await;
+    assert_eq!(app.get_api_keys(&key).await.status(), 403);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use lgr_ehr::utils::{config::AuthProviderKind, tracing::init_tracing_for_tests};
+
+use crate::{
+    helpers::{TestApp, WebSession, generate_valid_email, totp_code},
+    token_endpoint::{TokenEndpointStub, VALID_CODE},
+};
+
+// App signing browsers in through Keycloak, with the token endpoint stubbed out
+async fn app_with_stubbed_keycloak() -> (TestApp, TokenEndpointStub) {
+    let (stub, base_url) = TokenEndpointStub::start().await;
+    let app = TestApp::new_with(|settings| {
+        settings.auth_provider = AuthProviderKind::Keycloak;
+        settings.keycloak_realm = "ehr".into();
+        settings.keycloak_client_id = "ehr-backend".into();
+        settings.keycloak_issuer = format!("{base_url}/realms/ehr");
+        settings.keycloak_base_url = base_url;
+    })
+    .await;
+    (app, stub)
+}
+
+// Query parameters of the provider URL the browser was redirected to
+fn redirect_params(response: &reqwest::Response) -> (String, HashMap<String, String>) {
+    let location = response.headers()["location"].to_str().unwrap();
+    let url = reqwest::Url::parse(location).expect("Location is not an absolute URL");
+    let params = url.query_pairs().into_owned().collect();
+    let mut endpoint = url.clone();
+    endpoint.set_query(None);
+    (endpoint.to_string(), params)
+}
+
+// Starts a sign-in and tells the stub to expect it; returns the state value
+async fn begin_sign_in(app: &TestApp, stub: &TokenEndpointStub) -> String {
+    let response = app.get_authorize(Some("/patients")).await;
+    assert_eq!(response.status(), 302);
+    let (_, params) = redirect_params(&response);
+    stub.expect(&params["code_challenge"], &params["nonce"]);
+    params["state"].clone()
+}
+
+fn session_cookie(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|value| value.to_str().unwrap().to_string())
+        .find(|cookie| cookie.starts_with("ehr_session="))
+}
+
+// The cookies a browser would send after the response, and the CSRF token it can read
+fn web_session(response: &reqwest::Response) -> WebSession {
+    let cookies: Vec<String> = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|value| {
+            value
+                .to_str()
+                .unwrap()
+                .split(';')
+                .next()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    let csrf_token = cookies
+        .iter()
+        .find_map(|cookie| cookie.strip_prefix("ehr_csrf="))
+        .expect("No CSRF cookie set")
+        .to_string();
+    WebSession {
+        cookie: cookies.join("; "),
+        csrf_token,
+    }
+}
+
+#[tokio::test]
+async fn authorize_should_redirect_to_provider_with_pkce() {
+    init_tracing_for_tests();
+    let (mut app, _stub) = app_with_stubbed_keycloak().await;
+
+    let response = app.get_authorize(None).await;
+
+    assert_eq!(response.status(), 302);
+    let (endpoint, params) = redirect_params(&response);
+    assert!(endpoint.ends_with("/realms/ehr/protocol/openid-connect/auth"));
+    assert_eq!(params["response_type"], "code");
+    assert_eq!(params["client_id"], "ehr-backend");
+    assert_eq!(params["code_challenge_method"], "S256");
+    assert_eq!(params["code_challenge"].len(), 43);
+    assert!(params["redirect_uri"].ends_with("/api/auth/callback"));
+    assert!(!params["state"].is_empty());
+    assert!(!params["nonce"].is_empty());
+    assert!(!params.contains_key("code_verifier"));
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn authorize_should_reject_return_to_outside_the_site() {
+    init_tracing_for_tests();
+    let (mut app, _stub) = app_with_stubbed_keycloak().await;
+
+    for return_to in ["https://evil.example", "//evil.example"] {
+        let response = app.get_authorize(Some(return_to)).await;
+        assert_eq!(response.status(), 400, "Failed for return_to: {return_to}");
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn callback_should_set_session_cookie_and_redirect() {
+    init_tracing_for_tests();
+    let (mut app, stub) = app_with_stubbed_keycloak().await;
+    let state = begin_sign_in(&app, &stub).await;
+
+    let response = app
+        .get_callback(&[("state", &state), ("code", VALID_CODE)])
+        .await;
+
+    assert_eq!(response.status(), 302);
+    assert_eq!(response.headers()["location"], "/patients");
+    let cookie = session_cookie(&response).expect("No session cookie set");
+    assert!(cookie.contains("HttpOnly"));
+    assert!(cookie.contains("Secure"));
+    assert!(cookie.contains("SameSite=Lax"));
+
+    let (sessions,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM web_sessions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(sessions, 1);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn callback_should_reject_unknown_state() {
+    init_tracing_for_tests();
+    let (mut app, stub) = app_with_stubbed_keycloak().await;
+    begin_sign_in(&app, &stub).await;
+
+    let response = app
+        .get_callback(&[("state", "forged-state"), ("code", VALID_CODE)])
+        .await;
+
+    assert_eq!(response.status(), 400);
+    assert!(session_cookie(&response).is_none());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn callback_should_reject_reused_state() {
+    init_tracing_for_tests();
+    let (mut app, stub) = app_with_stubbed_keycloak().await;
+    let state = begin_sign_in(&app, &stub).await;
+
+    let first = app
+        .get_callback(&[("state", &state), ("code", VALID_CODE)])
+        .await;
+    assert_eq!(first.status(), 302);
+
+    let second = app
+        .get_callback(&[("state", &state), ("code", VALID_CODE)])
+        .await;
+    assert_eq!(second.status(), 400);
+    assert!(session_cookie(&second).is_none());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn callback_should_reject_code_redeemed_with_another_verifier() {
+    init_tracing_for_tests();
+    let (mut app, stub) = app_with_stubbed_keycloak().await;
+    let state = begin_sign_in(&app, &stub).await;
+    // A second sign-in replaces the challenge the stub expects
+    begin_sign_in(&app, &stub).await;
+
+    let response = app
+        .get_callback(&[("state", &state), ("code", VALID_CODE)])
+        .await;
+
+    assert_eq!(response.status(), 401);
+    assert!(session_cookie(&response).is_none());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn callback_should_reject_provider_error() {
+    init_tracing_for_tests();
+    let (mut app, stub) = app_with_stubbed_keycloak().await;
+    let state = begin_sign_in(&app, &stub).await;
+
+    let response = app
+        .get_callback(&[("state", &state), ("error", "access_denied")])
+        .await;
+
+    assert_eq!(response.status(), 400);
+    assert!(session_cookie(&response).is_none());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn callback_should_hold_session_until_enrolled_user_gives_code() {
+    init_tracing_for_tests();
+    let (mut app, stub) = app_with_stubbed_keycloak().await;
+    let user_id = uuid::Uuid::new_v4().to_string();
+    let secret = totp_rs::Secret::generate_secret().to_encoded().to_string();
+    sqlx::query(
+        r#"
+        INSERT INTO mfa_factors (id, user_id, kind, label, secret, confirmed_at)
+        VALUES ($1, $2::uuid, 'totp', 'Work phone', $3, NOW())
+        "#,
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind(&user_id)
+    .bind(&secret)
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    // Stored the way factors were before secrets were encrypted
+    app.seal_mfa_secrets().await;
+    stub.sign_in_as(&user_id, &generate_valid_email());
+    let state = begin_sign_in(&app, &stub).await;
+
+    let response = app
+        .get_callback(&[("state", &state), ("code", VALID_CODE)])
+        .await;
+    assert_eq!(response.status(), 302);
+    let session = web_session(&response);
+
+    let response = app.get_user_in_session(&user_id, &session).await;
+    assert_eq!(response.status(), 401);
+    let error: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(error["code"], "MfaRequired");
+
+    let response = app
+        .post_verify_mfa(
+            serde_json::json!({ "code": totp_code(&secret, -5) }),
+            &session,
+        )
+        .await;
+    assert_eq!(response.status(), 401);
+    let error: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(error["code"], "InvalidMfaCode");
+    let (failures,): (i32,) =
+        sqlx::query_as("SELECT failures FROM login_attempts WHERE scope = 'account'")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(failures, 1);
+
+    let response = app
+        .post_verify_mfa(
+            serde_json::json!({ "code": totp_code(&secret, 0) }),
+            &session,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let upgraded = web_session(&response);
+    assert_ne!(upgraded.csrf_token, session.csrf_token);
+
+    let (pending,): (bool,) = sqlx::query_as("SELECT mfa_pending FROM web_sessions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert!(!pending);
+    // The id handed out before the code was given no longer opens the session
+    let response = app
+        .post_verify_mfa(
+            serde_json::json!({ "code": totp_code(&secret, 1) }),
+            &session,
+        )
+        .await;
+    assert_eq!(response.status(), 401);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,143 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+#[tokio::test]
+async fn change_password_should_replace_password() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (_, token) = app.signup_and_login(&email).await;
+
+    let response = app
+        .post_change_password(
+            serde_json::json!({
+                "current_password": "Password123!",
+                "new_password": "NewPassword456!"
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let response = app
+        .post_login(serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status(), 401);
+
+    app.login(&email, "NewPassword456!").await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn change_password_should_return_400_for_invalid_passwords() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let (_, token) = app.signup_and_login(&generate_valid_email()).await;
+
+    let test_cases = [
+        // Wrong current password
+        (
+            serde_json::json!({
+                "current_password": "WrongPassword1!",
+                "new_password": "NewPassword456!"
+            }),
+            "InvalidInput",
+        ),
+        // New password fails validation
+        (
+            serde_json::json!({
+                "current_password": "Password123!",
+                "new_password": "weak"
+            }),
+            "InvalidPassword",
+        ),
+        // New password same as the current one
+        (
+            serde_json::json!({
+                "current_password": "Password123!",
+                "new_password": "Password123!"
+            }),
+            "InvalidInput",
+        ),
+    ];
+
+    for (body, code) in test_cases {
+        let response = app.post_change_password(body.clone(), &token).await;
+        assert_eq!(response.status(), 400, "Failed for input: {:?}", body);
+
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["code"], code);
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn change_password_should_return_401_without_token() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_change_password(
+            serde_json::json!({
+                "current_password": "Password123!",
+                "new_password": "NewPassword456!"
+            }),
+            "",
+        )
+        .await;
+    assert_eq!(response.status(), 401);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn change_password_should_lock_account_after_repeated_wrong_guesses() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login(&email).await;
+    let body = serde_json::json!({
+        "current_password": "WrongPassword1!",
+        "new_password": "NewPassword456!"
+    });
+
+    for i in 0..4 {
+        let response = app.post_change_password(body.clone(), &token).await;
+        assert_eq!(response.status(), 400, "Attempt {} failed", i);
+    }
+    let response = app.post_change_password(body, &token).await;
+    assert_eq!(response.status(), 423);
+
+    // The lockout covers logins and the right current password alike
+    let response = app
+        .post_change_password(
+            serde_json::json!({
+                "current_password": "Password123!",
+                "new_password": "NewPassword456!"
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 423);
+    let response = app
+        .post_login(serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status(), 423);
+
+    let audited: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM audit_logs WHERE user_id = $1::uuid AND action = 'login_lockout'",
+    )
+    .bind(&user_id)
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to query audit_logs");
+    assert_eq!(audited, 1);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,162 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+#[tokio::test]
+async fn delete_user_should_return_403_for_non_owner_roles() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let user_id = app.signup(&generate_valid_email()).await;
+
+    let mut created = Vec::new();
+    for role in ["biller", "clinician", "admin"] {
+        let (id, token) = app.signup_and_login_as(&generate_valid_email(), role).await;
+
+        let response = app
+            .post_delete_user(serde_json::json!({ "user_id": user_id }), &token)
+            .await;
+        assert_eq!(response.status(), 403, "Role {} was not rejected", role);
+
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["code"], "MissingPermission");
+
+        created.push(id);
+    }
+
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let response = app
+        .post_delete_user(serde_json::json!({ "user_id": user_id }), &owner_token)
+        .await;
+    assert_eq!(response.status(), 200);
+
+    for id in created {
+        app.remove_user(&id).await;
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn delete_user_should_return_401_without_token() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_delete_user(
+            serde_json::json!({ "user_id": uuid::Uuid::new_v4().to_string() }),
+            "",
+        )
+        .await;
+
+    assert_eq!(response.status(), 401);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn delete_user_should_remove_mirrored_row() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let user_id = app.signup(&generate_valid_email()).await;
+    assert!(app.mirrored_user(&user_id).await.is_some());
+    let family_id = uuid::Uuid::new_v4();
+    for statement in [
+        "INSERT INTO refresh_token_families (id, user_id) VALUES ($2, $1::uuid)",
+        "INSERT INTO refresh_tokens (token_hash, family_id) VALUES ('hash', $2)",
+        "INSERT INTO mfa_enrollment_tokens (token_hash, user_id, email, expires_at) VALUES ('hash', $1::uuid, 'a@b.c', NOW())",
+    ] {
+        sqlx::query(statement)
+            .bind(&user_id)
+            .bind(family_id)
+            .execute(&app.db_pool)
+            .await
+            .unwrap();
+    }
+
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let response = app
+        .post_delete_user(serde_json::json!({ "user_id": user_id }), &owner_token)
+        .await;
+    assert_eq!(response.status(), 200);
+
+    assert!(app.mirrored_user(&user_id).await.is_none());
+    // No credentials are left behind
+    let remaining: i64 = sqlx::query_scalar(
+        r#"
+        SELECT (SELECT COUNT(*) FROM refresh_token_families WHERE user_id = $1::uuid)
+            + (SELECT COUNT(*) FROM refresh_tokens WHERE family_id = $2)
+            + (SELECT COUNT(*) FROM mfa_enrollment_tokens WHERE user_id = $1::uuid)
+        "#,
+    )
+    .bind(&user_id)
+    .bind(family_id)
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(remaining, 0);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn delete_user_should_return_409_once_user_has_touched_phi() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let user_id = app.signup(&generate_valid_email()).await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    sqlx::query(
+        "INSERT INTO audit_logs (user_id, action, resource_type) VALUES ($1::uuid, 'read', 'patient')",
+    )
+    .bind(&user_id)
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .post_delete_user(serde_json::json!({ "user_id": user_id }), &owner_token)
+        .await;
+    assert_eq!(response.status(), 409);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "PhiHistory");
+    assert!(app.mirrored_user(&user_id).await.is_some());
+
+    let response = app
+        .post_disable_user(&user_id, "Left the practice", &owner_token)
+        .await;
+    assert_eq!(response.status(), 200);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn delete_user_should_return_409_once_user_has_signed_in() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    // Nothing audits patient reads yet, so any sign-in counts as possible PHI access
+    let (user_id, _) = app.signup_and_login(&generate_valid_email()).await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+
+    let response = app
+        .post_delete_user(serde_json::json!({ "user_id": user_id }), &owner_token)
+        .await;
+    assert_eq!(response.status(), 409);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "PhiHistory");
+    assert!(app.mirrored_user(&user_id).await.is_some());
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
@@ -0,0 +1,57 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+#[tokio::test]
+async fn forgot_password_should_return_202_and_send_email() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    app.signup_and_login(&email).await;
+
+    let response = app
+        .post_forgot_password(serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status(), 202);
+
+    // Signup also sent a verification email to the same address
+    let messages = app.smtp.messages_to(&email);
+    assert_eq!(
+        messages
+            .iter()
+            .filter(|m| m.contains("Subject: Update your password"))
+            .count(),
+        1
+    );
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn forgot_password_should_return_202_for_unknown_email() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_forgot_password(serde_json::json!({ "email": generate_valid_email() }))
+        .await;
+    assert_eq!(response.status(), 202);
+
+    assert!(app.smtp.messages().is_empty());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn forgot_password_should_return_400_for_invalid_email() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_forgot_password(serde_json::json!({ "email": "not-an-email" }))
+        .await;
+    assert_eq!(response.status(), 400);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,52 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+#[tokio::test]
+async fn get_user_should_return_profile_and_role() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login_as(&email, "clinician").await;
+
+    let response = app.get_user(&user_id, &token).await;
+    assert_eq!(response.status(), 200);
+
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["user_id"], user_id.as_str());
+    assert_eq!(body["email"], email.as_str());
+    assert_eq!(body["first_name"], "Test");
+    assert_eq!(body["role"], "clinician");
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn get_user_should_return_404_for_unknown_user() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let admin_token = app.admin_token().await;
+    let response = app
+        .get_user(&uuid::Uuid::new_v4().to_string(), &admin_token)
+        .await;
+
+    assert_eq!(response.status(), 404);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn get_user_should_return_401_without_token() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let response = app.get_user(&uuid::Uuid::new_v4().to_string(), "").await;
+
+    assert_eq!(response.status(), 401);
+
+    app.cleanup().await;
+}
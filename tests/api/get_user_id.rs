@@ -8,22 +8,16 @@ async fn get_user_id_should_return_200() {
     let mut app = TestApp::new().await;
 
     let email = generate_valid_email();
+    app.signup(&email).await;
+    let admin_token = app.admin_token().await;
 
     let response = app
-        .post_signup(serde_json::json!({
-            "email": email,
-            "first_name": "Test",
-            "last_name": "User",
-            "password": "Password123!"
-        }))
-        .await;
-
-    assert_eq!(response.status(), 201);
-
-    let response = app
-        .post_get_user_id(serde_json::json!({
-            "email": email,
-        }))
+        .post_get_user_id(
+            serde_json::json!({
+                "email": email,
+            }),
+            &admin_token,
+        )
         .await;
 
     assert_eq!(response.status(), 200);
@@ -33,9 +27,63 @@ async fn get_user_id_should_return_200() {
         "Response body does not contain user_id"
     );
 
-    let response = app.post_delete_user(body).await;
+    let owner_token = app.owner_token().await;
+    let response = app.post_delete_user(body, &owner_token).await;
 
     assert_eq!(response.status(), 200);
 
     app.cleanup().await;
 }
+
+#[tokio::test]
+async fn get_user_id_should_return_401_without_valid_token() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let test_cases = vec![
+        // Missing token
+        "",
+        // Malformed token
+        "not-a-token",
+        // Unsigned token
+        "eyJhbGciOiJub25lIn0.eyJzdWIiOiJhZG1pbiJ9.",
+    ];
+
+    for (i, token) in test_cases.into_iter().enumerate() {
+        let response = app
+            .post_get_user_id(
+                serde_json::json!({
+                    "email": generate_valid_email(),
+                }),
+                token,
+            )
+            .await;
+        assert_eq!(response.status(), 401, "Test case {} failed", i);
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn get_user_id_should_return_403_without_ehr_role() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login(&email).await;
+
+    let response = app
+        .post_get_user_id(
+            serde_json::json!({
+                "email": email,
+            }),
+            &token,
+        )
+        .await;
+
+    assert_eq!(response.status(), 403);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
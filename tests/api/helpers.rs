@@ -1,26 +1,80 @@
-use lgr_ehr::{EHRApp, utils::config::AppSettings};
+use lgr_ehr::{
+    EHRApp,
+    domain::types::{
+        email::Email,
+        password::Password,
+        user::{User, UserUpdate},
+    },
+    services::{mfa, user_sync},
+    state::AppState,
+    utils::config::AppSettings,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use secrecy::ExposeSecret;
 use sqlx::{Executor, PgPool, postgres::PgPoolOptions};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::smtp::SmtpStandIn;
+
+// Code the authenticator app would show `steps` periods from now
+pub fn totp_code(secret: &str, steps: i64) -> String {
+    let totp = TOTP::new(
+        Algorithm::SHA1,
+        6,
+        0,
+        30,
+        Secret::Encoded(secret.to_string()).to_bytes().unwrap(),
+        None,
+        String::new(),
+    )
+    .unwrap();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    totp.generate((now + steps * 30) as u64)
+}
+
+// Cookies and CSRF token of a browser signed in with a session cookie
+pub struct WebSession {
+    pub cookie: String,
+    pub csrf_token: String,
+}
 
 pub struct TestApp {
     address: String,
     http_client: reqwest::Client,
-    _db_pool: PgPool,
+    state: AppState,
+    pub smtp: SmtpStandIn,
+    admin: Option<(String, String)>,
+    owner: Option<(String, String)>,
+    pub db_pool: PgPool,
     db_name: String,
     cleanup_called: bool,
 }
 
 impl TestApp {
     pub async fn new() -> Self {
+        Self::new_with(|_| {}).await
+    }
+
+    // Lets a test adjust the settings before the app is built
+    pub async fn new_with(configure: impl FnOnce(&mut AppSettings)) -> Self {
         // Create test database first
         let (db_pool, db_name) = create_test_database().await;
 
         // Create database URL for the test database
         let test_db_url = AppSettings::database_url_for(&db_name);
 
-        // Create settings with test database
-        let settings = AppSettings::for_tests(test_db_url);
+        // Create settings with test database, sending mail to a local stand-in
+        let (smtp, smtp_port) = SmtpStandIn::start().await;
+        let mut settings = AppSettings::for_tests(test_db_url);
+        settings.smtp_host = "127.0.0.1".into();
+        settings.smtp_port = smtp_port;
+        configure(&mut settings);
         let app = EHRApp::build(settings.clone()).await;
+        let state = app.state().clone();
 
         // Spawn the server
         tokio::spawn(async move { app.run().await.expect("Failed to start test server") });
@@ -29,13 +83,19 @@ impl TestApp {
         let address = format!("https://{}", settings.app_address());
         let http_client = reqwest::Client::builder()
             .danger_accept_invalid_certs(true)
+            // Redirects are asserted on, not followed
+            .redirect(reqwest::redirect::Policy::none())
             .build()
             .expect("Failed to build HTTP client");
 
         Self {
             address,
             http_client,
-            _db_pool: db_pool,
+            state,
+            smtp,
+            admin: None,
+            owner: None,
+            db_pool,
             db_name,
             cleanup_called: false,
         }
@@ -58,25 +118,726 @@ impl TestApp {
             .expect("Failed to execute request")
     }
 
-    pub async fn post_get_user_id(&self, body: serde_json::Value) -> reqwest::Response {
+    pub async fn post_signup_as(&self, body: serde_json::Value, token: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/auth/signup", &self.address))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_login(&self, body: serde_json::Value) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/auth/login", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_logout(&self, body: serde_json::Value) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/auth/logout", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_refresh(&self, body: serde_json::Value) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/auth/refresh", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_authorize(&self, return_to: Option<&str>) -> reqwest::Response {
+        let mut request = self
+            .http_client
+            .get(format!("{}/api/auth/authorize", &self.address));
+        if let Some(return_to) = return_to {
+            request = request.query(&[("return_to", return_to)]);
+        }
+        request.send().await.expect("Failed to execute request")
+    }
+
+    pub async fn get_callback(&self, query: &[(&str, &str)]) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/api/auth/callback", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_forgot_password(&self, body: serde_json::Value) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/auth/password/forgot", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_change_password(
+        &self,
+        body: serde_json::Value,
+        token: &str,
+    ) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/auth/password/change", &self.address))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_get_user_id(
+        &self,
+        body: serde_json::Value,
+        token: &str,
+    ) -> reqwest::Response {
         self.http_client
             .post(format!("{}/api/auth/get_user_id", &self.address))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_invitation(&self, body: serde_json::Value, token: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/invitations", &self.address))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_accept_invitation(&self, body: serde_json::Value) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/invitations/accept", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    // Token from the link in the latest invitation sent to the address
+    pub fn invitation_token(&self, email: &str) -> String {
+        let message = self
+            .smtp
+            .messages_to(email)
+            .pop()
+            .expect("No invitation was sent")
+            // Undo the quoted-printable encoding of the link
+            .replace("=\n", "")
+            .replace("=3D", "=");
+        let (_, rest) = message
+            .split_once("?token=")
+            .expect("Invitation has no link");
+        rest.chars().take_while(char::is_ascii_hexdigit).collect()
+    }
+
+    pub async fn post_practice(&self, body: serde_json::Value, token: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/practices", &self.address))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_practices(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/api/practices", &self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn put_user_practice(
+        &self,
+        user_id: &str,
+        practice_id: &str,
+        token: &str,
+    ) -> reqwest::Response {
+        self.http_client
+            .put(format!("{}/api/users/{}/practice", &self.address, user_id))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "practice_id": practice_id }))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    // Creates a practice as the given owner and returns its id
+    pub async fn create_practice(&self, name: &str, owner_token: &str) -> String {
+        let response = self
+            .post_practice(serde_json::json!({ "name": name }), owner_token)
+            .await;
+        assert_eq!(response.status(), 201);
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        body["practice_id"].as_str().unwrap().to_string()
+    }
+
+    pub async fn post_api_key(&self, body: serde_json::Value, token: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/api-keys", &self.address))
+            .bearer_auth(token)
             .json(&body)
             .send()
             .await
             .expect("Failed to execute request")
     }
 
-    pub async fn post_delete_user(&self, body: serde_json::Value) -> reqwest::Response {
+    pub async fn get_api_keys(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/api/api-keys", &self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn delete_api_key(&self, key_id: &str, token: &str) -> reqwest::Response {
+        self.http_client
+            .delete(format!("{}/api/api-keys/{}", &self.address, key_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_delete_user(
+        &self,
+        body: serde_json::Value,
+        token: &str,
+    ) -> reqwest::Response {
         self.http_client
             .post(format!("{}/api/auth/delete_user", &self.address))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_disable_user(
+        &self,
+        user_id: &str,
+        reason: &str,
+        token: &str,
+    ) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/users/{}/disable", &self.address, user_id))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "reason": reason }))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_enable_user(
+        &self,
+        user_id: &str,
+        reason: &str,
+        token: &str,
+    ) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/users/{}/enable", &self.address, user_id))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "reason": reason }))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_offboard_user(
+        &self,
+        user_id: &str,
+        body: serde_json::Value,
+        token: &str,
+    ) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/users/{}/offboard", &self.address, user_id))
+            .bearer_auth(token)
             .json(&body)
             .send()
             .await
             .expect("Failed to execute request")
     }
 
+    pub async fn get_offboarding(&self, user_id: &str, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/api/users/{}/offboard", &self.address, user_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_users(&self, query: &[(&str, &str)], token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/api/users", &self.address))
+            .query(query)
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_user(&self, user_id: &str, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/api/users/{}", &self.address, user_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn patch_user(
+        &self,
+        user_id: &str,
+        body: serde_json::Value,
+        token: &str,
+    ) -> reqwest::Response {
+        self.http_client
+            .patch(format!("{}/api/users/{}", &self.address, user_id))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_unlock_user(&self, user_id: &str, token: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/users/{}/unlock", &self.address, user_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_user_sessions(&self, user_id: &str, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/api/users/{}/sessions", &self.address, user_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn delete_user_session(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        token: &str,
+    ) -> reqwest::Response {
+        self.http_client
+            .delete(format!(
+                "{}/api/users/{}/sessions/{}",
+                &self.address, user_id, session_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn delete_user_sessions(&self, user_id: &str, token: &str) -> reqwest::Response {
+        self.http_client
+            .delete(format!("{}/api/users/{}/sessions", &self.address, user_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_enroll_totp(
+        &self,
+        body: serde_json::Value,
+        token: &str,
+    ) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/auth/mfa/totp", &self.address))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_confirm_totp(
+        &self,
+        factor_id: &str,
+        body: serde_json::Value,
+        token: &str,
+    ) -> reqwest::Response {
+        self.http_client
+            .post(format!(
+                "{}/api/auth/mfa/totp/{}/confirm",
+                &self.address, factor_id
+            ))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_mfa_factors(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/api/auth/mfa/factors", &self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn delete_mfa_factor(
+        &self,
+        factor_id: &str,
+        body: serde_json::Value,
+        token: &str,
+    ) -> reqwest::Response {
+        self.http_client
+            .delete(format!(
+                "{}/api/auth/mfa/factors/{}",
+                &self.address, factor_id
+            ))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_recovery_codes(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/auth/mfa/recovery-codes", &self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    // Signs up and logs in a fresh user, returning its id and access token
+    // Signs up a verified user who has never signed in, returning its id
+    pub async fn signup(&self, email: &str) -> String {
+        let response = self
+            .post_signup(serde_json::json!({
+                "email": email,
+                "first_name": "Test",
+                "last_name": "User",
+                "password": "Password123!"
+            }))
+            .await;
+        assert_eq!(response.status(), 201);
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        let user_id = body["user_id"].as_str().unwrap().to_string();
+        self.verify_email(&user_id).await;
+        user_id
+    }
+
+    pub async fn signup_and_login(&self, email: &str) -> (String, String) {
+        self.signup(email).await;
+
+        let body = self.login_body(email, "Password123!").await;
+        (
+            body["user"]["user_id"].as_str().unwrap().to_string(),
+            body["access_token"].as_str().unwrap().to_string(),
+        )
+    }
+
+    // Access token of an admin created on first use and removed again by cleanup()
+    pub async fn admin_token(&mut self) -> String {
+        if self.admin.is_none() {
+            let email = generate_valid_email();
+            let admin = if self.state.settings.registration.open_signup {
+                self.signup_and_login_as(&email, "admin").await
+            } else {
+                self.create_user_as(&email, "admin").await
+            };
+            self.admin = Some(admin);
+        }
+        self.admin.as_ref().unwrap().1.clone()
+    }
+
+    // Access token of an owner, the only role allowed to delete users, likewise
+    // created on first use and removed by cleanup()
+    pub async fn owner_token(&mut self) -> String {
+        if self.owner.is_none() {
+            let email = generate_valid_email();
+            let owner = if self.state.settings.registration.open_signup {
+                self.signup_and_login_as(&email, "owner").await
+            } else {
+                self.create_user_as(&email, "owner").await
+            };
+            self.owner = Some(owner);
+        }
+        self.owner.as_ref().unwrap().1.clone()
+    }
+
+    // Signs up a user holding the given realm role, returning its id and access token
+    pub async fn signup_and_login_as(&self, email: &str, role: &str) -> (String, String) {
+        let (user_id, _) = self.signup_and_login(email).await;
+        self.grant_realm_role(&user_id, role).await;
+        (user_id, self.enroll_and_login(email).await)
+    }
+
+    // Creates a verified user without going through signup, for when signup is closed
+    pub async fn create_user_as(&self, email: &str, role: &str) -> (String, String) {
+        let mut user = User::new(
+            email.to_string(),
+            Email::new(email.to_string()).unwrap(),
+            Password::new("Password123!".to_string()).unwrap(),
+            "Test".to_string(),
+            "User".to_string(),
+            Some(role.parse().expect("Unknown realm role")),
+        );
+        user.email_verified = true;
+        let user_id = user_sync::create_user(&self.state, user)
+            .await
+            .expect("Failed to create user");
+        (user_id, self.enroll_and_login(email).await)
+    }
+
+    // Roles that require MFA only get an enrollment token until an authenticator is
+    // enrolled; enrolls one with it and logs in again with a code
+    async fn enroll_and_login(&self, email: &str) -> String {
+        let body = self.login_body(email, "Password123!").await;
+        let Some(enrollment_token) = body["enrollment_token"].as_str() else {
+            return body["access_token"].as_str().unwrap().to_string();
+        };
+
+        let response = self
+            .post_enroll_totp(serde_json::json!({}), enrollment_token)
+            .await;
+        assert_eq!(response.status(), 201);
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        let secret = body["secret"].as_str().unwrap();
+        let response = self
+            .post_confirm_totp(
+                body["factor_id"].as_str().unwrap(),
+                serde_json::json!({ "code": totp_code(secret, 0) }),
+                enrollment_token,
+            )
+            .await;
+        assert_eq!(response.status(), 200);
+
+        // The confirming code can't be replayed, so log in with the next one
+        let response = self
+            .post_login(serde_json::json!({
+                "email": email,
+                "password": "Password123!",
+                "mfa_code": totp_code(secret, 1)
+            }))
+            .await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        body["access_token"].as_str().unwrap().to_string()
+    }
+
+    // Grants a realm role through user_sync, bypassing the API's own checks
+    pub async fn grant_realm_role(&self, user_id: &str, role: &str) {
+        let current = self
+            .state
+            .auth_provider
+            .read()
+            .await
+            .get_user(user_id.to_string())
+            .await
+            .expect("Failed to get user");
+        let update = UserUpdate {
+            user_id: Some(user_id.to_string()),
+            role: Some(Some(role.parse().expect("Unknown realm role"))),
+            ..Default::default()
+        };
+        user_sync::update_user(&self.state, &current, update)
+            .await
+            .expect("Failed to grant realm role");
+    }
+
+    // Marks the user's email as verified, as following the emailed link would
+    pub async fn verify_email(&self, user_id: &str) {
+        self.state
+            .auth_provider
+            .read()
+            .await
+            .update_user(UserUpdate {
+                user_id: Some(user_id.to_string()),
+                email_verified: Some(true),
+                ..Default::default()
+            })
+            .await
+            .expect("Failed to verify email");
+    }
+
+    pub async fn post_resend_verification(&self, body: serde_json::Value) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/auth/verify-email/resend", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    // Logs in an existing user with a session cookie instead of tokens
+    pub async fn login_session(&self, email: &str, password: &str) -> WebSession {
+        let response = self
+            .post_login(serde_json::json!({
+                "email": email,
+                "password": password,
+                "session": true
+            }))
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let cookie = response
+            .headers()
+            .get_all("set-cookie")
+            .iter()
+            .map(|value| {
+                value
+                    .to_str()
+                    .unwrap()
+                    .split(';')
+                    .next()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        WebSession {
+            cookie,
+            csrf_token: body["csrf_token"].as_str().unwrap().to_string(),
+        }
+    }
+
+    pub async fn get_user_in_session(
+        &self,
+        user_id: &str,
+        session: &WebSession,
+    ) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/api/users/{}", &self.address, user_id))
+            .header("cookie", &session.cookie)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn patch_user_in_session(
+        &self,
+        user_id: &str,
+        body: serde_json::Value,
+        session: &WebSession,
+        csrf_token: Option<&str>,
+    ) -> reqwest::Response {
+        let mut request = self
+            .http_client
+            .patch(format!("{}/api/users/{}", &self.address, user_id))
+            .header("cookie", &session.cookie)
+            .json(&body);
+        if let Some(csrf_token) = csrf_token {
+            request = request.header("x-csrf-token", csrf_token);
+        }
+        request.send().await.expect("Failed to execute request")
+    }
+
+    pub async fn post_verify_mfa(
+        &self,
+        body: serde_json::Value,
+        session: &WebSession,
+    ) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/api/auth/mfa/verify", &self.address))
+            .header("cookie", &session.cookie)
+            .header("x-csrf-token", &session.csrf_token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn delete_session(&self, session: &WebSession) -> reqwest::Response {
+        self.http_client
+            .delete(format!("{}/api/auth/session", &self.address))
+            .header("cookie", &session.cookie)
+            .header("x-csrf-token", &session.csrf_token)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    // Logs in an existing user and returns the access token
+    pub async fn login(&self, email: &str, password: &str) -> String {
+        let body = self.login_body(email, password).await;
+        body["access_token"].as_str().unwrap().to_string()
+    }
+
+    pub async fn login_body(&self, email: &str, password: &str) -> serde_json::Value {
+        let response = self
+            .post_login(serde_json::json!({
+                "email": email,
+                "password": password
+            }))
+            .await;
+        assert_eq!(response.status(), 200);
+
+        response.json().await.expect("Failed to parse JSON")
+    }
+
+    // Email and role of the user's row in the local users table, if there is one
+    pub async fn mirrored_user(&self, user_id: &str) -> Option<(String, Option<String>)> {
+        sqlx::query_as("SELECT email, role FROM users WHERE id = $1::uuid")
+            .bind(user_id)
+            .fetch_optional(&self.db_pool)
+            .await
+            .expect("Failed to query users table")
+    }
+
+    // Encrypts TOTP secrets inserted directly in plaintext, as the server does at startup
+    pub async fn seal_mfa_secrets(&self) {
+        mfa::seal_plaintext_secrets(&self.state)
+            .await
+            .expect("Failed to encrypt TOTP secrets");
+    }
+
+    // Deletes a user at the provider, bypassing the API, which refuses to delete anyone
+    // who has signed in
+    pub async fn remove_user(&self, user_id: &str) {
+        let _ = self
+            .state
+            .auth_provider
+            .read()
+            .await
+            .delete_user(user_id.to_string())
+            .await;
+    }
+
     pub async fn cleanup(&mut self) {
+        for (user_id, _) in [self.admin.take(), self.owner.take()].into_iter().flatten() {
+            let _ = self
+                .state
+                .auth_provider
+                .read()
+                .await
+                .delete_user(user_id)
+                .await;
+        }
         if !self.cleanup_called {
             cleanup_test_database(&self.db_name).await;
             self.cleanup_called = true;
@@ -105,7 +866,7 @@ async fn create_test_database() -> (PgPool, String) {
 
     // Connect to postgres admin database
     let admin_connection = PgPoolOptions::new()
-        .connect(&admin_db_url.expose_secret())
+        .connect(admin_db_url.expose_secret())
         .await
         .expect("Failed to connect to PostgreSQL admin database");
 
@@ -119,7 +880,7 @@ async fn create_test_database() -> (PgPool, String) {
     let test_db_url = AppSettings::database_url_for(&db_name);
 
     let test_connection = PgPoolOptions::new()
-        .connect(&test_db_url.expose_secret())
+        .connect(test_db_url.expose_secret())
         .await
         .expect("Failed to connect to test database");
 
@@ -136,7 +897,7 @@ async fn cleanup_test_database(db_name: &str) {
     let admin_db_url = AppSettings::admin_database_url();
 
     if let Ok(admin_connection) = PgPoolOptions::new()
-        .connect(&admin_db_url.expose_secret())
+        .connect(admin_db_url.expose_secret())
         .await
     {
         // Terminate connections to test database
@@ -0,0 +1,203 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+// Invitations on and open signup off, as INVITATIONS_ENABLED configures it
+async fn invitation_app() -> TestApp {
+    TestApp::new_with(|settings| {
+        settings.registration.invitations_enabled = true;
+        settings.registration.open_signup = false;
+    })
+    .await
+}
+
+fn acceptance(token: &str) -> serde_json::Value {
+    serde_json::json!({
+        "token": token,
+        "password": "Password123!",
+        "first_name": "Invited",
+        "last_name": "Clinician"
+    })
+}
+
+#[tokio::test]
+async fn invited_staff_should_sign_up_with_the_assigned_role() {
+    init_tracing_for_tests();
+    let mut app = invitation_app().await;
+    let admin_token = app.admin_token().await;
+
+    let email = generate_valid_email();
+    let response = app
+        .post_invitation(
+            serde_json::json!({ "email": email, "role": "clinician" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 201);
+
+    let token = app.invitation_token(&email);
+    assert_eq!(token.len(), 64);
+    // Only the hash is kept
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM invitations WHERE token_hash = $1")
+        .bind(&token)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to query invitations");
+    assert_eq!(stored, 0);
+
+    let response = app.post_accept_invitation(acceptance(&token)).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let user_id = body["user_id"].as_str().unwrap().to_string();
+
+    // The link proved the address, so the account can log in straight away
+    let body = app.login_body(&email, "Password123!").await;
+    assert_eq!(body["user"]["role"], "clinician");
+    assert_eq!(
+        app.mirrored_user(&user_id).await,
+        Some((email, Some("clinician".to_string())))
+    );
+
+    // Single use
+    let response = app.post_accept_invitation(acceptance(&token)).await;
+    assert_eq!(response.status(), 400);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn accept_invitation_should_reject_expired_and_unknown_tokens() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new_with(|settings| {
+        settings.registration.invitations_enabled = true;
+        settings.registration.invitation_ttl_secs = 0;
+    })
+    .await;
+    let admin_token = app.admin_token().await;
+
+    let email = generate_valid_email();
+    let response = app
+        .post_invitation(
+            serde_json::json!({ "email": email, "role": "biller" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 201);
+
+    for token in [app.invitation_token(&email), "0".repeat(64)] {
+        let response = app.post_accept_invitation(acceptance(&token)).await;
+        assert_eq!(response.status(), 400);
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["code"], "InvalidInput");
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn accept_invitation_should_keep_the_token_when_the_password_is_rejected() {
+    init_tracing_for_tests();
+    let mut app = invitation_app().await;
+    let admin_token = app.admin_token().await;
+
+    let email = generate_valid_email();
+    app.post_invitation(
+        serde_json::json!({ "email": email, "role": "clinician" }),
+        &admin_token,
+    )
+    .await;
+    let token = app.invitation_token(&email);
+
+    let mut body = acceptance(&token);
+    body["password"] = "weak".into();
+    let response = app.post_accept_invitation(body).await;
+    assert_eq!(response.status(), 400);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "InvalidPassword");
+
+    let response = app.post_accept_invitation(acceptance(&token)).await;
+    assert_eq!(response.status(), 201);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn create_invitation_should_require_permission_to_assign_the_role() {
+    init_tracing_for_tests();
+    let mut app = invitation_app().await;
+    let admin_token = app.admin_token().await;
+    let (_, clinician_token) = app
+        .create_user_as(&generate_valid_email(), "clinician")
+        .await;
+
+    let test_cases = [
+        (clinician_token.as_str(), "clinician", "MissingPermission"),
+        // Only an owner may hand out the owner role
+        (admin_token.as_str(), "owner", "CannotAssignRole"),
+    ];
+
+    for (token, role, code) in test_cases {
+        let response = app
+            .post_invitation(
+                serde_json::json!({ "email": generate_valid_email(), "role": role }),
+                token,
+            )
+            .await;
+        assert_eq!(response.status(), 403, "Failed for role: {role}");
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["code"], code);
+    }
+
+    // Existing accounts cannot be invited again
+    let email = generate_valid_email();
+    app.create_user_as(&email, "clinician").await;
+    let response = app
+        .post_invitation(
+            serde_json::json!({ "email": email, "role": "clinician" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 409);
+
+    // An account whose address merely contains the invited one is someone else
+    let email = generate_valid_email();
+    app.create_user_as(&format!("jo{email}"), "clinician").await;
+    let response = app
+        .post_invitation(
+            serde_json::json!({ "email": email, "role": "clinician" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 201);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn signup_should_be_closed_while_invitations_are_enabled() {
+    init_tracing_for_tests();
+    let mut app = invitation_app().await;
+    let admin_token = app.admin_token().await;
+
+    let signup = |email: String| {
+        serde_json::json!({
+            "email": email,
+            "first_name": "Test",
+            "last_name": "User",
+            "password": "Password123!"
+        })
+    };
+
+    let response = app.post_signup(signup(generate_valid_email())).await;
+    assert_eq!(response.status(), 403);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "SignupDisabled");
+
+    // Administrators can still create accounts directly
+    let response = app
+        .post_signup_as(signup(generate_valid_email()), &admin_token)
+        .await;
+    assert_eq!(response.status(), 201);
+
+    app.cleanup().await;
+}
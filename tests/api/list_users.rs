@@ -0,0 +1,142 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+async fn signup_named(app: &TestApp, first_name: &str, last_name: &str) -> String {
+    let response = app
+        .post_signup(serde_json::json!({
+            "email": generate_valid_email(),
+            "first_name": first_name,
+            "last_name": last_name,
+            "password": "Password123!"
+        }))
+        .await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    body["user_id"].as_str().unwrap().to_string()
+}
+
+async fn list(app: &TestApp, query: &[(&str, &str)], token: &str) -> serde_json::Value {
+    let response = app.get_users(query, token).await;
+    assert_eq!(response.status(), 200);
+    response.json().await.expect("Failed to parse JSON")
+}
+
+fn last_names(body: &serde_json::Value) -> Vec<&str> {
+    body["users"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|user| user["last_name"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn list_users_should_page_search_and_sort() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    signup_named(&app, "Ada", "Lovelace").await;
+    signup_named(&app, "Grace", "Hopper").await;
+    signup_named(&app, "Alan", "Turing").await;
+    let admin_token = app.admin_token().await;
+
+    let body = list(&app, &[], &admin_token).await;
+    assert_eq!(last_names(&body), ["Hopper", "Lovelace", "Turing", "User"]);
+    assert_eq!(body["total"], 4);
+
+    let body = list(
+        &app,
+        &[("sort", "-name"), ("per_page", "2"), ("page", "2")],
+        &admin_token,
+    )
+    .await;
+    assert_eq!(last_names(&body), ["Lovelace", "Hopper"]);
+    assert_eq!(body["total"], 4);
+
+    let body = list(&app, &[("search", "GRACE hop")], &admin_token).await;
+    assert_eq!(last_names(&body), ["Hopper"]);
+    let body = list(&app, &[("search", "@example.com")], &admin_token).await;
+    assert_eq!(body["total"], 4);
+    // Wildcards are matched literally
+    let body = list(&app, &[("search", "%")], &admin_token).await;
+    assert_eq!(body["total"], 0);
+
+    let body = list(&app, &[("page", "9")], &admin_token).await;
+    assert_eq!(last_names(&body), Vec::<&str>::new());
+    assert_eq!(body["total"], 4);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn list_users_should_filter_by_role_and_enabled() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let disabled_id = signup_named(&app, "Ada", "Lovelace").await;
+    signup_named(&app, "Grace", "Hopper").await;
+    let admin_token = app.admin_token().await;
+    sqlx::query("UPDATE users SET enabled = FALSE WHERE id = $1::uuid")
+        .bind(&disabled_id)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let body = list(&app, &[("role", "admin")], &admin_token).await;
+    assert_eq!(body["total"], 1);
+    let admin = &body["users"][0];
+    assert_eq!(admin["role"], "admin");
+    assert_eq!(admin["enabled"], true);
+    assert!(admin["created_at"].is_i64());
+    assert!(admin["last_login_at"].is_i64());
+    assert!(admin["email"].as_str().unwrap().ends_with("@example.com"));
+
+    let body = list(&app, &[("enabled", "false")], &admin_token).await;
+    assert_eq!(last_names(&body), ["Lovelace"]);
+    assert!(body["users"][0]["last_login_at"].is_null());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn list_users_should_reject_bad_queries() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let admin_token = app.admin_token().await;
+
+    for query in [
+        [("sort", "password")],
+        [("role", "janitor")],
+        [("per_page", "101")],
+        [("page", "0")],
+    ] {
+        let response = app.get_users(&query, &admin_token).await;
+        assert_eq!(response.status(), 400, "{query:?}");
+    }
+    assert_eq!(app.get_users(&[], "").await.status(), 401);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn list_users_should_only_show_the_callers_practice() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    signup_named(&app, "Ada", "Lovelace").await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let (admin_id, admin_token) = app
+        .signup_and_login_as(&generate_valid_email(), "admin")
+        .await;
+    let practice_id = app.create_practice("North Clinic", &owner_token).await;
+    let response = app
+        .put_user_practice(&admin_id, &practice_id, &owner_token)
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let body = list(&app, &[], &admin_token).await;
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["users"][0]["user_id"], admin_id.as_str());
+
+    app.cleanup().await;
+}
@@ -0,0 +1,122 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+fn login_body(email: &str, password: &str) -> serde_json::Value {
+    serde_json::json!({ "email": email, "password": password })
+}
+
+#[tokio::test]
+async fn login_should_lock_account_after_repeated_failures() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+
+    for i in 0..4 {
+        let response = app.post_login(login_body(&email, "WrongPassword1!")).await;
+        assert_eq!(response.status(), 401, "Attempt {} failed", i);
+    }
+    let response = app.post_login(login_body(&email, "WrongPassword1!")).await;
+    assert_eq!(response.status(), 423);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "AccountLocked");
+
+    // Even the right password is refused while locked
+    let response = app.post_login(login_body(&email, "Password123!")).await;
+    assert_eq!(response.status(), 423);
+
+    let audited: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM audit_logs WHERE user_id = $1::uuid AND action = 'login_lockout'",
+    )
+    .bind(&user_id)
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to query audit_logs");
+    assert_eq!(audited, 1);
+
+    let token = app.admin_token().await;
+    let response = app.post_unlock_user(&user_id, &token).await;
+    assert_eq!(response.status(), 200);
+
+    let response = app.post_login(login_body(&email, "Password123!")).await;
+    assert_eq!(response.status(), 200);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn login_success_should_reset_failures() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+
+    for _ in 0..2 {
+        for _ in 0..4 {
+            let response = app.post_login(login_body(&email, "WrongPassword1!")).await;
+            assert_eq!(response.status(), 401);
+        }
+        let response = app.post_login(login_body(&email, "Password123!")).await;
+        assert_eq!(response.status(), 200);
+    }
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn login_should_throttle_source_address_across_accounts() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    // Everything needing a login happens before the address is throttled
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+
+    for _ in 0..19 {
+        let response = app
+            .post_login(login_body(&generate_valid_email(), "Password123!"))
+            .await;
+        assert_eq!(response.status(), 401);
+    }
+    let response = app
+        .post_login(login_body(&generate_valid_email(), "Password123!"))
+        .await;
+    assert_eq!(response.status(), 429);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "TooManyAttempts");
+
+    let response = app.post_login(login_body(&email, "Password123!")).await;
+    assert_eq!(response.status(), 429);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn unlock_user_should_require_manage_users() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let (user_id, token) = app.signup_and_login(&generate_valid_email()).await;
+
+    let response = app.post_unlock_user(&user_id, &token).await;
+    assert_eq!(response.status(), 403);
+
+    let admin_token = app.admin_token().await;
+    let response = app
+        .post_unlock_user(&uuid::Uuid::new_v4().to_string(), &admin_token)
+        .await;
+    assert_eq!(response.status(), 404);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
@@ -0,0 +1,107 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+#[tokio::test]
+async fn login_should_return_400_for_malformed_request() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let test_cases = vec![
+        // Missing email
+        serde_json::json!({
+            "password": "Password123!"
+        }),
+        // Missing password
+        serde_json::json!({
+            "email": generate_valid_email(),
+        }),
+        // Invalid email
+        serde_json::json!({
+            "email": "not-an-email",
+            "password": "Password123!"
+        }),
+    ];
+
+    for (i, test_case) in test_cases.into_iter().enumerate() {
+        let response = app.post_login(test_case).await;
+        assert_eq!(response.status(), 400, "Test case {} failed", i);
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn login_should_return_200_with_tokens() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+
+    let response = app
+        .post_signup(serde_json::json!({
+            "email": email,
+            "first_name": "Test",
+            "last_name": "User",
+            "password": "Password123!"
+        }))
+        .await;
+
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    app.verify_email(body["user_id"].as_str().unwrap()).await;
+
+    let response = app
+        .post_login(serde_json::json!({
+            "email": email,
+            "password": "Password123!"
+        }))
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    for field in ["access_token", "refresh_token", "id_token"] {
+        assert!(
+            body.get(field).and_then(|t| t.as_str()).is_some(),
+            "Response body does not contain {field}"
+        );
+    }
+    let user_id = body["user"]["user_id"]
+        .as_str()
+        .expect("Response body does not contain user_id");
+
+    app.remove_user(user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn login_should_return_401_for_invalid_credentials() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+
+    let test_cases = vec![
+        // Wrong password
+        serde_json::json!({
+            "email": email,
+            "password": "WrongPassword123!"
+        }),
+        // Unknown user
+        serde_json::json!({
+            "email": generate_valid_email(),
+            "password": "Password123!"
+        }),
+    ];
+
+    for (i, test_case) in test_cases.into_iter().enumerate() {
+        let response = app.post_login(test_case).await;
+        assert_eq!(response.status(), 401, "Test case {} failed", i);
+    }
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
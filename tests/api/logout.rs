@@ -0,0 +1,70 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+#[tokio::test]
+async fn logout_should_return_400_for_malformed_request() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let test_cases = vec![
+        // Missing refresh token
+        serde_json::json!({}),
+        // Empty refresh token
+        serde_json::json!({
+            "refresh_token": "  "
+        }),
+    ];
+
+    for (i, test_case) in test_cases.into_iter().enumerate() {
+        let response = app.post_logout(test_case).await;
+        assert_eq!(response.status(), 400, "Test case {} failed", i);
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn logout_should_be_idempotent() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+
+    let response = app
+        .post_signup(serde_json::json!({
+            "email": email,
+            "first_name": "Test",
+            "last_name": "User",
+            "password": "Password123!"
+        }))
+        .await;
+
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    app.verify_email(body["user_id"].as_str().unwrap()).await;
+
+    let response = app
+        .post_login(serde_json::json!({
+            "email": email,
+            "password": "Password123!"
+        }))
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let refresh_token = body["refresh_token"].clone();
+    let user_id = body["user"]["user_id"].clone();
+
+    // Logging out twice with the same token should succeed both times
+    for _ in 0..2 {
+        let response = app
+            .post_logout(serde_json::json!({ "refresh_token": refresh_token }))
+            .await;
+        assert_eq!(response.status(), 200);
+    }
+
+    app.remove_user(user_id.as_str().unwrap()).await;
+
+    app.cleanup().await;
+}
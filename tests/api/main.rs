@@ -1,4 +1,27 @@
+mod account_status;
+mod api_keys;
+mod authorization_code;
+mod change_password;
+mod delete_user;
+mod forgot_password;
+mod get_user;
 mod get_user_id;
 mod health;
 mod helpers;
+mod invitations;
+mod list_users;
+mod lockout;
+mod login;
+mod logout;
+mod mfa;
+mod offboarding;
+mod password_policy;
+mod practices;
+mod refresh;
 mod signup;
+mod smtp;
+mod token_endpoint;
+mod update_user;
+mod user_sessions;
+mod verify_email;
+mod web_sessions;
@@ -0,0 +1,284 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email, totp_code};
+
+fn login_body(email: &str, mfa_code: Option<&str>) -> serde_json::Value {
+    serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "mfa_code": mfa_code
+    })
+}
+
+// Enrolls and confirms an authenticator, returning the factor id and its secret
+async fn enroll(app: &TestApp, token: &str) -> (String, String) {
+    let response = app
+        .post_enroll_totp(serde_json::json!({ "label": "Work phone" }), token)
+        .await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let factor_id = body["factor_id"].as_str().unwrap().to_string();
+    let secret = body["secret"].as_str().unwrap().to_string();
+    assert!(
+        body["provisioning_uri"]
+            .as_str()
+            .unwrap()
+            .starts_with("otpauth://totp/")
+    );
+
+    let response = app
+        .post_confirm_totp(
+            &factor_id,
+            serde_json::json!({ "code": totp_code(&secret, 0) }),
+            token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    (factor_id, secret)
+}
+
+#[tokio::test]
+async fn mfa_endpoints_should_return_401_without_token() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let response = app.post_enroll_totp(serde_json::json!({}), "").await;
+    assert_eq!(response.status(), 401);
+    let response = app.get_mfa_factors("").await;
+    assert_eq!(response.status(), 401);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn confirm_totp_should_reject_wrong_code() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let (user_id, token) = app.signup_and_login(&generate_valid_email()).await;
+    let response = app.post_enroll_totp(serde_json::json!({}), &token).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let factor_id = body["factor_id"].as_str().unwrap();
+    let secret = body["secret"].as_str().unwrap();
+
+    // Only the encrypted secret is stored
+    let (stored, nonce): (String, Option<Vec<u8>>) =
+        sqlx::query_as("SELECT secret, secret_nonce FROM mfa_factors WHERE id = $1::uuid")
+            .bind(factor_id)
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert_ne!(stored, secret);
+    assert!(nonce.is_some());
+
+    let response = app
+        .post_confirm_totp(
+            factor_id,
+            serde_json::json!({ "code": totp_code(secret, -5) }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 401);
+    let error: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(error["code"], "InvalidMfaCode");
+
+    // An unconfirmed factor is listed but does not gate login
+    let response = app.get_mfa_factors(&token).await;
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["factors"][0]["confirmed"], false);
+    assert_eq!(body["factors"][0]["label"], "Authenticator app");
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn login_should_require_code_once_totp_is_enrolled() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login(&email).await;
+    let (_, secret) = enroll(&app, &token).await;
+
+    let response = app.post_login(login_body(&email, None)).await;
+    assert_eq!(response.status(), 401);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "MfaRequired");
+
+    // The code used to confirm enrollment can't be replayed
+    let response = app
+        .post_login(login_body(&email, Some(&totp_code(&secret, 0))))
+        .await;
+    assert_eq!(response.status(), 401);
+
+    let response = app
+        .post_login(login_body(&email, Some(&totp_code(&secret, 1))))
+        .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["mfa_enrollment_required"], false);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn recovery_codes_should_work_once_each() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login(&email).await;
+
+    let response = app.post_recovery_codes(&token).await;
+    assert_eq!(response.status(), 409);
+
+    enroll(&app, &token).await;
+    let response = app.post_recovery_codes(&token).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let codes = body["recovery_codes"].as_array().unwrap();
+    assert_eq!(codes.len(), 10);
+    let code = codes[0].as_str().unwrap();
+
+    let response = app.post_login(login_body(&email, Some(code))).await;
+    assert_eq!(response.status(), 200);
+
+    let response = app.post_login(login_body(&email, Some(code))).await;
+    assert_eq!(response.status(), 401);
+
+    let audited: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM audit_logs WHERE user_id = $1::uuid AND action LIKE 'mfa_%'",
+    )
+    .bind(&user_id)
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to query audit_logs");
+    assert_eq!(audited, 3);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn removing_factor_should_stop_requiring_code() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login(&email).await;
+    let (factor_id, secret) = enroll(&app, &token).await;
+
+    let response = app
+        .delete_mfa_factor(
+            &factor_id,
+            serde_json::json!({ "code": totp_code(&secret, 1) }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let response = app
+        .delete_mfa_factor(&factor_id, serde_json::json!({}), &token)
+        .await;
+    assert_eq!(response.status(), 404);
+
+    let response = app.post_login(login_body(&email, None)).await;
+    assert_eq!(response.status(), 200);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn removing_confirmed_factor_should_require_code() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login(&email).await;
+    let (factor_id, secret) = enroll(&app, &token).await;
+
+    let response = app
+        .delete_mfa_factor(&factor_id, serde_json::json!({}), &token)
+        .await;
+    assert_eq!(response.status(), 401);
+    let error: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(error["code"], "MfaRequired");
+
+    // The code that confirmed the factor has been spent
+    let response = app
+        .delete_mfa_factor(
+            &factor_id,
+            serde_json::json!({ "code": totp_code(&secret, 0) }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 401);
+    let error: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(error["code"], "InvalidMfaCode");
+
+    let response = app.post_login(login_body(&email, None)).await;
+    assert_eq!(response.status(), 401);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn login_should_only_allow_enrollment_for_owners_without_mfa() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+    app.grant_realm_role(&user_id, "owner").await;
+    let body = app.login_body(&email, "Password123!").await;
+    assert_eq!(body["mfa_enrollment_required"], true);
+    assert!(body.get("access_token").is_none());
+    let enrollment_token = body["enrollment_token"].as_str().unwrap();
+
+    // Neither guarded routes nor the caller's own account are reachable with it
+    let response = app.get_users(&[], enrollment_token).await;
+    assert_eq!(response.status(), 403);
+    let error: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(error["code"], "MfaEnrollmentRequired");
+    let response = app.get_user(&user_id, enrollment_token).await;
+    assert_eq!(response.status(), 403);
+    let response = app.get_mfa_factors(enrollment_token).await;
+    assert_eq!(response.status(), 403);
+
+    let (_, secret) = enroll(&app, enrollment_token).await;
+    let response = app
+        .post_login(login_body(&email, Some(&totp_code(&secret, 1))))
+        .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["mfa_enrollment_required"], false);
+    let token = body["access_token"].as_str().unwrap();
+    assert_eq!(app.get_users(&[], token).await.status(), 200);
+
+    // Enrolling spends the enrollment token
+    let response = app
+        .post_enroll_totp(serde_json::json!({}), enrollment_token)
+        .await;
+    assert_eq!(response.status(), 401);
+
+    let clinician = generate_valid_email();
+    let (clinician_id, _) = app.signup_and_login_as(&clinician, "clinician").await;
+    let body = app.login_body(&clinician, "Password123!").await;
+    assert_eq!(body["mfa_enrollment_required"], false);
+
+    for id in [user_id, clinician_id] {
+        app.remove_user(&id).await;
+    }
+
+    app.cleanup().await;
+}
@@ -0,0 +1,214 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+fn step_statuses(job: &serde_json::Value) -> Vec<(String, String)> {
+    job["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| {
+            (
+                step["step"].as_str().unwrap().to_string(),
+                step["status"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+async fn offboarded_reasons(app: &TestApp) -> Vec<(String, Option<String>)> {
+    sqlx::query_as(
+        "SELECT action, reason FROM audit_logs WHERE action LIKE 'user_offboard%' ORDER BY id",
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn offboard_user_should_run_every_step_and_audit() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    // Leaving owner with an API key of their own, offboarded by another owner
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login_as(&email, "owner").await;
+    let response = app
+        .post_api_key(
+            serde_json::json!({ "name": "Billing export", "scopes": ["users:read"] }),
+            &token,
+        )
+        .await;
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let key = body["key"].as_str().unwrap().to_string();
+    let (successor_id, _) = app.signup_and_login(&generate_valid_email()).await;
+    let owner_token = app.owner_token().await;
+
+    let response = app
+        .post_offboard_user(
+            &user_id,
+            serde_json::json!({ "reason": "Left the practice", "reassign_to": successor_id }),
+            &owner_token,
+        )
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let job: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    // The handover steps have no module to run them, so the job is not complete
+    assert_eq!(job["status"], "needs_attention");
+    assert!(job["completed_at"].is_number());
+    assert_eq!(job["reassign_to"], successor_id.as_str());
+    let expected = [
+        ("disable_account", "completed"),
+        ("revoke_sessions", "completed"),
+        ("revoke_api_keys", "completed"),
+        ("reassign_tasks", "skipped"),
+        ("reassign_appointments", "skipped"),
+        ("transfer_patients", "skipped"),
+    ]
+    .map(|(step, status)| (step.to_string(), status.to_string()));
+    assert_eq!(step_statuses(&job), expected);
+
+    assert_eq!(app.get_user(&user_id, &token).await.status(), 401);
+    assert_eq!(app.get_user(&user_id, &key).await.status(), 401);
+    let response = app
+        .post_login(serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status(), 403);
+    assert_eq!(
+        offboarded_reasons(&app).await,
+        vec![(
+            "user_offboarding_needs_attention".to_string(),
+            Some("Left the practice".to_string())
+        )]
+    );
+
+    let response = app.get_offboarding(&user_id, &owner_token).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["job_id"], job["job_id"]);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn offboard_user_should_resume_from_failed_step() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (user_id, _) = app.signup_and_login(&generate_valid_email()).await;
+    let admin_token = app.admin_token().await;
+
+    // Revoking API keys fails while their table is unavailable
+    sqlx::query("ALTER TABLE api_keys RENAME TO api_keys_unavailable")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    let response = app
+        .post_offboard_user(
+            &user_id,
+            serde_json::json!({ "reason": "Left the practice" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let job: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(job["status"], "failed");
+    let statuses: Vec<String> = step_statuses(&job)
+        .into_iter()
+        .map(|(_, status)| status)
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            "completed",
+            "completed",
+            "failed",
+            "pending",
+            "pending",
+            "pending"
+        ]
+    );
+    assert!(offboarded_reasons(&app).await.is_empty());
+
+    sqlx::query("ALTER TABLE api_keys_unavailable RENAME TO api_keys")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    let response = app
+        .post_offboard_user(
+            &user_id,
+            serde_json::json!({ "reason": "Retrying" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let resumed: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(resumed["job_id"], job["job_id"]);
+    assert_eq!(resumed["status"], "needs_attention");
+    let attempts: Vec<i64> = resumed["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| step["attempts"].as_i64().unwrap())
+        .collect();
+    assert_eq!(attempts, [1, 1, 2, 1, 1, 1]);
+    assert_eq!(
+        offboarded_reasons(&app).await,
+        vec![(
+            "user_offboarding_needs_attention".to_string(),
+            Some("Left the practice".to_string())
+        )]
+    );
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn offboard_user_should_reject_invalid_requests() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (user_id, _) = app.signup_and_login(&generate_valid_email()).await;
+    let (admin_id, admin_token) = app
+        .signup_and_login_as(&generate_valid_email(), "admin")
+        .await;
+    let (_, clinician_token) = app
+        .signup_and_login_as(&generate_valid_email(), "clinician")
+        .await;
+    let body = serde_json::json!({ "reason": "Left the practice" });
+
+    let response = app.get_offboarding(&user_id, &admin_token).await;
+    assert_eq!(response.status(), 404);
+
+    let response = app
+        .post_offboard_user(&user_id, body.clone(), &clinician_token)
+        .await;
+    assert_eq!(response.status(), 403);
+
+    let response = app
+        .post_offboard_user(&admin_id, body.clone(), &admin_token)
+        .await;
+    assert_eq!(response.status(), 400);
+
+    let response = app
+        .post_offboard_user(
+            &user_id,
+            serde_json::json!({ "reason": "Left the practice", "reassign_to": user_id }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 400);
+
+    let response = app
+        .post_offboard_user(
+            &user_id,
+            serde_json::json!({ "reason": "", "reassign_to": admin_id }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 400);
+
+    // Nothing was started by the rejected requests
+    let response = app.get_offboarding(&user_id, &admin_token).await;
+    assert_eq!(response.status(), 404);
+
+    app.cleanup().await;
+}
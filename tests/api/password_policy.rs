@@ -0,0 +1,141 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+fn failed_rules(body: &serde_json::Value) -> Vec<&str> {
+    body["failed_rules"]
+        .as_array()
+        .expect("Missing failed_rules")
+        .iter()
+        .map(|rule| rule["rule"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn signup_should_list_every_failed_password_rule() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_signup(serde_json::json!({
+            "email": generate_valid_email(),
+            "first_name": "Test",
+            "last_name": "User",
+            "password": "abc"
+        }))
+        .await;
+    assert_eq!(response.status(), 400);
+
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "InvalidPassword");
+    assert_eq!(
+        failed_rules(&body),
+        ["min_length", "requires_digit", "requires_symbol"]
+    );
+    assert!(
+        body["message"]
+            .as_str()
+            .unwrap()
+            .contains("Must be at least 8 characters long")
+    );
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn signup_should_reject_personal_info_and_breached_passwords() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let test_cases = [("Jane2024!!", "personal_info"), ("P@ssw0rd!", "breached")];
+
+    for (password, rule) in test_cases {
+        let response = app
+            .post_signup(serde_json::json!({
+                "email": generate_valid_email(),
+                "first_name": "Jane",
+                "last_name": "Doe",
+                "password": password
+            }))
+            .await;
+        assert_eq!(response.status(), 400, "Failed for password: {password}");
+
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        assert_eq!(failed_rules(&body), [rule]);
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn change_password_should_reject_a_recent_password() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (_, token) = app.signup_and_login(&email).await;
+
+    let response = app
+        .post_change_password(
+            serde_json::json!({
+                "current_password": "Password123!",
+                "new_password": "NewPassword456!"
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    // The signup password is still in the history
+    let token = app.login(&email, "NewPassword456!").await;
+    let response = app
+        .post_change_password(
+            serde_json::json!({
+                "current_password": "NewPassword456!",
+                "new_password": "Password123!"
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 400);
+
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(failed_rules(&body), ["recently_used"]);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn password_policy_should_follow_settings() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new_with(|settings| {
+        settings.password_policy.min_length = 16;
+        settings.password_policy.history_size = 0;
+    })
+    .await;
+
+    let email = generate_valid_email();
+    let response = app
+        .post_signup(serde_json::json!({
+            "email": email,
+            "first_name": "Test",
+            "last_name": "User",
+            "password": "Password123!"
+        }))
+        .await;
+    assert_eq!(response.status(), 400);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(failed_rules(&body), ["min_length"]);
+
+    let response = app
+        .post_signup(serde_json::json!({
+            "email": email,
+            "first_name": "Test",
+            "last_name": "User",
+            "password": "LongerPassword123!"
+        }))
+        .await;
+    assert_eq!(response.status(), 201);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,150 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+#[tokio::test]
+async fn owners_should_create_and_list_practices() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let admin_token = app.admin_token().await;
+
+    let north = app.create_practice("North Clinic", &owner_token).await;
+    let south = app.create_practice("South Clinic", &owner_token).await;
+
+    let response = app.get_practices(&owner_token).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let ids: Vec<&str> = body["practices"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|practice| practice["practice_id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, [north.as_str(), south.as_str()]);
+
+    let duplicate = app
+        .post_practice(serde_json::json!({ "name": "North Clinic" }), &owner_token)
+        .await;
+    assert_eq!(duplicate.status(), 409);
+
+    let forbidden = app
+        .post_practice(serde_json::json!({ "name": "West Clinic" }), &admin_token)
+        .await;
+    assert_eq!(forbidden.status(), 403);
+    assert_eq!(app.get_practices(&admin_token).await.status(), 403);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn users_of_another_practice_should_be_unreachable() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let outsider_email = generate_valid_email();
+    let (outsider_id, _) = app.signup_and_login(&outsider_email).await;
+    let (admin_id, admin_token) = app
+        .signup_and_login_as(&generate_valid_email(), "admin")
+        .await;
+
+    let practice_id = app.create_practice("North Clinic", &owner_token).await;
+    let response = app
+        .put_user_practice(&admin_id, &practice_id, &owner_token)
+        .await;
+    assert_eq!(response.status(), 200);
+
+    // The outsider belongs to no practice, so the admin can no longer see them
+    assert_eq!(app.get_user(&outsider_id, &admin_token).await.status(), 404);
+    let response = app
+        .patch_user(
+            &outsider_id,
+            serde_json::json!({ "first_name": "Renamed" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 404);
+    let response = app
+        .post_get_user_id(serde_json::json!({ "email": outsider_email }), &admin_token)
+        .await;
+    assert_eq!(response.status(), 404);
+    let response = app
+        .post_disable_user(&outsider_id, "Left the practice", &admin_token)
+        .await;
+    assert_eq!(response.status(), 404);
+
+    // Staff the admin creates join the admin's practice
+    let response = app
+        .post_signup_as(
+            serde_json::json!({
+                "email": generate_valid_email(),
+                "first_name": "Test",
+                "last_name": "User",
+                "password": "Password123!",
+                "role": "clinician"
+            }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let colleague_id = body["user_id"].as_str().unwrap();
+
+    let response = app.get_user(colleague_id, &admin_token).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["practice_id"], practice_id.as_str());
+
+    // Their own account stays reachable to everyone
+    assert_eq!(app.get_user(&admin_id, &admin_token).await.status(), 200);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn moving_users_should_require_manage_practices() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let (user_id, _) = app.signup_and_login(&generate_valid_email()).await;
+    let admin_token = app.admin_token().await;
+    let practice_id = app.create_practice("North Clinic", &owner_token).await;
+
+    let response = app
+        .put_user_practice(&user_id, &practice_id, &admin_token)
+        .await;
+    assert_eq!(response.status(), 403);
+
+    let unknown = uuid::Uuid::new_v4().to_string();
+    let response = app
+        .put_user_practice(&user_id, &unknown, &owner_token)
+        .await;
+    assert_eq!(response.status(), 404);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "PracticeNotFound");
+
+    let response = app
+        .put_user_practice(&user_id, &practice_id, &owner_token)
+        .await;
+    assert_eq!(response.status(), 200);
+    let stored: Option<uuid::Uuid> =
+        sqlx::query_scalar("SELECT practice_id FROM users WHERE id = $1::uuid")
+            .bind(&user_id)
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(stored.map(|id| id.to_string()), Some(practice_id));
+
+    // Once moved out, the user is beyond the owner's reach as well
+    let other = app.create_practice("South Clinic", &owner_token).await;
+    let response = app.put_user_practice(&user_id, &other, &owner_token).await;
+    assert_eq!(response.status(), 404);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,109 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+#[tokio::test]
+async fn refresh_should_return_400_for_malformed_request() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let test_cases = [
+        serde_json::json!({}),
+        serde_json::json!({ "refresh_token": "" }),
+    ];
+
+    for test_case in test_cases {
+        let response = app.post_refresh(test_case.clone()).await;
+        assert_eq!(response.status(), 400, "Failed for input: {:?}", test_case);
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn refresh_should_return_401_for_unknown_token() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_refresh(serde_json::json!({ "refresh_token": "not-a-real-token" }))
+        .await;
+
+    assert_eq!(response.status(), 401);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn refresh_should_rotate_tokens() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+    let login = app.login_body(&email, "Password123!").await;
+
+    let response = app
+        .post_refresh(serde_json::json!({ "refresh_token": login["refresh_token"] }))
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_ne!(body["refresh_token"], login["refresh_token"]);
+
+    let response = app
+        .get_user(&user_id, body["access_token"].as_str().unwrap())
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let response = app
+        .post_refresh(serde_json::json!({ "refresh_token": body["refresh_token"] }))
+        .await;
+    assert_eq!(response.status(), 200);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn refresh_should_end_session_and_audit_on_reuse() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+    let login = app.login_body(&email, "Password123!").await;
+
+    let response = app
+        .post_refresh(serde_json::json!({ "refresh_token": login["refresh_token"] }))
+        .await;
+    assert_eq!(response.status(), 200);
+    let rotated: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+
+    // Replaying the rotated-out token is rejected...
+    let response = app
+        .post_refresh(serde_json::json!({ "refresh_token": login["refresh_token"] }))
+        .await;
+    assert_eq!(response.status(), 401);
+
+    // ...and takes the legitimate holder's tokens down with it
+    let response = app
+        .post_refresh(serde_json::json!({ "refresh_token": rotated["refresh_token"] }))
+        .await;
+    assert_eq!(response.status(), 401);
+
+    let response = app
+        .get_user(&user_id, rotated["access_token"].as_str().unwrap())
+        .await;
+    assert_eq!(response.status(), 401);
+
+    let (events,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM audit_logs WHERE action = 'refresh_token_reuse' AND user_id = $1::uuid",
+    )
+    .bind(&user_id)
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to query audit_logs");
+    assert!(events >= 1);
+
+    app.cleanup().await;
+}
@@ -84,10 +84,15 @@ async fn signup_should_return_201() {
 
     assert_eq!(response.status(), 201);
 
+    let token = app.admin_token().await;
+
     let response = app
-        .post_get_user_id(serde_json::json!({
-            "email": email,
-        }))
+        .post_get_user_id(
+            serde_json::json!({
+                "email": email,
+            }),
+            &token,
+        )
         .await;
 
     assert_eq!(response.status(), 200);
@@ -97,7 +102,8 @@ async fn signup_should_return_201() {
         "Response body does not contain user_id"
     );
 
-    let response = app.post_delete_user(body).await;
+    let owner_token = app.owner_token().await;
+    let response = app.post_delete_user(body, &owner_token).await;
 
     assert_eq!(response.status(), 200);
 
@@ -122,10 +128,15 @@ async fn signup_should_return_409_for_duplicate_email() {
 
     assert_eq!(response.status(), 201);
 
+    let token = app.admin_token().await;
+
     let response = app
-        .post_get_user_id(serde_json::json!({
-            "email": email,
-        }))
+        .post_get_user_id(
+            serde_json::json!({
+                "email": email,
+            }),
+            &token,
+        )
         .await;
 
     assert_eq!(response.status(), 200);
@@ -146,9 +157,195 @@ async fn signup_should_return_409_for_duplicate_email() {
 
     assert_eq!(response.status(), 409);
 
-    let response = app.post_delete_user(body).await;
+    let owner_token = app.owner_token().await;
+    let response = app.post_delete_user(body, &owner_token).await;
 
     assert_eq!(response.status(), 200);
 
     app.cleanup().await;
 }
+
+fn signup_body(email: &str, role: &str) -> serde_json::Value {
+    serde_json::json!({
+        "email": email,
+        "first_name": "Test",
+        "last_name": "User",
+        "password": "Password123!",
+        "role": role
+    })
+}
+
+#[tokio::test]
+async fn signup_should_assign_role_when_caller_manages_users() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let admin_token = app.admin_token().await;
+    let response = app
+        .post_signup_as(signup_body(&generate_valid_email(), "biller"), &admin_token)
+        .await;
+    assert_eq!(response.status(), 201);
+
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let user_id = body["user_id"].as_str().unwrap().to_string();
+
+    let response = app.get_user(&user_id, &admin_token).await;
+    assert_eq!(response.status(), 200);
+
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["role"], "biller");
+
+    let owner_token = app.owner_token().await;
+    app.post_delete_user(serde_json::json!({ "user_id": user_id }), &owner_token)
+        .await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn signup_should_reject_role_without_permission() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_signup(signup_body(&generate_valid_email(), "clinician"))
+        .await;
+    assert_eq!(response.status(), 401);
+
+    let response = app
+        .post_signup(signup_body(&generate_valid_email(), "superuser"))
+        .await;
+    assert_eq!(response.status(), 400);
+
+    let admin_token = app.admin_token().await;
+    let response = app
+        .post_signup_as(signup_body(&generate_valid_email(), "owner"), &admin_token)
+        .await;
+    assert_eq!(response.status(), 403);
+
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "CannotAssignRole");
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn signup_should_mirror_user_into_users_table() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let admin_token = app.admin_token().await;
+    let response = app
+        .post_signup_as(signup_body(&email, "clinician"), &admin_token)
+        .await;
+    assert_eq!(response.status(), 201);
+
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let user_id = body["user_id"].as_str().unwrap().to_string();
+
+    assert_eq!(
+        app.mirrored_user(&user_id).await,
+        Some((email, Some("clinician".to_string())))
+    );
+
+    let owner_token = app.owner_token().await;
+    app.post_delete_user(serde_json::json!({ "user_id": user_id }), &owner_token)
+        .await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn signup_should_remove_keycloak_user_when_mirroring_fails() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    // A stale row holding the email makes the mirror insert fail after Keycloak succeeded
+    let email = generate_valid_email();
+    sqlx::query("INSERT INTO users (id, email, email_display) VALUES ($1::uuid, $2, $2)")
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&email)
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to insert stale row");
+
+    let response = app
+        .post_signup(serde_json::json!({
+            "email": email,
+            "first_name": "Test",
+            "last_name": "User",
+            "password": "Password123!"
+        }))
+        .await;
+    assert_eq!(response.status(), 409);
+
+    let admin_token = app.admin_token().await;
+    let response = app
+        .post_get_user_id(serde_json::json!({ "email": email }), &admin_token)
+        .await;
+    assert_eq!(response.status(), 404);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn signup_should_treat_emails_by_canonical_form() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let local = uuid::Uuid::new_v4().simple().to_string();
+    let typed = format!("  {}@BÜCHER.Example ", local.to_uppercase());
+    let canonical = format!("{local}@xn--bcher-kva.example");
+
+    let response = app
+        .post_signup(serde_json::json!({
+            "email": typed,
+            "first_name": "Test",
+            "last_name": "User",
+            "password": "Password123!"
+        }))
+        .await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let user_id = body["user_id"].as_str().unwrap().to_string();
+    app.verify_email(&user_id).await;
+
+    let (email, display): (String, String) =
+        sqlx::query_as("SELECT email, email_display FROM users WHERE id = $1::uuid")
+            .bind(&user_id)
+            .fetch_one(&app.db_pool)
+            .await
+            .expect("Failed to query users table");
+    assert_eq!(email, canonical);
+    assert_eq!(display, typed.trim());
+
+    // Another spelling of the same address is the same account
+    let response = app
+        .post_signup(serde_json::json!({
+            "email": format!("{local}@bücher.example"),
+            "first_name": "Test",
+            "last_name": "User",
+            "password": "Password123!"
+        }))
+        .await;
+    assert_eq!(response.status(), 409);
+
+    let token = app.login(&canonical, "Password123!").await;
+    let admin_token = app.admin_token().await;
+    let response = app
+        .post_get_user_id(serde_json::json!({ "email": typed }), &admin_token)
+        .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["user_id"], user_id);
+
+    // The user sees the address as they typed it
+    let response = app.get_user(&user_id, &token).await;
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["email"], typed.trim());
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
@@ -0,0 +1,85 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+// Local SMTP stand-in that accepts every message and keeps it for assertions
+#[derive(Clone, Default)]
+pub struct SmtpStandIn {
+    messages: Arc<Mutex<Vec<String>>>,
+}
+
+impl SmtpStandIn {
+    // Starts listening on a random port and returns the port
+    pub async fn start() -> (Self, u16) {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind SMTP stand-in");
+        let port = listener.local_addr().unwrap().port();
+
+        let stand_in = Self::default();
+        let messages = stand_in.messages.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(handle_session(socket, messages.clone()));
+            }
+        });
+
+        (stand_in, port)
+    }
+
+    // Raw headers and body of every message received so far
+    pub fn messages(&self) -> Vec<String> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    pub fn messages_to(&self, email: &str) -> Vec<String> {
+        self.messages()
+            .into_iter()
+            .filter(|message| message.contains(&format!("To: {email}")))
+            .collect()
+    }
+}
+
+async fn handle_session(socket: TcpStream, messages: Arc<Mutex<Vec<String>>>) {
+    let (reader, mut writer) = socket.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    if writer.write_all(b"220 localhost ESMTP\r\n").await.is_err() {
+        return;
+    }
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let command = line.to_ascii_uppercase();
+        let reply: &[u8] = if command.starts_with("DATA") {
+            if writer
+                .write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n")
+                .await
+                .is_err()
+            {
+                return;
+            }
+            let mut data = String::new();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line == "." {
+                    break;
+                }
+                data.push_str(&line);
+                data.push('\n');
+            }
+            messages.lock().unwrap().push(data);
+            b"250 OK\r\n"
+        } else if command.starts_with("QUIT") {
+            let _ = writer.write_all(b"221 Bye\r\n").await;
+            return;
+        } else {
+            b"250 OK\r\n"
+        };
+
+        if writer.write_all(reply).await.is_err() {
+            return;
+        }
+    }
+}
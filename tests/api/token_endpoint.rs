@@ -0,0 +1,115 @@
+use std::sync::{Arc, Mutex};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use poem::{
+    EndpointExt, Route, Server, handler,
+    http::StatusCode,
+    listener::TcpListener,
+    post,
+    web::{Data, Form, Json},
+};
+use sha2::{Digest, Sha256};
+
+pub const VALID_CODE: &str = "valid-code";
+
+#[derive(Default)]
+struct Expected {
+    code_challenge: Option<String>,
+    nonce: Option<String>,
+    // Subject and email to sign in as; a fresh subject without email otherwise
+    user: Option<(String, String)>,
+}
+
+// Stand-in for Keycloak's token endpoint. It only redeems VALID_CODE, and only
+// with the verifier matching the challenge of the sign-in it was told to expect.
+#[derive(Clone, Default)]
+pub struct TokenEndpointStub {
+    expected: Arc<Mutex<Expected>>,
+}
+
+impl TokenEndpointStub {
+    // Serves the stub on a random port and returns its base URL
+    pub async fn start() -> (Self, String) {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let stub = Self::default();
+        let app = Route::new()
+            .at("/realms/ehr/protocol/openid-connect/token", post(token))
+            .data(stub.clone());
+        tokio::spawn(async move {
+            Server::new(TcpListener::bind(format!("127.0.0.1:{port}")))
+                .run(app)
+                .await
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        (stub, format!("http://127.0.0.1:{port}"))
+    }
+
+    // The challenge and nonce the browser was sent to the provider with
+    pub fn expect(&self, code_challenge: &str, nonce: &str) {
+        let mut expected = self.expected.lock().unwrap();
+        expected.code_challenge = Some(code_challenge.to_string());
+        expected.nonce = Some(nonce.to_string());
+    }
+
+    pub fn sign_in_as(&self, sub: &str, email: &str) {
+        self.expected.lock().unwrap().user = Some((sub.to_string(), email.to_string()));
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct TokenRequest {
+    grant_type: String,
+    code: String,
+    code_verifier: String,
+}
+
+fn encode_token(payload: serde_json::Value) -> String {
+    format!(
+        "{}.{}.signature",
+        URL_SAFE_NO_PAD.encode(br#"{"alg":"RS256","typ":"JWT"}"#),
+        URL_SAFE_NO_PAD.encode(payload.to_string())
+    )
+}
+
+#[handler]
+fn token(
+    Form(request): Form<TokenRequest>,
+    Data(stub): Data<&TokenEndpointStub>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let expected = stub.expected.lock().unwrap();
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(request.code_verifier.as_bytes()));
+
+    if request.grant_type != "authorization_code"
+        || request.code != VALID_CODE
+        || expected.code_challenge.as_deref() != Some(challenge.as_str())
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "invalid_grant" })),
+        );
+    }
+
+    let (sub, email) = match &expected.user {
+        Some((sub, email)) => (sub.clone(), Some(email.clone())),
+        None => (uuid::Uuid::new_v4().to_string(), None),
+    };
+    let access_token = serde_json::json!({ "sub": sub, "email": email, "email_verified": true });
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "access_token": encode_token(access_token),
+            "refresh_token": "refresh-token",
+            "id_token": encode_token(serde_json::json!({ "sub": sub, "nonce": expected.nonce })),
+            "token_type": "Bearer",
+            "expires_in": 300,
+            "refresh_expires_in": 1800,
+            "session_state": "provider-session",
+        })),
+    )
+}
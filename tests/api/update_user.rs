@@ -0,0 +1,377 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+#[tokio::test]
+async fn update_user_should_update_own_profile() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login(&email).await;
+
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "first_name": "Updated" }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let body = app.login_body(&email, "Password123!").await;
+    assert_eq!(body["user"]["first_name"], "Updated");
+    assert_eq!(body["user"]["last_name"], "User");
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn update_user_should_guard_own_password_and_email() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login(&email).await;
+
+    // Own passwords change only through /auth/password/change, which asks for the old one
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "password": "NewPassword456!" }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 400);
+    app.login(&email, "Password123!").await;
+
+    let new_email = generate_valid_email();
+    for body in [
+        serde_json::json!({ "email": new_email }),
+        serde_json::json!({ "email": new_email, "current_password": "WrongPassword1!" }),
+    ] {
+        let response = app.patch_user(&user_id, body, &token).await;
+        assert_eq!(response.status(), 400);
+    }
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "email": new_email, "current_password": "Password123!" }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    // Managers may still set a password for someone else
+    let admin_token = app.admin_token().await;
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "password": "NewPassword456!" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    app.verify_email(&user_id).await;
+    app.login(&new_email, "NewPassword456!").await;
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn update_user_should_return_400_for_invalid_fields() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let (user_id, token) = app.signup_and_login(&generate_valid_email()).await;
+
+    let test_cases = [
+        (
+            serde_json::json!({ "email": "not-an-email" }),
+            "InvalidEmail",
+        ),
+        (
+            serde_json::json!({ "password": "NewPassword456!" }),
+            "InvalidInput",
+        ),
+        (serde_json::json!({ "last_name": " " }), "InvalidInput"),
+        (serde_json::json!({}), "InvalidInput"),
+    ];
+
+    for (body, code) in test_cases {
+        let response = app.patch_user(&user_id, body.clone(), &token).await;
+        assert_eq!(response.status(), 400, "Failed for input: {:?}", body);
+
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["code"], code);
+    }
+
+    let admin_token = app.admin_token().await;
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "password": "weak" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 400);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "InvalidPassword");
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn update_user_should_return_409_for_taken_email() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let taken_email = generate_valid_email();
+    let (other_id, _) = app.signup_and_login(&taken_email).await;
+    let (user_id, token) = app.signup_and_login(&generate_valid_email()).await;
+
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "email": taken_email, "current_password": "Password123!" }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 409);
+
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "UserExists");
+
+    for id in [user_id, other_id] {
+        app.remove_user(&id).await;
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn update_user_should_return_403_for_other_users_without_permission() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let (user_id, _) = app.signup_and_login(&generate_valid_email()).await;
+    let (clinician_id, token) = app
+        .signup_and_login_as(&generate_valid_email(), "clinician")
+        .await;
+
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "first_name": "Nope" }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 403);
+
+    let admin_token = app.admin_token().await;
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "first_name": "Allowed" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    for id in [user_id, clinician_id] {
+        app.remove_user(&id).await;
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn update_user_should_return_401_without_token() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .patch_user(
+            &uuid::Uuid::new_v4().to_string(),
+            serde_json::json!({ "first_name": "Test" }),
+            "",
+        )
+        .await;
+
+    assert_eq!(response.status(), 401);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn update_user_should_replace_role() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let (user_id, token) = app
+        .signup_and_login_as(&generate_valid_email(), "clinician")
+        .await;
+
+    // Users cannot change their own role without permission to manage users
+    let response = app
+        .patch_user(&user_id, serde_json::json!({ "role": "admin" }), &token)
+        .await;
+    assert_eq!(response.status(), 403);
+
+    let admin_token = app.admin_token().await;
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "role": "biller" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let body: serde_json::Value = app
+        .get_user(&user_id, &admin_token)
+        .await
+        .json()
+        .await
+        .expect("Failed to parse JSON");
+    assert_eq!(body["role"], "biller");
+
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "role": "owner" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 403);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn update_user_should_return_403_for_admin_changing_owner() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let (owner_id, _) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let admin_token = app.admin_token().await;
+
+    let test_cases = vec![
+        serde_json::json!({ "password": "Takeover123!" }),
+        serde_json::json!({ "email": generate_valid_email() }),
+        serde_json::json!({ "email_verified": false }),
+        serde_json::json!({ "first_name": "Nope" }),
+    ];
+    for (i, test_case) in test_cases.into_iter().enumerate() {
+        let response = app.patch_user(&owner_id, test_case, &admin_token).await;
+        assert_eq!(response.status(), 403, "Test case {} failed", i);
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["code"], "CannotAssignRole", "Test case {} failed", i);
+    }
+
+    let owner_token = app.owner_token().await;
+    let response = app
+        .patch_user(
+            &owner_id,
+            serde_json::json!({ "password": "Handover123!" }),
+            &owner_token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    app.remove_user(&owner_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn update_user_should_update_mirrored_row() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let (user_id, _) = app.signup_and_login(&generate_valid_email()).await;
+
+    let new_email = generate_valid_email();
+    let admin_token = app.admin_token().await;
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "email": new_email, "role": "biller" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    assert_eq!(
+        app.mirrored_user(&user_id).await,
+        Some((new_email, Some("biller".to_string())))
+    );
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn update_user_should_revert_provider_when_commit_fails() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let (user_id, _) = app.signup_and_login(&generate_valid_email()).await;
+    let admin_token = app.admin_token().await;
+
+    // A deferred check only fails at commit, after the provider was already updated
+    sqlx::query(
+        r#"
+        CREATE FUNCTION reject_user_updates() RETURNS TRIGGER AS $$
+        BEGIN
+            RAISE EXCEPTION 'users are read-only';
+        END;
+        $$ LANGUAGE plpgsql
+        "#,
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"
+        CREATE CONSTRAINT TRIGGER users_read_only AFTER UPDATE ON users
+        DEFERRABLE INITIALLY DEFERRED
+        FOR EACH ROW EXECUTE FUNCTION reject_user_updates()
+        "#,
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "first_name": "Renamed", "role": "biller" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 500);
+
+    // The role the user never had is taken away again, as is the new name
+    let response = app.get_user(&user_id, &admin_token).await;
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["role"], serde_json::Value::Null);
+    assert_eq!(body["first_name"], "Test");
+
+    sqlx::query("DROP TRIGGER users_read_only ON users")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
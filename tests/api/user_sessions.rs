@@ -0,0 +1,157 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+async fn session_ids(app: &TestApp, user_id: &str, token: &str) -> Vec<String> {
+    let response = app.get_user_sessions(user_id, token).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    body["sessions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|session| session["session_id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn audited(app: &TestApp, action: &str) -> Vec<String> {
+    sqlx::query_scalar("SELECT resource_id FROM audit_logs WHERE action = $1")
+        .bind(action)
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn session_endpoints_should_be_owner_only() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (user_id, user_token) = app.signup_and_login(&generate_valid_email()).await;
+    let admin_token = app.admin_token().await;
+
+    for token in [&user_token, &admin_token] {
+        assert_eq!(app.get_user_sessions(&user_id, token).await.status(), 403);
+        assert_eq!(
+            app.delete_user_sessions(&user_id, token).await.status(),
+            403
+        );
+    }
+    assert_eq!(app.get_user_sessions(&user_id, "").await.status(), 401);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn list_sessions_should_return_each_active_session() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+    app.login(&email, "Password123!").await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+
+    let response = app.get_user_sessions(&user_id, &owner_token).await;
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let sessions = body["sessions"].as_array().unwrap();
+    assert_eq!(sessions.len(), 2);
+    for session in sessions {
+        assert!(session["session_id"].is_string());
+        assert!(session["started_at"].as_u64().unwrap() > 0);
+        assert!(
+            session["last_access_at"].as_u64().unwrap() >= session["started_at"].as_u64().unwrap()
+        );
+    }
+
+    assert_eq!(
+        app.get_user_sessions("00000000-0000-0000-0000-000000000000", &owner_token)
+            .await
+            .status(),
+        404
+    );
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn revoke_session_should_end_only_that_session_and_audit() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let email = generate_valid_email();
+    let (user_id, lost_device_token) = app.signup_and_login(&email).await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let lost_device_session = session_ids(&app, &user_id, &owner_token).await.remove(0);
+    let other_token = app.login(&email, "Password123!").await;
+
+    let response = app
+        .delete_user_session(&user_id, &lost_device_session, &owner_token)
+        .await;
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        app.get_user(&user_id, &lost_device_token).await.status(),
+        401
+    );
+    assert_eq!(app.get_user(&user_id, &other_token).await.status(), 200);
+    assert_eq!(
+        audited(&app, "session_revoked").await,
+        vec![format!("{user_id}/{lost_device_session}")]
+    );
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn revoke_session_should_return_404_for_another_users_session() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let (user_id, _) = app.signup_and_login(&generate_valid_email()).await;
+    let (other_id, other_token) = app.signup_and_login(&generate_valid_email()).await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+    let other_session = session_ids(&app, &other_id, &owner_token).await.remove(0);
+
+    let response = app
+        .delete_user_session(&user_id, &other_session, &owner_token)
+        .await;
+
+    assert_eq!(response.status(), 404);
+    assert_eq!(app.get_user(&other_id, &other_token).await.status(), 200);
+    assert!(audited(&app, "session_revoked").await.is_empty());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn revoke_all_sessions_should_sign_user_out_everywhere_and_audit() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let email = generate_valid_email();
+    let (user_id, first_token) = app.signup_and_login(&email).await;
+    let second_token = app.login(&email, "Password123!").await;
+    let session = app.login_session(&email, "Password123!").await;
+    let (_, owner_token) = app
+        .signup_and_login_as(&generate_valid_email(), "owner")
+        .await;
+
+    let response = app.delete_user_sessions(&user_id, &owner_token).await;
+
+    assert_eq!(response.status(), 200);
+    for token in [&first_token, &second_token] {
+        assert_eq!(app.get_user(&user_id, token).await.status(), 401);
+    }
+    assert_eq!(
+        app.get_user_in_session(&user_id, &session).await.status(),
+        401
+    );
+    assert!(session_ids(&app, &user_id, &owner_token).await.is_empty());
+    assert_eq!(audited(&app, "all_sessions_revoked").await, vec![user_id]);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,200 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+fn signup_body(email: &str) -> serde_json::Value {
+    serde_json::json!({
+        "email": email,
+        "first_name": "Test",
+        "last_name": "User",
+        "password": "Password123!"
+    })
+}
+
+fn login_body(email: &str) -> serde_json::Value {
+    serde_json::json!({ "email": email, "password": "Password123!" })
+}
+
+#[tokio::test]
+async fn login_should_return_403_until_email_is_verified() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let response = app.post_signup(signup_body(&email)).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let user_id = body["user_id"].as_str().unwrap().to_string();
+
+    let messages = app.smtp.messages_to(&email);
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].contains("Subject: Verify your email"));
+
+    let response = app.post_login(login_body(&email)).await;
+    assert_eq!(response.status(), 403);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "EmailNotVerified");
+
+    app.verify_email(&user_id).await;
+    let response = app.post_login(login_body(&email)).await;
+    assert_eq!(response.status(), 200);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn resend_verification_should_return_202_and_only_email_unverified_users() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let response = app.post_signup(signup_body(&email)).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let user_id = body["user_id"].as_str().unwrap().to_string();
+
+    let response = app
+        .post_resend_verification(serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status(), 202);
+    assert_eq!(app.smtp.messages_to(&email).len(), 2);
+
+    // Unknown and already verified addresses get the same answer and no email
+    let response = app
+        .post_resend_verification(serde_json::json!({ "email": generate_valid_email() }))
+        .await;
+    assert_eq!(response.status(), 202);
+
+    app.verify_email(&user_id).await;
+    let response = app
+        .post_resend_verification(serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status(), 202);
+    assert_eq!(app.smtp.messages().len(), 2);
+
+    let response = app
+        .post_resend_verification(serde_json::json!({ "email": "not-an-email" }))
+        .await;
+    assert_eq!(response.status(), 400);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn signup_should_allow_admins_to_create_verified_staff() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let mut body = signup_body(&email);
+    body["email_verified"] = true.into();
+
+    let response = app.post_signup(body.clone()).await;
+    assert_eq!(response.status(), 401);
+
+    let (_, token) = app.signup_and_login(&generate_valid_email()).await;
+    let response = app.post_signup_as(body.clone(), &token).await;
+    assert_eq!(response.status(), 403);
+
+    let admin_token = app.admin_token().await;
+    let response = app.post_signup_as(body, &admin_token).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let user_id = body["user_id"].as_str().unwrap().to_string();
+
+    assert!(app.smtp.messages_to(&email).is_empty());
+    let response = app.post_login(login_body(&email)).await;
+    assert_eq!(response.status(), 200);
+
+    app.remove_user(&user_id).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn update_user_should_let_only_admins_mark_email_verified() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let email = generate_valid_email();
+    let response = app.post_signup(signup_body(&email)).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    let user_id = body["user_id"].as_str().unwrap().to_string();
+
+    let (other_id, token) = app.signup_and_login(&generate_valid_email()).await;
+    let response = app
+        .patch_user(
+            &other_id,
+            serde_json::json!({ "email_verified": false }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 403);
+
+    let admin_token = app.admin_token().await;
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "email_verified": true }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let response = app.get_user(&user_id, &admin_token).await;
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["email_verified"], true);
+
+    let response = app.post_login(login_body(&email)).await;
+    assert_eq!(response.status(), 200);
+
+    for id in [user_id, other_id] {
+        app.remove_user(&id).await;
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn update_user_should_require_verifying_a_changed_email() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+
+    let (user_id, token) = app.signup_and_login(&generate_valid_email()).await;
+    let new_email = generate_valid_email();
+
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "email": new_email, "current_password": "Password123!" }),
+            &token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    assert_eq!(app.smtp.messages_to(&new_email).len(), 1);
+    let response = app.post_login(login_body(&new_email)).await;
+    assert_eq!(response.status(), 403);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["code"], "EmailNotVerified");
+
+    // A manager vouching for the address leaves it verified
+    let admin_token = app.admin_token().await;
+    let other_email = generate_valid_email();
+    let response = app
+        .patch_user(
+            &user_id,
+            serde_json::json!({ "email": other_email, "email_verified": true }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    app.login(&other_email, "Password123!").await;
+
+    app.cleanup().await;
+}
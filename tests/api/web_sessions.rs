@@ -0,0 +1,177 @@
+use lgr_ehr::utils::tracing::init_tracing_for_tests;
+
+use crate::helpers::{TestApp, generate_valid_email};
+
+#[tokio::test]
+async fn login_with_session_should_set_cookies_instead_of_returning_tokens() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let email = generate_valid_email();
+    app.signup_and_login(&email).await;
+
+    let response = app
+        .post_login(serde_json::json!({
+            "email": email,
+            "password": "Password123!",
+            "session": true
+        }))
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let cookies: Vec<String> = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|value| value.to_str().unwrap().to_string())
+        .collect();
+    let session = cookies
+        .iter()
+        .find(|cookie| cookie.starts_with("ehr_session="))
+        .expect("No session cookie set");
+    assert!(session.contains("HttpOnly"));
+    assert!(session.contains("Secure"));
+    let csrf = cookies
+        .iter()
+        .find(|cookie| cookie.starts_with("ehr_csrf="))
+        .expect("No CSRF cookie set");
+    assert!(!csrf.contains("HttpOnly"));
+
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert!(body["access_token"].is_null());
+    assert!(body["refresh_token"].is_null());
+    assert!(csrf.contains(body["csrf_token"].as_str().unwrap()));
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn session_cookie_should_authenticate_requests() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+    let session = app.login_session(&email, "Password123!").await;
+
+    let response = app.get_user_in_session(&user_id, &session).await;
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["user_id"], user_id.as_str());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn state_changing_request_should_require_csrf_token() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+    let session = app.login_session(&email, "Password123!").await;
+    let update = serde_json::json!({ "first_name": "Renamed" });
+
+    for csrf_token in [None, Some("not-the-token")] {
+        let response = app
+            .patch_user_in_session(&user_id, update.clone(), &session, csrf_token)
+            .await;
+        assert_eq!(response.status(), 403, "Failed for token: {csrf_token:?}");
+        let body: serde_json::Value = response.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["code"], "InvalidCsrfToken");
+    }
+
+    let response = app
+        .patch_user_in_session(&user_id, update, &session, Some(&session.csrf_token))
+        .await;
+    assert_eq!(response.status(), 200);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn session_should_refresh_expired_access_token() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+    let session = app.login_session(&email, "Password123!").await;
+
+    // Stands in for an access token that has run out
+    sqlx::query("UPDATE web_sessions SET access_token = 'expired'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let response = app.get_user_in_session(&user_id, &session).await;
+    assert_eq!(response.status(), 200);
+
+    let (access_token,): (String,) = sqlx::query_as("SELECT access_token FROM web_sessions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_ne!(access_token, "expired");
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn session_should_end_after_idle_timeout() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new_with(|settings| settings.session.idle_timeout_secs = 1).await;
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+    let session = app.login_session(&email, "Password123!").await;
+
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    let response = app.get_user_in_session(&user_id, &session).await;
+
+    assert_eq!(response.status(), 401);
+    let (sessions,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM web_sessions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(sessions, 0);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn session_should_end_after_absolute_timeout_despite_activity() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new_with(|settings| settings.session.absolute_timeout_secs = 2).await;
+    let email = generate_valid_email();
+    let (user_id, _) = app.signup_and_login(&email).await;
+    let session = app.login_session(&email, "Password123!").await;
+
+    for _ in 0..2 {
+        let response = app.get_user_in_session(&user_id, &session).await;
+        assert_eq!(response.status(), 200);
+        tokio::time::sleep(std::time::Duration::from_millis(800)).await;
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(800)).await;
+
+    let response = app.get_user_in_session(&user_id, &session).await;
+    assert_eq!(response.status(), 401);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+async fn logout_session_should_end_only_that_session() {
+    init_tracing_for_tests();
+    let mut app = TestApp::new().await;
+    let email = generate_valid_email();
+    let (user_id, token) = app.signup_and_login(&email).await;
+    let session = app.login_session(&email, "Password123!").await;
+    let other_device = app.login_session(&email, "Password123!").await;
+
+    let response = app.delete_session(&session).await;
+    assert_eq!(response.status(), 200);
+
+    let response = app.get_user_in_session(&user_id, &session).await;
+    assert_eq!(response.status(), 401);
+    let response = app.get_user_in_session(&user_id, &other_device).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(app.get_user(&user_id, &token).await.status(), 200);
+
+    app.cleanup().await;
+}